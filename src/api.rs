@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use crate::checker::{check_entries, check_entry, finish_diagnostics};
+use crate::diagnostics::display_path;
+use crate::parsing::span::Span;
+use crate::project::{Project, ProjectError};
+use crate::r#static::module_loader::{Module, ModuleLoader};
+use crate::r#static::xs_error::XSError;
+
+pub use crate::r#static::prelude::PreludeVersion;
+pub use crate::r#static::xs_error::{Code, Severity};
+
+/// The path [check_source] gives to the script it checks
+pub const SOURCE_PATH: &str = "<source>";
+
+/// What a warning is reported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Off,
+    Warning,
+    Error,
+}
+
+/// How scripts are checked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// directories to resolve `include` paths against, after the directory of the including file
+    pub search_roots: Vec<PathBuf>,
+    /// the builtin functions and constants scripts are checked against
+    pub prelude: PreludeVersion,
+    /// overrides how each warning is reported. Errors always stop a script from compiling, so
+    /// they cannot be turned off or into warnings
+    pub lints: HashMap<Code, LintLevel>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { search_roots: vec![], prelude: PreludeVersion::LATEST, lints: HashMap::new() }
+    }
+}
+
+/// 1-based, columns are counted in chars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub file: PathBuf,
+    /// byte offsets into the file
+    pub span: Range<usize>,
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Location {
+    fn new(module: &Module, span: &Span) -> Self {
+        let (start_line, start_col) = module.line_col(span.start);
+        let (end_line, end_col) = module.line_col(span.end);
+
+        Location {
+            file: module.path.clone(),
+            span: span.into_range(),
+            start: Position { line: start_line, column: start_col },
+            end: Position { line: end_line, column: end_col },
+        }
+    }
+}
+
+/// An error or warning found in a script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub code: Code,
+    pub severity: Severity,
+    pub message: String,
+    pub location: Location,
+    /// secondary locations with a label explaining how they relate to [Diagnostic::location]
+    pub related: Vec<(String, Location)>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    fn new(loader: &ModuleLoader, err: &XSError) -> Self {
+        let module = loader.module(err.file_id);
+        Diagnostic {
+            code: err.code,
+            severity: err.severity,
+            message: err.msg.clone(),
+            location: Location::new(module, &err.span),
+            related: err.related.iter()
+                .map(|(label, span)| (label.clone(), Location::new(module, span)))
+                .collect(),
+            notes: err.notes.clone(),
+        }
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.severity == Severity::Warning
+    }
+}
+
+/// e.g. `main.xs:3:5: error[XS0003]: Undefined name `x``
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f, "{}:{}:{}: {}[{}]: {}",
+            display_path(&self.location.file), self.location.start.line, self.location.start.column,
+            self.severity, self.code, self.message,
+        )
+    }
+}
+
+/// The outcome of checking a script and the files it includes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// in the order of the files they were found in, and then by where they start
+    pub diagnostics: Vec<Diagnostic>,
+    /// every file that was checked, the checked script first
+    pub files: Vec<PathBuf>,
+}
+
+impl Report {
+    pub fn num_errors(&self) -> usize {
+        self.diagnostics.iter().filter(|diag| !diag.is_warning()).count()
+    }
+
+    pub fn num_warnings(&self) -> usize {
+        self.diagnostics.len() - self.num_errors()
+    }
+
+    /// Whether the script would fail to compile in game
+    pub fn has_errors(&self) -> bool {
+        self.num_errors() > 0
+    }
+}
+
+/// Checks the script `src` and the files it includes. The script is named [SOURCE_PATH], so its
+/// includes are resolved against the working directory and the search roots
+pub fn check_source(src: &str, options: &Options) -> Report {
+    let path = Path::new(SOURCE_PATH);
+    let mut loader = ModuleLoader::new(options.search_roots.clone());
+    loader.set_source(path, src.to_string());
+
+    check(&mut loader, path, options).expect("the source of the script is always available")
+}
+
+/// Checks the script at `path` and the files it includes. An error is only returned if the
+/// script itself cannot be read, problems with included files are reported as diagnostics
+pub fn check_file(path: &Path, options: &Options) -> io::Result<Report> {
+    let mut loader = ModuleLoader::new(options.search_roots.clone());
+    check(&mut loader, path, options)
+}
+
+/// Checks every entry of a project. Files included by several entries are only read and parsed
+/// once. An error is only returned if an entry cannot be read
+pub fn check_project(project: &Project) -> Result<Report, ProjectError> {
+    let mut loader = ModuleLoader::new(project.options.search_roots.clone());
+    let mut io_err = None;
+    let errs = check_entries(&mut loader, project.entries.iter(), &project.options, |entry, err| {
+        io_err.get_or_insert_with(|| ProjectError {
+            path: project.path.clone(),
+            msg: format!("cannot read entry `{}`: {}", display_path(entry), err),
+        });
+    });
+    if let Some(err) = io_err {
+        return Err(err);
+    }
+
+    Ok(report(&loader, &errs))
+}
+
+fn check(loader: &mut ModuleLoader, path: &Path, options: &Options) -> io::Result<Report> {
+    let mut errs = vec![];
+    check_entry(loader, path, options, &mut errs)?;
+    finish_diagnostics(&mut errs, options);
+
+    Ok(report(loader, &errs))
+}
+
+fn report(loader: &ModuleLoader, errs: &[XSError]) -> Report {
+    Report {
+        diagnostics: errs.iter().map(|err| Diagnostic::new(loader, err)).collect(),
+        files: loader.modules().map(|module| module.path.clone()).collect(),
+    }
+}
@@ -0,0 +1,10 @@
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    if let Err(err) = xs_check::internal::lsp::run() {
+        eprintln!("error: {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,59 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::api::{LintLevel, Options};
+use crate::r#static::module_loader::{xs_tc_module, ModuleLoader};
+use crate::r#static::prelude::prelude;
+use crate::r#static::type_check::Groups;
+use crate::r#static::xs_error::{Severity, XSError};
+
+/// Type checks the entry script at `path` and the files it includes, adding their errors to `errs`.
+/// Files shared by several entries checked with the same `loader` are only read and parsed once
+pub fn check_entry(
+    loader: &mut ModuleLoader,
+    path: &Path,
+    options: &Options,
+    errs: &mut Vec<XSError>,
+) -> io::Result<()> {
+    let mut type_env = prelude(options.prelude);
+    xs_tc_module(path, loader, &mut type_env, &mut Groups::new(), errs)
+}
+
+/// Checks each of `entries` with [check_entry], sharing one `loader`, and then applies
+/// [finish_diagnostics]. An entry which cannot be read is passed to `on_io_err` and skipped
+pub fn check_entries<'a>(
+    loader: &mut ModuleLoader,
+    entries: impl IntoIterator<Item = &'a PathBuf>,
+    options: &Options,
+    mut on_io_err: impl FnMut(&Path, io::Error),
+) -> Vec<XSError> {
+    let mut errs = vec![];
+    for entry in entries {
+        if let Err(err) = check_entry(loader, entry, options, &mut errs) {
+            on_io_err(entry, err);
+        }
+    }
+    finish_diagnostics(&mut errs, options);
+
+    errs
+}
+
+/// Applies [Options::lints] to the errors found by [check_entry] and sorts them by file and
+/// position. A file included by more than one entry is checked once per entry, so the errors it
+/// has regardless of the entry are only kept once
+pub fn finish_diagnostics(errs: &mut Vec<XSError>, options: &Options) {
+    let mut seen = HashSet::new();
+    errs.retain(|err| seen.insert((err.file_id, err.span, err.code, err.msg.clone())));
+
+    errs.retain_mut(|err| {
+        let level = options.lints.get(&err.code).filter(|_| err.code.severity() == Severity::Warning);
+        match level {
+            Some(LintLevel::Off) => return false,
+            Some(LintLevel::Warning) => err.severity = Severity::Warning,
+            Some(LintLevel::Error) => err.severity = Severity::Error,
+            None => {}
+        }
+        true
+    });
+    errs.sort_by_key(|err| (err.file_id, err.span.start));
+}
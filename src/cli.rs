@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+use clap::{Parser, Subcommand, ValueEnum};
+use xs_check::internal::formatter::BraceStyle;
+
+/// The longest `--simulate`, the game clock is an `int` of milliseconds
+const MAX_SIMULATED_SECS: i64 = i32::MAX as i64 / 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// source snippets with the offending spans underlined
+    Human,
+    /// a JSON array of diagnostics
+    Json,
+    /// a SARIF 2.1.0 log
+    Sarif,
+    /// GitHub Actions workflow commands
+    Github,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Emit {
+    /// the control flow graph of each function and rule, in the Graphviz DOT language
+    Cfg,
+    /// the Pseudo Assembly the whole script is lowered to, with its includes inlined
+    Pa,
+    /// the disassembled bytecode the Pseudo Assembly is compiled to
+    Bc,
+}
+
+/// A linter for AoE2:DE's flavour of XS
+#[derive(Debug, Parser)]
+#[command(version, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub check: CheckArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Format files in place
+    Fmt(FmtArgs),
+    /// Run a script outside of the game. `xsChatData` messages are written to stdout
+    Run(RunArgs),
+}
+
+#[derive(Debug, clap::Args)]
+pub struct CheckArgs {
+    /// Files to check. Directories are searched recursively for `.xs` files. When no files are
+    /// given, the project in the working directory is checked
+    pub paths: Vec<PathBuf>,
+
+    /// Check the entries of this project file instead of the given files
+    #[arg(short, long, value_name = "FILE", conflicts_with = "paths")]
+    pub project: Option<PathBuf>,
+
+    /// Additional directory to resolve `include` paths against. May be given multiple times
+    #[arg(short = 'I', long = "include-dir", value_name = "DIR")]
+    pub search_roots: Vec<PathBuf>,
+
+    /// How diagnostics are written. `human` is written to stderr, all other formats to stdout
+    #[arg(short, long, value_enum, default_value_t = Format::Human)]
+    pub format: Format,
+
+    /// Exit with a non-zero status when warnings are found
+    #[arg(short = 'W', long)]
+    pub deny_warnings: bool,
+
+    /// Only report errors and do not print a summary
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Print every file as it is checked
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Write an intermediate form of each file to stdout instead of checking it
+    #[arg(long, value_enum, value_name = "FORM")]
+    pub emit: Option<Emit>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct FmtArgs {
+    /// Files to format. Directories are searched recursively for `.xs` files
+    #[arg(required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// List the files that are not formatted instead of rewriting them, and exit with a non-zero
+    /// status if there are any
+    #[arg(long)]
+    pub check: bool,
+
+    /// Number of spaces per indentation level
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    pub indent_width: usize,
+
+    /// Indent with tabs instead of spaces
+    #[arg(long)]
+    pub tabs: bool,
+
+    /// Where the opening brace of a block goes
+    #[arg(long, value_enum, default_value_t = BraceStyle::SameLine)]
+    pub brace_style: BraceStyle,
+
+    /// Write `if(x)` instead of `if (x)`, likewise for `while`, `for` and `switch`
+    #[arg(long)]
+    pub no_space_after_keyword: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct RunArgs {
+    /// The script to run
+    pub path: PathBuf,
+
+    /// Additional directory to resolve `include` paths against. May be given multiple times
+    #[arg(short = 'I', long = "include-dir", value_name = "DIR")]
+    pub search_roots: Vec<PathBuf>,
+
+    /// The function or rule to run after the top level of the script has been run
+    #[arg(short, long, value_name = "NAME", default_value = "main")]
+    pub entry: String,
+
+    /// After the entry has run, run the active rules on a simulated game clock for this many
+    /// seconds, writing a line for each rule as it runs
+    #[arg(
+        short, long, value_name = "SECONDS",
+        value_parser = clap::value_parser!(i32).range(0..=MAX_SIMULATED_SECS),
+    )]
+    pub simulate: Option<i32>,
+
+    /// The length of a simulated game tick in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 50, value_parser = clap::value_parser!(i32).range(1..))]
+    pub tick: i32,
+
+    /// The number of rules that may run in one tick. Rules past their `maxInterval` run first
+    #[arg(long, value_name = "N")]
+    pub rules_per_tick: Option<usize>,
+
+    /// Compile the script to bytecode and run it on the VM instead of interpreting it
+    #[arg(long)]
+    pub vm: bool,
+
+    /// Stop the script with an error when a single call runs more than this many statements, or
+    /// instructions on the VM, as a script which never finishes would otherwise hang. 0 for no limit
+    #[arg(long, value_name = "N", default_value_t = 10_000_000)]
+    pub max_steps: u64,
+}
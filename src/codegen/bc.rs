@@ -0,0 +1,229 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::codegen::pa::{BinOp, Reg, UnOp};
+use crate::dynamic::value::{fmt_float, Value};
+use crate::parsing::ast::identifier::Identifier;
+
+pub mod reg_alloc;
+pub mod lower;
+pub mod encoding;
+
+/// The address of an instruction, its index in [Bytecode::code]
+pub type Addr = usize;
+
+/// Where an instruction reads a value from or writes it to. PA identifiers no longer exist, they
+/// have been given a register or a slot in the frame of their procedure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operand {
+    Reg(Reg),
+    /// an index into [Bytecode::consts]
+    Const(u32),
+    /// a global variable, as an index into [Bytecode::names]
+    Global(u32),
+    /// the stack slot at an offset from the address in a register
+    Mem(Reg, i32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    Copy { dst: Operand, src: Operand },
+    Unary { dst: Operand, op: UnOp, src: Operand },
+    Binary { dst: Operand, op: BinOp, src1: Operand, src2: Operand },
+    Vec { dst: Operand, x: Operand, y: Operand, z: Operand },
+    Ret,
+    /// `name` is an index into [Bytecode::names]. Procedures which are not in [Bytecode::procs]
+    /// are builtins
+    Jmp { name: u32, argc: u32 },
+    Ifn { cond: Operand, addr: Addr },
+    Goto(Addr),
+    Push(Operand),
+    Pop(Operand),
+    /// `msg` is an index into [Bytecode::consts]
+    Trap(u32),
+}
+
+/// A rule and the options it starts with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleEntry {
+    /// an index into [Bytecode::names]
+    pub name: u32,
+    pub active: bool,
+    pub run_immediately: bool,
+    /// in seconds
+    pub min_interval: i32,
+    /// in seconds
+    pub max_interval: Option<i32>,
+    pub priority: i32,
+    pub group: Option<String>,
+}
+
+/// A script compiled for the XS VM, see `maths/docs/code_gen/xs_bc.md`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bytecode {
+    /// the literals used by the code
+    pub consts: Vec<Value>,
+    /// the names of globals and procedures used by the code
+    pub names: Vec<Identifier>,
+    /// the entry of each procedure, by its index in [Bytecode::names]
+    pub procs: Vec<(u32, Addr)>,
+    /// every rule in the order it was defined
+    pub rules: Vec<RuleEntry>,
+    /// how deep function calls may be nested, if the script sets it
+    pub recursion_limit: Option<u32>,
+    pub code: Vec<Instr>,
+}
+
+impl Bytecode {
+    /// The entry of the procedure `name`
+    pub fn entry(&self, name: &str) -> Option<Addr> {
+        self.procs.iter()
+            .find(|(idx, _addr)| self.names[*idx as usize].0 == name)
+            .map(|(_idx, addr)| *addr)
+    }
+
+    /// The index of `name` in [Bytecode::names], added if it is not there yet
+    pub fn name_idx(&mut self, name: &Identifier) -> u32 {
+        match self.names.iter().position(|known| known == name) {
+            Some(idx) => idx as u32,
+            None => {
+                self.names.push(name.clone());
+                self.names.len() as u32 - 1
+            }
+        }
+    }
+
+    /// The index of `val` in [Bytecode::consts], added if it is not there yet
+    pub fn const_idx(&mut self, val: Value) -> u32 {
+        match self.consts.iter().position(|known| *known == val) {
+            Some(idx) => idx as u32,
+            None => {
+                self.consts.push(val);
+                self.consts.len() as u32 - 1
+            }
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut Formatter<'_>, operand: &Operand) -> fmt::Result {
+        match operand {
+            Operand::Reg(reg) => write!(f, "{}", reg),
+            Operand::Const(idx) => match &self.consts[*idx as usize] {
+                Value::Float(val) => write!(f, "{}", fmt_float(*val)),
+                Value::Str(val) => write!(f, "{:?}", val),
+                Value::Vec([x, y, z]) => {
+                    write!(f, "vector({}, {}, {})", fmt_float(*x), fmt_float(*y), fmt_float(*z))
+                }
+                val => write!(f, "{}", val),
+            },
+            Operand::Global(idx) => write!(f, "@{}", self.names[*idx as usize].0),
+            Operand::Mem(reg, offset) => write!(f, "[{}{:+}]", reg, offset),
+        }
+    }
+
+    /// Writes one instruction in the same syntax as PA
+    fn fmt_instr(&self, f: &mut Formatter<'_>, instr: &Instr) -> fmt::Result {
+        let ops = |f: &mut Formatter<'_>, sep: &str, operands: &[&Operand]| {
+            for (idx, operand) in operands.iter().enumerate() {
+                if idx > 0 {
+                    write!(f, "{}", sep)?;
+                }
+                self.fmt_operand(f, operand)?;
+            }
+            Ok(())
+        };
+        match instr {
+            Instr::Copy { dst, src } => ops(f, " <- ", &[dst, src]),
+            Instr::Unary { dst, op, src } => {
+                ops(f, "", &[dst])?;
+                match op {
+                    UnOp::Neg => write!(f, " <- -")?,
+                    UnOp::Not => write!(f, " <- !")?,
+                    UnOp::Conv(type_) => write!(f, " <- ({}) ", type_.type_())?,
+                    UnOp::Bits => write!(f, " <- bits ")?,
+                    UnOp::Member(_) => write!(f, " <- ")?,
+                };
+                ops(f, "", &[src])?;
+                match op {
+                    UnOp::Member(idx) => write!(f, ".{}", ["x", "y", "z"][*idx as usize]),
+                    _ => Ok(()),
+                }
+            }
+            Instr::Binary {
+                dst: Operand::Reg(Reg::SP),
+                op: op @ (BinOp::Add | BinOp::Sub),
+                src1: Operand::Reg(Reg::SP),
+                src2: Operand::Const(idx),
+            } if matches!(self.consts[*idx as usize], Value::Int(_)) => {
+                let size = &self.consts[*idx as usize];
+                write!(f, "{} {}", if *op == BinOp::Add { "alloc" } else { "dealloc" }, size)
+            }
+            Instr::Binary { dst, op, src1, src2 } => {
+                ops(f, "", &[dst])?;
+                write!(f, " <- ")?;
+                ops(f, &format!(" {} ", op), &[src1, src2])
+            }
+            Instr::Vec { dst, x, y, z } => {
+                ops(f, "", &[dst])?;
+                write!(f, " <- vector(")?;
+                ops(f, ", ", &[x, y, z])?;
+                write!(f, ")")
+            }
+            Instr::Ret => write!(f, "ret"),
+            Instr::Jmp { name, argc } => write!(f, "jmp {}, {}", self.names[*name as usize].0, argc),
+            Instr::Ifn { cond, addr } => {
+                write!(f, "ifn ")?;
+                ops(f, "", &[cond])?;
+                write!(f, " goto {}", addr)
+            }
+            Instr::Goto(addr) => write!(f, "goto {}", addr),
+            Instr::Push(src) => {
+                write!(f, "push ")?;
+                ops(f, "", &[src])
+            }
+            Instr::Pop(dst) => {
+                write!(f, "pop ")?;
+                ops(f, "", &[dst])
+            }
+            Instr::Trap(msg) => {
+                write!(f, "trap ")?;
+                self.fmt_operand(f, &Operand::Const(*msg))
+            }
+        }
+    }
+}
+
+/// The disassembly. The rules and the recursion limit come first as comments, followed by one
+/// addressed instruction per line under the label of its procedure
+impl Display for Bytecode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(limit) = self.recursion_limit {
+            writeln!(f, "; infiniteRecursionLimit {}", limit)?;
+        }
+        for rule in self.rules.iter() {
+            write!(f, "; rule {}", self.names[rule.name as usize].0)?;
+            write!(f, " {}", if rule.active { "active" } else { "inactive" })?;
+            if rule.run_immediately {
+                write!(f, " runImmediately")?;
+            }
+            write!(f, " minInterval {}", rule.min_interval)?;
+            if let Some(max) = rule.max_interval {
+                write!(f, " maxInterval {}", max)?;
+            }
+            write!(f, " priority {}", rule.priority)?;
+            if let Some(group) = &rule.group {
+                write!(f, " group {:?}", group)?;
+            }
+            writeln!(f)?;
+        }
+
+        let width = self.code.len().saturating_sub(1).to_string().len();
+        for (addr, instr) in self.code.iter().enumerate() {
+            for (name, _entry) in self.procs.iter().filter(|(_name, entry)| *entry == addr) {
+                writeln!(f, "{}:", self.names[*name as usize].0)?;
+            }
+            write!(f, "    {:>width$}: ", addr)?;
+            self.fmt_instr(f, instr)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
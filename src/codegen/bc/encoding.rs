@@ -0,0 +1,468 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::codegen::bc::{Addr, Bytecode, Instr, Operand, RuleEntry};
+use crate::codegen::pa::{BinOp, ConvType, Reg, UnOp};
+use crate::dynamic::value::Value;
+use crate::parsing::ast::identifier::Identifier;
+
+/// The first bytes of every bytecode file
+pub const MAGIC: &[u8; 4] = b"XSBC";
+/// The version of the format described in `maths/docs/code_gen/xs_bc.md`
+pub const VERSION: u16 = 1;
+
+/// The opcodes, see `maths/docs/xs_vm/xs_ops.md`
+pub mod op {
+    pub const MOV: u8 = 0x01;
+    pub const NEG: u8 = 0x02;
+    pub const NOT: u8 = 0x03;
+    pub const CONV: u8 = 0x04;
+    pub const BITS: u8 = 0x05;
+    pub const MEMBER: u8 = 0x06;
+    /// the binary operators follow in the order of [BinOp](crate::codegen::pa::BinOp), from `add`
+    /// to `or`
+    pub const ADD: u8 = 0x10;
+    pub const OR: u8 = 0x1C;
+    pub const VEC: u8 = 0x20;
+    pub const RET: u8 = 0x30;
+    pub const JMP: u8 = 0x31;
+    pub const IFN: u8 = 0x32;
+    pub const GOTO: u8 = 0x33;
+    pub const PUSH: u8 = 0x34;
+    pub const POP: u8 = 0x35;
+    pub const TRAP: u8 = 0x36;
+}
+
+const BIN_OPS: [BinOp; 13] = [
+    BinOp::Add, BinOp::Sub, BinOp::Mul, BinOp::Div, BinOp::Rem,
+    BinOp::Lt, BinOp::Gt, BinOp::Le, BinOp::Ge, BinOp::Eq, BinOp::Ne,
+    BinOp::And, BinOp::Or,
+];
+const CONV_TYPES: [ConvType; 5] = [
+    ConvType::Int, ConvType::Float, ConvType::Bool, ConvType::Str, ConvType::Vec,
+];
+
+/// Why a sequence of bytes is not valid bytecode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub msg: String,
+    /// where in the bytes the problem was found
+    pub offset: usize,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.msg, self.offset)
+    }
+}
+
+/// Writes bytecode in the binary format
+pub fn encode(bc: &Bytecode) -> Vec<u8> {
+    let mut out = vec![];
+    out.extend(MAGIC);
+    out.extend(VERSION.to_le_bytes());
+    match bc.recursion_limit {
+        Some(limit) => {
+            out.push(1);
+            put_u32(&mut out, limit);
+        }
+        None => out.push(0),
+    }
+
+    put_u32(&mut out, bc.consts.len() as u32);
+    for val in bc.consts.iter() {
+        match val {
+            Value::Int(val) => {
+                out.push(0);
+                out.extend(val.to_le_bytes());
+            }
+            Value::Float(val) => {
+                out.push(1);
+                out.extend(val.to_le_bytes());
+            }
+            Value::Bool(val) => {
+                out.push(2);
+                out.push(*val as u8);
+            }
+            Value::Str(val) => {
+                out.push(3);
+                put_str(&mut out, val);
+            }
+            Value::Vec(val) => {
+                out.push(4);
+                for component in val {
+                    out.extend(component.to_le_bytes());
+                }
+            }
+        }
+    }
+
+    put_u32(&mut out, bc.names.len() as u32);
+    for name in bc.names.iter() {
+        put_str(&mut out, &name.0);
+    }
+
+    put_u32(&mut out, bc.code.len() as u32);
+    for instr in bc.code.iter() {
+        put_instr(&mut out, instr);
+    }
+
+    put_u32(&mut out, bc.procs.len() as u32);
+    for (name, addr) in bc.procs.iter() {
+        put_u32(&mut out, *name);
+        put_u32(&mut out, *addr as u32);
+    }
+
+    put_u32(&mut out, bc.rules.len() as u32);
+    for rule in bc.rules.iter() {
+        put_u32(&mut out, rule.name);
+        let flags = rule.active as u8
+            | (rule.run_immediately as u8) << 1
+            | (rule.max_interval.is_some() as u8) << 2
+            | (rule.group.is_some() as u8) << 3;
+        out.push(flags);
+        out.extend(rule.min_interval.to_le_bytes());
+        if let Some(max) = rule.max_interval {
+            out.extend(max.to_le_bytes());
+        }
+        out.extend(rule.priority.to_le_bytes());
+        if let Some(group) = &rule.group {
+            put_str(&mut out, group);
+        }
+    }
+    out
+}
+
+fn put_u32(out: &mut Vec<u8>, val: u32) {
+    out.extend(val.to_le_bytes());
+}
+
+fn put_str(out: &mut Vec<u8>, val: &str) {
+    put_u32(out, val.len() as u32);
+    out.extend(val.as_bytes());
+}
+
+fn put_operand(out: &mut Vec<u8>, operand: &Operand) {
+    match operand {
+        Operand::Reg(reg) => out.extend([0, reg.0]),
+        Operand::Const(idx) => {
+            out.push(1);
+            put_u32(out, *idx);
+        }
+        Operand::Global(idx) => {
+            out.push(2);
+            put_u32(out, *idx);
+        }
+        Operand::Mem(reg, offset) => {
+            out.extend([3, reg.0]);
+            out.extend(offset.to_le_bytes());
+        }
+    }
+}
+
+fn put_instr(out: &mut Vec<u8>, instr: &Instr) {
+    match instr {
+        Instr::Copy { dst, src } => {
+            out.push(op::MOV);
+            put_operand(out, dst);
+            put_operand(out, src);
+        }
+        Instr::Unary { dst, op: uop, src } => {
+            out.push(match uop {
+                UnOp::Neg => op::NEG,
+                UnOp::Not => op::NOT,
+                UnOp::Conv(_) => op::CONV,
+                UnOp::Bits => op::BITS,
+                UnOp::Member(_) => op::MEMBER,
+            });
+            put_operand(out, dst);
+            put_operand(out, src);
+            match uop {
+                UnOp::Conv(type_) => {
+                    out.push(CONV_TYPES.iter().position(|conv| conv == type_).unwrap() as u8);
+                }
+                UnOp::Member(idx) => out.push(*idx),
+                _ => {}
+            }
+        }
+        Instr::Binary { dst, op: bop, src1, src2 } => {
+            out.push(op::ADD + BIN_OPS.iter().position(|known| known == bop).unwrap() as u8);
+            put_operand(out, dst);
+            put_operand(out, src1);
+            put_operand(out, src2);
+        }
+        Instr::Vec { dst, x, y, z } => {
+            out.push(op::VEC);
+            for operand in [dst, x, y, z] {
+                put_operand(out, operand);
+            }
+        }
+        Instr::Ret => out.push(op::RET),
+        Instr::Jmp { name, argc } => {
+            out.push(op::JMP);
+            put_u32(out, *name);
+            put_u32(out, *argc);
+        }
+        Instr::Ifn { cond, addr } => {
+            out.push(op::IFN);
+            put_operand(out, cond);
+            put_u32(out, *addr as u32);
+        }
+        Instr::Goto(addr) => {
+            out.push(op::GOTO);
+            put_u32(out, *addr as u32);
+        }
+        Instr::Push(src) => {
+            out.push(op::PUSH);
+            put_operand(out, src);
+        }
+        Instr::Pop(dst) => {
+            out.push(op::POP);
+            put_operand(out, dst);
+        }
+        Instr::Trap(msg) => {
+            out.push(op::TRAP);
+            put_u32(out, *msg);
+        }
+    }
+}
+
+/// Reads bytecode written by [encode]. Every index and address in it is checked to be in range,
+/// so a VM may run the result without checking them again
+pub fn decode(bytes: &[u8]) -> Result<Bytecode, DecodeError> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(reader.err_at(0, "Not XS bytecode"));
+    }
+    let version = u16::from_le_bytes(reader.array()?);
+    if version != VERSION {
+        return Err(reader.err_at(MAGIC.len(), &format!("Unsupported bytecode version {}", version)));
+    }
+
+    let mut bc = Bytecode::default();
+    if reader.bool()? {
+        bc.recursion_limit = Some(reader.u32()?);
+    }
+
+    for _ in 0..reader.u32()? {
+        let start = reader.pos;
+        let val = match reader.u8()? {
+            0 => Value::Int(i32::from_le_bytes(reader.array()?)),
+            1 => Value::Float(f32::from_le_bytes(reader.array()?)),
+            2 => Value::Bool(reader.bool()?),
+            3 => Value::Str(reader.str()?),
+            4 => Value::Vec([reader.f32()?, reader.f32()?, reader.f32()?]),
+            tag => return Err(reader.err_at(start, &format!("Unknown constant type {}", tag))),
+        };
+        bc.consts.push(val);
+    }
+
+    for _ in 0..reader.u32()? {
+        let name = reader.str()?;
+        bc.names.push(Identifier(name));
+    }
+
+    let len = reader.u32()? as usize;
+    for _ in 0..len {
+        let instr = reader.instr(&bc, len)?;
+        bc.code.push(instr);
+    }
+
+    for _ in 0..reader.u32()? {
+        let name = reader.name(&bc)?;
+        let addr = reader.addr(len)?;
+        bc.procs.push((name, addr));
+    }
+
+    for _ in 0..reader.u32()? {
+        let name = reader.name(&bc)?;
+        let start = reader.pos;
+        let flags = reader.u8()?;
+        if flags >> 4 != 0 {
+            return Err(reader.err_at(start, "Unknown rule flags"));
+        }
+        let min_interval = i32::from_le_bytes(reader.array()?);
+        let max_interval = match flags & 0b100 {
+            0 => None,
+            _ => Some(i32::from_le_bytes(reader.array()?)),
+        };
+        let priority = i32::from_le_bytes(reader.array()?);
+        let group = match flags & 0b1000 {
+            0 => None,
+            _ => Some(reader.str()?),
+        };
+        bc.rules.push(RuleEntry {
+            name,
+            active: flags & 0b1 != 0,
+            run_immediately: flags & 0b10 != 0,
+            min_interval,
+            max_interval,
+            priority,
+            group,
+        });
+    }
+
+    if reader.pos != bytes.len() {
+        return Err(reader.err_at(reader.pos, "Unexpected bytes after the end of the bytecode"));
+    }
+    Ok(bc)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn err_at(&self, offset: usize, msg: &str) -> DecodeError {
+        DecodeError { msg: msg.to_string(), offset }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        match self.bytes.get(self.pos..self.pos + len) {
+            Some(bytes) => {
+                self.pos += len;
+                Ok(bytes)
+            }
+            None => Err(self.err_at(self.bytes.len(), "Unexpected end of the bytecode")),
+        }
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.take(N)?.try_into().expect("exactly N bytes were taken"))
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, DecodeError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(self.err_at(self.pos - 1, "Expected a boolean")),
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, DecodeError> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn str(&mut self) -> Result<String, DecodeError> {
+        let len = self.u32()? as usize;
+        let start = self.pos;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| self.err_at(start, "Invalid UTF-8 in a string"))
+    }
+
+    /// An index into the names
+    fn name(&mut self, bc: &Bytecode) -> Result<u32, DecodeError> {
+        let start = self.pos;
+        let idx = self.u32()?;
+        match (idx as usize) < bc.names.len() {
+            true => Ok(idx),
+            false => Err(self.err_at(start, &format!("Name {} does not exist", idx))),
+        }
+    }
+
+    /// An index into the constants
+    fn constant(&mut self, bc: &Bytecode) -> Result<u32, DecodeError> {
+        let start = self.pos;
+        let idx = self.u32()?;
+        match (idx as usize) < bc.consts.len() {
+            true => Ok(idx),
+            false => Err(self.err_at(start, &format!("Constant {} does not exist", idx))),
+        }
+    }
+
+    /// An address in code of length `len`
+    fn addr(&mut self, len: usize) -> Result<Addr, DecodeError> {
+        let start = self.pos;
+        let addr = self.u32()? as usize;
+        match addr < len {
+            true => Ok(addr),
+            false => Err(self.err_at(start, &format!("Address {} is past the end of the code", addr))),
+        }
+    }
+
+    fn reg(&mut self) -> Result<Reg, DecodeError> {
+        match self.u8()? {
+            reg @ 0..=31 => Ok(Reg(reg)),
+            reg => Err(self.err_at(self.pos - 1, &format!("Register {} does not exist", reg))),
+        }
+    }
+
+    fn operand(&mut self, bc: &Bytecode) -> Result<Operand, DecodeError> {
+        let start = self.pos;
+        match self.u8()? {
+            0 => Ok(Operand::Reg(self.reg()?)),
+            1 => Ok(Operand::Const(self.constant(bc)?)),
+            2 => Ok(Operand::Global(self.name(bc)?)),
+            3 => Ok(Operand::Mem(self.reg()?, i32::from_le_bytes(self.array()?))),
+            tag => Err(self.err_at(start, &format!("Unknown operand type {}", tag))),
+        }
+    }
+
+    fn instr(&mut self, bc: &Bytecode, len: usize) -> Result<Instr, DecodeError> {
+        let start = self.pos;
+        let instr = match self.u8()? {
+            op::MOV => Instr::Copy { dst: self.operand(bc)?, src: self.operand(bc)? },
+            opcode @ (op::NEG | op::NOT | op::BITS) => Instr::Unary {
+                dst: self.operand(bc)?,
+                op: match opcode {
+                    op::NEG => UnOp::Neg,
+                    op::NOT => UnOp::Not,
+                    _ => UnOp::Bits,
+                },
+                src: self.operand(bc)?,
+            },
+            op::CONV => {
+                let (dst, src) = (self.operand(bc)?, self.operand(bc)?);
+                let type_ = match CONV_TYPES.get(self.u8()? as usize) {
+                    Some(type_) => *type_,
+                    None => return Err(self.err_at(self.pos - 1, "Unknown conversion type")),
+                };
+                Instr::Unary { dst, op: UnOp::Conv(type_), src }
+            }
+            op::MEMBER => {
+                let (dst, src) = (self.operand(bc)?, self.operand(bc)?);
+                let idx = match self.u8()? {
+                    idx @ 0..=2 => idx,
+                    _ => return Err(self.err_at(self.pos - 1, "Unknown vector member")),
+                };
+                Instr::Unary { dst, op: UnOp::Member(idx), src }
+            }
+            opcode @ op::ADD..=op::OR => Instr::Binary {
+                dst: self.operand(bc)?,
+                op: BIN_OPS[(opcode - op::ADD) as usize],
+                src1: self.operand(bc)?,
+                src2: self.operand(bc)?,
+            },
+            op::VEC => Instr::Vec {
+                dst: self.operand(bc)?,
+                x: self.operand(bc)?,
+                y: self.operand(bc)?,
+                z: self.operand(bc)?,
+            },
+            op::RET => Instr::Ret,
+            op::JMP => Instr::Jmp { name: self.name(bc)?, argc: self.u32()? },
+            op::IFN => Instr::Ifn { cond: self.operand(bc)?, addr: self.addr(len)? },
+            op::GOTO => Instr::Goto(self.addr(len)?),
+            op::PUSH => Instr::Push(self.operand(bc)?),
+            op::POP => Instr::Pop(self.operand(bc)?),
+            op::TRAP => {
+                let msg_start = self.pos;
+                let msg = self.constant(bc)?;
+                if !matches!(bc.consts[msg as usize], Value::Str(_)) {
+                    return Err(self.err_at(msg_start, "The message of a `trap` must be a string"));
+                }
+                Instr::Trap(msg)
+            }
+            opcode => return Err(self.err_at(start, &format!("Unknown opcode 0x{:02X}", opcode))),
+        };
+        Ok(instr)
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use crate::codegen::bc::reg_alloc::{allocate, Allocation};
+use crate::codegen::bc::{Bytecode, Instr, Operand, RuleEntry};
+use crate::codegen::pa;
+use crate::codegen::pa::{BinOp, Program, Reg};
+use crate::dynamic::scheduler::Rule;
+use crate::dynamic::value::Value;
+
+/// Compiles PA to bytecode. Each procedure has its identifiers allocated to registers
+/// separately. Registers which hold values still needed after a call are saved on the stack
+/// before its arguments are pushed and restored once they are popped again
+pub fn lower_program(program: &Program) -> Bytecode {
+    let mut bc = Bytecode {
+        recursion_limit: program.recursion_limit.map(|limit| limit as u32),
+        ..Bytecode::default()
+    };
+    for (name, opts) in program.rules.iter() {
+        let name_idx = bc.name_idx(name);
+        let rule = Rule::with_opts(name.clone(), opts.iter().map(|(opt, _span)| opt));
+        bc.rules.push(rule_entry(name_idx, rule));
+    }
+
+    let starts = program.instrs.iter()
+        .enumerate()
+        .filter(|(_idx, instr)| matches!(instr, pa::Instr::Label(_)))
+        .map(|(idx, _instr)| idx)
+        .collect::<Vec<usize>>();
+
+    // the address each PA instruction starts at in the bytecode, and one past the end
+    let mut addrs = vec![0; program.instrs.len() + 1];
+    // jumps which still point to PA addresses
+    let mut jumps = vec![];
+    for (proc_idx, start) in starts.iter().copied().enumerate() {
+        let end = starts.get(proc_idx + 1).copied().unwrap_or(program.instrs.len());
+        let instrs = &program.instrs[start..end];
+        let alloc = allocate(instrs, start + 1);
+
+        // the first argument pushed for each call, and the instruction which pops the arguments
+        let mut saves = HashMap::new();
+        let mut restores = HashMap::new();
+        for (idx, instr) in instrs.iter().enumerate() {
+            if let pa::Instr::Jmp { argc, .. } = instr {
+                let regs = &alloc.live_across[&idx];
+                saves.insert(idx - argc, regs);
+                restores.insert(if *argc > 0 { idx + 1 } else { idx }, regs);
+            }
+        }
+
+        for (idx, instr) in instrs.iter().enumerate() {
+            addrs[start + idx] = bc.code.len();
+            for reg in saves.get(&idx).into_iter().flat_map(|regs| regs.iter()) {
+                bc.code.push(Instr::Push(Operand::Reg(*reg)));
+            }
+
+            let lowered = lower_instr(&mut bc, &alloc, instr, &mut jumps);
+            bc.code.extend(lowered);
+
+            // the frame of a procedure starts with the slots for its spilled identifiers
+            if *instr == (pa::Instr::Copy { dst: pa::Operand::Reg(Reg::BP), src: pa::Operand::Reg(Reg::SP) })
+                && alloc.slots > 0
+            {
+                let size = bc.const_idx(Value::Int(alloc.slots as i32));
+                bc.code.push(Instr::Binary {
+                    dst: Operand::Reg(Reg::SP),
+                    op: BinOp::Add,
+                    src1: Operand::Reg(Reg::SP),
+                    src2: Operand::Const(size),
+                });
+            }
+            for reg in restores.get(&idx).into_iter().flat_map(|regs| regs.iter().rev()) {
+                bc.code.push(Instr::Pop(Operand::Reg(*reg)));
+            }
+        }
+    }
+    addrs[program.instrs.len()] = bc.code.len();
+
+    for (idx, pa_addr) in jumps {
+        let addr = addrs[pa_addr - 1];
+        match &mut bc.code[idx] {
+            Instr::Ifn { addr: target, .. } | Instr::Goto(target) => *target = addr,
+            instr => unreachable!("only jumps are patched, not `{:?}`", instr),
+        }
+    }
+    bc
+}
+
+/// The instruction `instr` compiles to, if any. Jumps are recorded in `jumps` to be pointed to
+/// their bytecode addresses later
+fn lower_instr(
+    bc: &mut Bytecode,
+    alloc: &Allocation,
+    instr: &pa::Instr,
+    jumps: &mut Vec<(usize, pa::Addr)>,
+) -> Option<Instr> {
+    match instr {
+        pa::Instr::Label(name) => {
+            let name = bc.name_idx(name);
+            bc.procs.push((name, bc.code.len()));
+            None
+        }
+        pa::Instr::Copy { dst, src } => {
+            let (dst, src) = (operand(bc, alloc, dst), operand(bc, alloc, src));
+            // identifiers which are copied to each other may have been given the same home
+            (dst != src).then_some(Instr::Copy { dst, src })
+        }
+        pa::Instr::Unary { dst, op, src } => Some(Instr::Unary {
+            dst: operand(bc, alloc, dst),
+            op: *op,
+            src: operand(bc, alloc, src),
+        }),
+        pa::Instr::Binary { dst, op, src1, src2 } => Some(Instr::Binary {
+            dst: operand(bc, alloc, dst),
+            op: *op,
+            src1: operand(bc, alloc, src1),
+            src2: operand(bc, alloc, src2),
+        }),
+        pa::Instr::Vec { dst, x, y, z } => Some(Instr::Vec {
+            dst: operand(bc, alloc, dst),
+            x: operand(bc, alloc, x),
+            y: operand(bc, alloc, y),
+            z: operand(bc, alloc, z),
+        }),
+        pa::Instr::Ret => Some(Instr::Ret),
+        pa::Instr::Jmp { label, argc } => Some(Instr::Jmp { name: bc.name_idx(label), argc: *argc as u32 }),
+        pa::Instr::Ifn { cond, addr } => {
+            jumps.push((bc.code.len(), *addr));
+            Some(Instr::Ifn { cond: operand(bc, alloc, cond), addr: *addr })
+        }
+        pa::Instr::Goto(addr) => {
+            jumps.push((bc.code.len(), *addr));
+            Some(Instr::Goto(*addr))
+        }
+        pa::Instr::Push(src) => Some(Instr::Push(operand(bc, alloc, src))),
+        pa::Instr::Pop(dst) => Some(Instr::Pop(operand(bc, alloc, dst))),
+        pa::Instr::Trap(msg) => Some(Instr::Trap(bc.const_idx(Value::Str(msg.clone())))),
+    }
+}
+
+fn operand(bc: &mut Bytecode, alloc: &Allocation, src: &pa::Operand) -> Operand {
+    match src {
+        pa::Operand::Const(val) => Operand::Const(bc.const_idx(val.clone())),
+        pa::Operand::Reg(reg) => Operand::Reg(*reg),
+        pa::Operand::Id(id) => alloc.homes[id],
+        pa::Operand::Global(name) => Operand::Global(bc.name_idx(name)),
+        pa::Operand::Mem(reg, offset) => Operand::Mem(*reg, *offset),
+    }
+}
+
+/// The options a rule starts with, like the interpreter sets them when the rule is defined
+fn rule_entry(name: u32, rule: Rule) -> RuleEntry {
+    RuleEntry {
+        name,
+        active: rule.active,
+        run_immediately: rule.run_immediately,
+        min_interval: rule.min_interval,
+        max_interval: rule.max_interval,
+        priority: rule.priority,
+        group: rule.group,
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::{HashMap, HashSet};
+use crate::codegen::bc::Operand;
+use crate::codegen::pa;
+use crate::codegen::pa::{Instr, Reg};
+use crate::parsing::ast::identifier::Identifier;
+
+/// The registers PA identifiers may be given. `r_ret` and the aliased registers are never given
+/// out, so the registers in between are the ones left
+pub const REGS: std::ops::RangeInclusive<u8> = 1..=26;
+
+/// Where the PA identifiers of one procedure live
+#[derive(Debug, Clone, Default)]
+pub struct Allocation {
+    /// the register or frame slot `[r_bp+n]` of each identifier
+    pub homes: HashMap<Identifier, Operand>,
+    /// the number of frame slots taken by spilled identifiers
+    pub slots: usize,
+    /// the registers which hold values that are still needed after each `jmp` returns, by the
+    /// index of the `jmp` in the procedure
+    pub live_across: HashMap<usize, Vec<Reg>>,
+}
+
+/// Gives each PA identifier in the procedure `instrs` a register, or a frame slot when all
+/// registers are taken. `base` is the PA address of the first instruction, which jumps are
+/// relative to. Identifiers which are live at the same time never share a home, see
+/// `maths/docs/code_gen/xs_bc.md`
+pub fn allocate(instrs: &[Instr], base: pa::Addr) -> Allocation {
+    let mut ids: Vec<Identifier> = vec![];
+    let mut idxs: HashMap<Identifier, usize> = HashMap::new();
+    let mut uses = Vec::with_capacity(instrs.len());
+    let mut defs = Vec::with_capacity(instrs.len());
+    for instr in instrs {
+        let (instr_uses, instr_def) = uses_def(instr);
+        let mut number = |id: &Identifier| *idxs.entry(id.clone()).or_insert_with(|| {
+            ids.push(id.clone());
+            ids.len() - 1
+        });
+        uses.push(instr_uses.into_iter().map(&mut number).collect::<Vec<usize>>());
+        defs.push(instr_def.map(&mut number));
+    }
+
+    let live_out = liveness(instrs, base, &uses, &defs);
+
+    let mut interference = vec![HashSet::new(); ids.len()];
+    for (idx, instr) in instrs.iter().enumerate() {
+        let Some(def) = defs[idx] else {
+            continue;
+        };
+        // a copy does not make its destination interfere with its source, so both may share a
+        // home and the copy can be left out
+        let copied = match instr {
+            Instr::Copy { src: pa::Operand::Id(src), .. } => Some(idxs[src]),
+            _ => None,
+        };
+        for live in live_out[idx].iter().copied() {
+            if live != def && Some(live) != copied {
+                interference[def].insert(live);
+                interference[live].insert(def);
+            }
+        }
+    }
+
+    // each identifier in the order it first appears takes the first home none of its neighbours
+    // has taken yet, registers first
+    let mut homes: Vec<Option<Operand>> = vec![None; ids.len()];
+    let mut slots = 0;
+    for id in 0..ids.len() {
+        let taken = interference[id].iter()
+            .filter_map(|neighbour| homes[*neighbour])
+            .collect::<HashSet<Operand>>();
+        let home = REGS.map(|reg| Operand::Reg(Reg(reg)))
+            .chain((0..).map(|slot| Operand::Mem(Reg::BP, slot)))
+            .find(|home| !taken.contains(home))
+            .expect("there are always free slots");
+        if let Operand::Mem(_reg, slot) = home {
+            slots = slots.max(slot as usize + 1);
+        }
+        homes[id] = Some(home);
+    }
+    let homes = homes.into_iter().map(|home| home.expect("every identifier has a home")).collect::<Vec<_>>();
+
+    let mut live_across = HashMap::new();
+    for (idx, instr) in instrs.iter().enumerate() {
+        if !matches!(instr, Instr::Jmp { .. }) {
+            continue;
+        }
+        let mut regs = live_out[idx].iter()
+            .filter_map(|id| match homes[*id] {
+                Operand::Reg(reg) => Some(reg),
+                _ => None,
+            })
+            .collect::<Vec<Reg>>();
+        regs.sort_by_key(|reg| reg.0);
+        regs.dedup();
+        live_across.insert(idx, regs);
+    }
+
+    Allocation {
+        homes: ids.into_iter().zip(homes).collect(),
+        slots,
+        live_across,
+    }
+}
+
+/// The identifiers an instruction reads, and the one it writes to
+fn uses_def(instr: &Instr) -> (Vec<&Identifier>, Option<&Identifier>) {
+    fn id(operand: &pa::Operand) -> Option<&Identifier> {
+        match operand {
+            pa::Operand::Id(id) => Some(id),
+            _ => None,
+        }
+    }
+    fn ids<'a>(operands: &[&'a pa::Operand]) -> Vec<&'a Identifier> {
+        operands.iter().filter_map(|operand| id(operand)).collect()
+    }
+    match instr {
+        Instr::Copy { dst, src } | Instr::Unary { dst, src, .. } => (ids(&[src]), id(dst)),
+        Instr::Binary { dst, src1, src2, .. } => (ids(&[src1, src2]), id(dst)),
+        Instr::Vec { dst, x, y, z } => (ids(&[x, y, z]), id(dst)),
+        Instr::Ifn { cond, .. } => (ids(&[cond]), None),
+        Instr::Push(src) => (ids(&[src]), None),
+        Instr::Pop(dst) => (vec![], id(dst)),
+        Instr::Label(_) | Instr::Ret | Instr::Jmp { .. } | Instr::Goto(_) | Instr::Trap(_) => (vec![], None),
+    }
+}
+
+/// The instructions of the procedure control may flow to right after the instruction at `idx`
+fn successors(instrs: &[Instr], base: pa::Addr, idx: usize) -> Vec<usize> {
+    let next = Some(idx + 1).filter(|next| *next < instrs.len());
+    let target = |addr: pa::Addr| addr.checked_sub(base).filter(|target| *target < instrs.len());
+    match &instrs[idx] {
+        Instr::Ret | Instr::Trap(_) => vec![],
+        Instr::Goto(addr) => target(*addr).into_iter().collect(),
+        Instr::Ifn { addr, .. } => next.into_iter().chain(target(*addr)).collect(),
+        _ => next.into_iter().collect(),
+    }
+}
+
+/// The identifiers live after each instruction, found by iterating the equations in
+/// `maths/docs/static/xs_liveness.md` backwards until nothing changes
+fn liveness(
+    instrs: &[Instr],
+    base: pa::Addr,
+    uses: &[Vec<usize>],
+    defs: &[Option<usize>],
+) -> Vec<HashSet<usize>> {
+    let succs = (0..instrs.len()).map(|idx| successors(instrs, base, idx)).collect::<Vec<_>>();
+    let mut live_in = vec![HashSet::new(); instrs.len()];
+    let mut live_out = vec![HashSet::new(); instrs.len()];
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for idx in (0..instrs.len()).rev() {
+            let out = succs[idx].iter()
+                .flat_map(|succ| live_in[*succ].iter().copied())
+                .collect::<HashSet<usize>>();
+            let mut in_ = out.clone();
+            if let Some(def) = defs[idx] {
+                in_.remove(&def);
+            }
+            in_.extend(uses[idx].iter().copied());
+
+            if in_.len() != live_in[idx].len() || out.len() != live_out[idx].len() {
+                changed = true;
+            }
+            live_in[idx] = in_;
+            live_out[idx] = out;
+        }
+    }
+    live_out
+}
@@ -0,0 +1,257 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::dynamic::value::{fmt_float, Value};
+use crate::parsing::ast::astree::RuleOpt;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::Spanned;
+
+pub mod munch;
+pub mod expression;
+pub mod statement;
+
+/// The address of an instruction, addresses start at 1
+pub type Addr = usize;
+
+/// One of the 32 registers, see the aliases below
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reg(pub u8);
+
+impl Reg {
+    /// the return value of a function
+    pub const RET: Reg = Reg(0);
+    /// the first free stack slot
+    pub const SP: Reg = Reg(27);
+    /// the stack frame of the running function
+    pub const BP: Reg = Reg(28);
+    /// the address of the last `jmp`
+    pub const LP: Reg = Reg(29);
+    /// the exception pointer
+    pub const XP: Reg = Reg(30);
+    /// always `0`
+    pub const ZERO: Reg = Reg(31);
+}
+
+/// Where an instruction reads a value from or writes it to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Const(Value),
+    Reg(Reg),
+    /// a PA identifier, local to the procedure it is used in. Variables keep their names while
+    /// identifiers made up by the compiler are numbered like `%3`, so that they never clash
+    Id(Identifier),
+    /// a global variable, including `static` locals
+    Global(Identifier),
+    /// the stack slot at an offset from the address in a register
+    Mem(Reg, i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    Neg,
+    Not,
+    /// converts a value like it is converted when assigned to a variable of this type
+    Conv(ConvType),
+    /// reinterprets the bits of an `int` or `bool` as a `float`, like XS does when either is passed
+    /// to a `float` parameter. Any other value is left as it is
+    Bits,
+    /// a component of a `vector`, `0` to `2` for `x` to `z`
+    Member(u8),
+}
+
+/// The types a value can be converted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvType {
+    Int,
+    Float,
+    Bool,
+    Str,
+    Vec,
+}
+
+impl ConvType {
+    /// `None` for types which no value has
+    pub fn from_type(type_: &Type) -> Option<Self> {
+        match type_ {
+            Type::Int => Some(ConvType::Int),
+            Type::Float => Some(ConvType::Float),
+            Type::Bool => Some(ConvType::Bool),
+            Type::Str => Some(ConvType::Str),
+            Type::Vec => Some(ConvType::Vec),
+            _ => None,
+        }
+    }
+
+    pub fn type_(&self) -> Type {
+        match self {
+            ConvType::Int => Type::Int,
+            ConvType::Float => Type::Float,
+            ConvType::Bool => Type::Bool,
+            ConvType::Str => Type::Str,
+            ConvType::Vec => Type::Vec,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    /// the entry of a procedure, does nothing when run
+    Label(Identifier),
+    /// `d <- s`
+    Copy { dst: Operand, src: Operand },
+    /// `d <- op s`
+    Unary { dst: Operand, op: UnOp, src: Operand },
+    /// `d <- s op s`
+    Binary { dst: Operand, op: BinOp, src1: Operand, src2: Operand },
+    /// `d <- vector(s, s, s)`, the components are converted to `float`s
+    Vec { dst: Operand, x: Operand, y: Operand, z: Operand },
+    /// jumps to the address in `r_lp` + 1
+    Ret,
+    /// writes the current address to `r_lp` and jumps to the procedure `label`. The number of
+    /// arguments pushed for it is kept for builtins, some of which are variadic
+    Jmp { label: Identifier, argc: usize },
+    /// jumps to `addr` if `cond` is `false`
+    Ifn { cond: Operand, addr: Addr },
+    Goto(Addr),
+    /// writes to the slot at `r_sp` and increments `r_sp`
+    Push(Operand),
+    /// decrements `r_sp` and reads the slot at `r_sp`
+    Pop(Operand),
+    /// stops the script with an error
+    Trap(String),
+}
+
+/// A script lowered to PA. Instruction `i` has the address `i + 1`
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    pub instrs: Vec<Instr>,
+    /// every rule in the order it was defined, with the options of its last definition
+    pub rules: Vec<(Identifier, Vec<Spanned<RuleOpt>>)>,
+    /// how deep function calls may be nested, if the script sets it
+    pub recursion_limit: Option<usize>,
+}
+
+impl Program {
+    /// The label of the procedure which initialises the global variables. It is not a valid XS
+    /// name, so it cannot clash with a function or rule
+    pub const GLOBALS: &'static str = "<globals>";
+
+    /// The address of the procedure `label`
+    pub fn entry(&self, label: &Identifier) -> Option<Addr> {
+        self.instrs.iter()
+            .position(|instr| matches!(instr, Instr::Label(name) if name == label))
+            .map(|idx| idx + 1)
+    }
+}
+
+impl Display for Reg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Reg::RET => write!(f, "r_ret"),
+            Reg::SP => write!(f, "r_sp"),
+            Reg::BP => write!(f, "r_bp"),
+            Reg::LP => write!(f, "r_lp"),
+            Reg::XP => write!(f, "r_xp"),
+            Reg(idx) => write!(f, "r{}", idx),
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Const(Value::Float(val)) => write!(f, "{}", fmt_float(*val)),
+            Operand::Const(Value::Str(val)) => write!(f, "{:?}", val),
+            Operand::Const(Value::Vec([x, y, z])) => {
+                write!(f, "vector({}, {}, {})", fmt_float(*x), fmt_float(*y), fmt_float(*z))
+            }
+            Operand::Const(val) => write!(f, "{}", val),
+            Operand::Reg(reg) => write!(f, "{}", reg),
+            Operand::Id(id) => write!(f, "{}", id.0),
+            Operand::Global(id) => write!(f, "@{}", id.0),
+            Operand::Mem(reg, offset) => write!(f, "[{}{:+}]", reg, offset),
+        }
+    }
+}
+
+impl Display for BinOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::Lt => "<",
+            BinOp::Gt => ">",
+            BinOp::Le => "<=",
+            BinOp::Ge => ">=",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+        })
+    }
+}
+
+impl Display for Instr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Instr::Label(label) => write!(f, "{}:", label.0),
+            Instr::Copy { dst, src } => write!(f, "{} <- {}", dst, src),
+            Instr::Unary { dst, op, src } => match op {
+                UnOp::Neg => write!(f, "{} <- -{}", dst, src),
+                UnOp::Not => write!(f, "{} <- !{}", dst, src),
+                UnOp::Conv(type_) => write!(f, "{} <- ({}) {}", dst, type_.type_(), src),
+                UnOp::Bits => write!(f, "{} <- bits {}", dst, src),
+                UnOp::Member(idx) => write!(f, "{} <- {}.{}", dst, src, ["x", "y", "z"][*idx as usize]),
+            },
+            // `alloc n` and `dealloc n` are aliases for moving the stack pointer
+            Instr::Binary {
+                dst: Operand::Reg(Reg::SP),
+                op: op @ (BinOp::Add | BinOp::Sub),
+                src1: Operand::Reg(Reg::SP),
+                src2: Operand::Const(Value::Int(size)),
+            } => write!(f, "{} {}", if *op == BinOp::Add { "alloc" } else { "dealloc" }, size),
+            Instr::Binary { dst, op, src1, src2 } => write!(f, "{} <- {} {} {}", dst, src1, op, src2),
+            Instr::Vec { dst, x, y, z } => write!(f, "{} <- vector({}, {}, {})", dst, x, y, z),
+            Instr::Ret => write!(f, "ret"),
+            Instr::Jmp { label, argc } => write!(f, "jmp {}, {}", label.0, argc),
+            Instr::Ifn { cond, addr } => write!(f, "ifn {} goto {}", cond, addr),
+            Instr::Goto(addr) => write!(f, "goto {}", addr),
+            Instr::Push(src) => write!(f, "push {}", src),
+            Instr::Pop(dst) => write!(f, "pop {}", dst),
+            Instr::Trap(msg) => write!(f, "trap {:?}", msg),
+        }
+    }
+}
+
+/// One addressed instruction per line, the instructions of each procedure are indented under its
+/// label
+impl Display for Program {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let width = self.instrs.len().to_string().len();
+        for (idx, instr) in self.instrs.iter().enumerate() {
+            let indent = if matches!(instr, Instr::Label(_)) { "" } else { "    " };
+            writeln!(f, "{:>width$}: {}{}", idx + 1, indent, instr)?;
+        }
+        Ok(())
+    }
+}
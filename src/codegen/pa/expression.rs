@@ -0,0 +1,160 @@
+use crate::codegen::pa::munch::Munch;
+use crate::codegen::pa::{BinOp, Instr, Operand, Reg, UnOp};
+use crate::dynamic::value::Value;
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::Spanned;
+
+impl Munch<'_> {
+    /// Emits the instructions which compute `expr` and returns where its value is. Literals and
+    /// locals are operands already, so they need no instructions (xsMmSrc). Globals are copied
+    /// first, as a function called later in the expression may change them
+    pub(crate) fn expr(&mut self, (expr, _span): &Spanned<Expr>) -> Operand {
+        match expr {
+            Expr::Literal(lit) => Operand::Const(Value::from_lit(lit)),
+            Expr::Identifier(name) => match self.var(name).0 {
+                src @ Operand::Global(_) => {
+                    let dst = self.new_id();
+                    self.emit(Instr::Copy { dst: dst.clone(), src });
+                    dst
+                }
+                src => src,
+            },
+            Expr::Paren(expr) => self.expr(expr),
+            Expr::Vec { x, y, z } => {
+                let (x, y, z) = (self.expr(x), self.expr(y), self.expr(z));
+                let components = [&x, &y, &z].map(|src| match src {
+                    Operand::Const(val) => match val.clone().convert(&Type::Float) {
+                        Some(Value::Float(val)) => Some(val),
+                        _ => None,
+                    },
+                    _ => None,
+                });
+                if let [Some(x), Some(y), Some(z)] = components {
+                    return Operand::Const(Value::Vec([x, y, z]));
+                }
+                let dst = self.new_id();
+                self.emit(Instr::Vec { dst: dst.clone(), x, y, z });
+                dst
+            }
+            Expr::FnCall { name: (name, _name_span), args } => {
+                self.call(name, args);
+                let dst = self.new_id();
+                self.emit(Instr::Copy { dst: dst.clone(), src: Operand::Reg(Reg::RET) });
+                dst
+            }
+            Expr::Member { expr, member: (member, _member_span) } => {
+                let src = self.expr(expr);
+                let dst = self.new_id();
+                match ["x", "y", "z"].iter().position(|axis| *axis == member.0) {
+                    Some(idx) => self.emit(Instr::Unary { dst: dst.clone(), op: UnOp::Member(idx as u8), src }),
+                    None => self.emit(Instr::Trap(format!("A `vector` has no member `{}`", member.0))),
+                };
+                dst
+            }
+
+            Expr::Neg(expr) => self.unary(UnOp::Neg, expr),
+            Expr::Not(expr) => self.unary(UnOp::Not, expr),
+
+            Expr::Star(expr1, expr2) => self.binary(BinOp::Mul, expr1, expr2),
+            Expr::FSlash(expr1, expr2) => self.binary(BinOp::Div, expr1, expr2),
+            Expr::PCent(expr1, expr2) => self.binary(BinOp::Rem, expr1, expr2),
+            Expr::Plus(expr1, expr2) => self.binary(BinOp::Add, expr1, expr2),
+            Expr::Minus(expr1, expr2) => self.binary(BinOp::Sub, expr1, expr2),
+
+            Expr::Lt(expr1, expr2) => self.binary(BinOp::Lt, expr1, expr2),
+            Expr::Gt(expr1, expr2) => self.binary(BinOp::Gt, expr1, expr2),
+            Expr::Le(expr1, expr2) => self.binary(BinOp::Le, expr1, expr2),
+            Expr::Ge(expr1, expr2) => self.binary(BinOp::Ge, expr1, expr2),
+            Expr::Eq(expr1, expr2) => self.binary(BinOp::Eq, expr1, expr2),
+            Expr::Ne(expr1, expr2) => self.binary(BinOp::Ne, expr1, expr2),
+
+            Expr::And(expr1, expr2) => self.binary(BinOp::And, expr1, expr2),
+            Expr::Or(expr1, expr2) => self.binary(BinOp::Or, expr1, expr2),
+        }
+    }
+
+    /// The PA operand and type of a variable (Δ). Names which are not local are global
+    pub(crate) fn var(&self, name: &Identifier) -> (Operand, Type) {
+        match self.locals.get(name) {
+            Some(local) => local.clone(),
+            None => (
+                Operand::Global(name.clone()),
+                self.global_types.get(name).cloned().unwrap_or(Type::Void),
+            ),
+        }
+    }
+
+    /// Pushes the arguments in reverse, jumps to the function and pops the arguments again. The
+    /// return value is left in `r_ret` (xsMmFncExpr). Parameters which are not given take their
+    /// default values
+    pub(crate) fn call(&mut self, name: &Identifier, args: &[Spanned<Expr>]) {
+        let param_types = match (self.fns.get(name), self.builtins.get(name)) {
+            (Some(params), _) => params.iter().map(|param| param.type_.clone()).collect(),
+            (None, Some(Type::Func { type_sign, .. })) => type_sign[..type_sign.len() - 1].to_vec(),
+            _ => vec![],
+        };
+
+        let mut srcs = Vec::with_capacity(param_types.len().max(args.len()));
+        for (idx, arg) in args.iter().enumerate() {
+            let src = self.expr(arg);
+            srcs.push(match param_types.get(idx) {
+                Some(Type::Float) => self.bits(src),
+                _ => src,
+            });
+        }
+        let defaults: Vec<_> = self.fns.get(name)
+            .map(|params| params.iter().skip(args.len()).map(|param| param.default.clone()).collect())
+            .unwrap_or_default();
+        for default in defaults.iter() {
+            let src = self.expr(default);
+            srcs.push(src);
+        }
+
+        let argc = srcs.len();
+        for src in srcs.into_iter().rev() {
+            self.emit(Instr::Push(src));
+        }
+        self.emit(Instr::Jmp { label: name.clone(), argc });
+        if argc > 0 {
+            self.emit(Instr::Binary {
+                dst: Operand::Reg(Reg::SP),
+                op: BinOp::Sub,
+                src1: Operand::Reg(Reg::SP),
+                src2: Operand::Const(Value::Int(argc as i32)),
+            });
+        }
+    }
+
+    /// `int` and `bool` arguments to a `float` parameter are not converted, their bits are
+    /// reinterpreted instead
+    fn bits(&mut self, src: Operand) -> Operand {
+        match src {
+            Operand::Const(Value::Int(val)) => Operand::Const(Value::Float(f32::from_bits(val as u32))),
+            Operand::Const(Value::Bool(val)) => Operand::Const(Value::Float(f32::from_bits(val as u32))),
+            Operand::Const(val) => Operand::Const(val),
+            src => {
+                let dst = self.new_id();
+                self.emit(Instr::Unary { dst: dst.clone(), op: UnOp::Bits, src });
+                dst
+            }
+        }
+    }
+
+    fn unary(&mut self, op: UnOp, expr: &Spanned<Expr>) -> Operand {
+        let src = self.expr(expr);
+        let dst = self.new_id();
+        self.emit(Instr::Unary { dst: dst.clone(), op, src });
+        dst
+    }
+
+    /// Both operands are computed before the operation, even for `&&` and `||` (xsMmOp)
+    fn binary(&mut self, op: BinOp, expr1: &Spanned<Expr>, expr2: &Spanned<Expr>) -> Operand {
+        let src1 = self.expr(expr1);
+        let src2 = self.expr(expr2);
+        let dst = self.new_id();
+        self.emit(Instr::Binary { dst: dst.clone(), op, src1, src2 });
+        dst
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use crate::codegen::pa::{Addr, ConvType, Instr, Operand, Program, Reg, UnOp};
+use crate::dynamic::value::Value;
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::param::Param;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::Spanned;
+use crate::r#static::module_loader::{Module, ModuleLoader};
+use crate::r#static::type_check::TypeEnv;
+
+/// Generates PA from the AST by maximal munch, following `maths/docs/code_gen/xs_mm.md`
+pub struct Munch<'a> {
+    pub(crate) instrs: Vec<Instr>,
+    pub(crate) builtins: &'a TypeEnv,
+    /// the parameters of each user defined function, used to fill in missing arguments
+    pub(crate) fns: HashMap<Identifier, Vec<Param>>,
+    pub(crate) global_types: HashMap<Identifier, Type>,
+    pub(crate) loop_limit: Option<i64>,
+
+    /// the name of the procedure being lowered
+    pub(crate) proc: Identifier,
+    pub(crate) return_type: Type,
+    /// the PA identifier and type of each local variable (Δ)
+    pub(crate) locals: HashMap<Identifier, (Operand, Type)>,
+    next_id: usize,
+    /// the jumps out of each enclosing loop or `switch`, to be patched once its end is known
+    pub(crate) breaks: Vec<Vec<usize>>,
+    /// the start of each enclosing loop
+    pub(crate) continues: Vec<Addr>,
+    pub(crate) labels: HashMap<Identifier, Addr>,
+    pub(crate) gotos: Vec<(usize, Spanned<Identifier>)>,
+    /// jumps to the end of the procedure, where the default value of its return type is returned
+    pub(crate) exits: Vec<usize>,
+    /// jumps to the epilogue of the procedure, after the return value has been set
+    pub(crate) returns: Vec<usize>,
+    /// the flags which record whether each `static` local has been initialised
+    pub(crate) static_flags: Vec<Identifier>,
+}
+
+/// Lowers a module and every module it (transitively) includes to PA. Included modules are
+/// inlined where they are included, in the same order the interpreter runs them. `builtins` are
+/// the types of the builtin functions and constants
+pub fn lower_module(module: &Module, loader: &mut ModuleLoader, builtins: &TypeEnv) -> Program {
+    let mut top_level = vec![];
+    flatten(module, loader, &mut HashSet::from([module.path.clone()]), &mut top_level);
+
+    let mut munch = Munch {
+        instrs: vec![],
+        builtins,
+        fns: HashMap::new(),
+        global_types: builtins.clone(),
+        loop_limit: None,
+        proc: Identifier::new(Program::GLOBALS),
+        return_type: Type::Void,
+        locals: HashMap::new(),
+        next_id: 0,
+        breaks: vec![],
+        continues: vec![],
+        labels: HashMap::new(),
+        gotos: vec![],
+        exits: vec![],
+        returns: vec![],
+        static_flags: vec![],
+    };
+    let mut program = Program::default();
+
+    // a function or rule may be redefined, its last definition is the one which runs
+    let mut last_fns = HashMap::new();
+    let mut last_rules = HashMap::new();
+    for (idx, (stmt, _span)) in top_level.iter().enumerate() {
+        match stmt {
+            ASTreeNode::FnDef { name: (name, _name_span), params, .. } => {
+                munch.fns.insert(name.clone(), params.clone());
+                last_fns.insert(name.clone(), idx);
+            }
+            ASTreeNode::RuleDef { name: (name, _name_span), rule_opts, .. } => {
+                last_rules.insert(name.clone(), idx);
+                match program.rules.iter_mut().find(|(rule, _opts)| rule == name) {
+                    Some((_rule, opts)) => *opts = rule_opts.clone(),
+                    None => program.rules.push((name.clone(), rule_opts.clone())),
+                }
+            }
+            ASTreeNode::VarDef { type_, name: (name, _name_span), .. } => {
+                munch.global_types.insert(name.clone(), type_.clone());
+            }
+            // a limit below 1 is a type error, it is ignored like the interpreter does
+            ASTreeNode::InfiniteLoopLimit((limit, _limit_span)) if *limit > 0 => {
+                munch.loop_limit = Some((*limit).min(i32::MAX as i64));
+            }
+            ASTreeNode::InfiniteRecursionLimit((limit, _limit_span)) if *limit > 0 => {
+                program.recursion_limit = Some((*limit).min(i32::MAX as i64) as usize);
+            }
+            _ => {}
+        }
+    }
+
+    for (idx, spanned_stmt) in top_level.iter().enumerate() {
+        let is_last = match &spanned_stmt.0 {
+            ASTreeNode::FnDef { name: (name, _name_span), .. } => last_fns[name] == idx,
+            ASTreeNode::RuleDef { name: (name, _name_span), .. } => last_rules[name] == idx,
+            _ => false,
+        };
+        if is_last {
+            munch.procedure(spanned_stmt);
+        }
+    }
+    munch.globals(&top_level);
+
+    program.instrs = munch.instrs;
+    program
+}
+
+/// The top level statements of `module` in the order they run, with each `include` replaced by
+/// the statements of the included module. Includes which cannot be found are kept
+fn flatten(
+    module: &Module,
+    loader: &mut ModuleLoader,
+    included: &mut HashSet<PathBuf>,
+    top_level: &mut Vec<Spanned<ASTreeNode>>,
+) {
+    for spanned_stmt in module.ast.iter() {
+        let (ASTreeNode::Include((inc_path, _inc_span)), _span) = spanned_stmt else {
+            top_level.push(spanned_stmt.clone());
+            continue;
+        };
+
+        match loader.resolve(inc_path, &module.path).and_then(|path| loader.load(&path).ok()) {
+            Some(inc_module) => {
+                if included.insert(inc_module.path.clone()) {
+                    flatten(&inc_module, loader, included, top_level);
+                }
+            }
+            None => top_level.push(spanned_stmt.clone()),
+        }
+    }
+}
+
+impl Munch<'_> {
+    /// Appends an instruction, returning its index so that jumps can be patched later
+    pub(crate) fn emit(&mut self, instr: Instr) -> usize {
+        self.instrs.push(instr);
+        self.instrs.len() - 1
+    }
+
+    /// The address of the next instruction (newAddr?)
+    pub(crate) fn next_addr(&self) -> Addr {
+        self.instrs.len() + 1
+    }
+
+    /// Points the jump at index `idx` to `addr`
+    pub(crate) fn patch(&mut self, idx: usize, addr: Addr) {
+        match &mut self.instrs[idx] {
+            Instr::Ifn { addr: target, .. } => *target = addr,
+            Instr::Goto(target) => *target = addr,
+            instr => unreachable!("only jumps are patched, not `{}`", instr),
+        }
+    }
+
+    /// A new PA identifier (newId)
+    pub(crate) fn new_id(&mut self) -> Operand {
+        self.fresh("")
+    }
+
+    /// A new PA identifier for a variable named `name`, which is different from the variable
+    /// itself and from any other identifier
+    pub(crate) fn fresh(&mut self, name: &str) -> Operand {
+        self.next_id += 1;
+        Operand::Id(Identifier(format!("{}%{}", name, self.next_id)))
+    }
+
+    /// `dst <- (type_) src`. Constants are converted right away
+    pub(crate) fn convert(&mut self, dst: Operand, src: Operand, type_: &Type) {
+        let Some(conv) = ConvType::from_type(type_) else {
+            self.emit(Instr::Copy { dst, src });
+            return;
+        };
+        match src {
+            Operand::Const(val) => match val.clone().convert(type_) {
+                Some(val) => self.emit(Instr::Copy { dst, src: Operand::Const(val) }),
+                None => self.emit(Instr::Unary { dst, op: UnOp::Conv(conv), src: Operand::Const(val) }),
+            },
+            src => self.emit(Instr::Unary { dst, op: UnOp::Conv(conv), src }),
+        };
+    }
+
+    /// A function or rule. Rules are procedures without parameters. The arguments are pushed in
+    /// reverse, so the first parameter is right below the saved `r_lp` and `r_bp` (xsMmFnDef)
+    fn procedure(&mut self, (stmt, _span): &Spanned<ASTreeNode>) {
+        let (name, params, return_type, body) = match stmt {
+            ASTreeNode::FnDef { name: (name, _name_span), params, return_type, body, .. } => {
+                (name, params.as_slice(), return_type, body)
+            }
+            ASTreeNode::RuleDef { name: (name, _name_span), body, .. } => (name, [].as_slice(), &Type::Void, body),
+            _ => return,
+        };
+        self.begin(name, return_type);
+        for (idx, param) in params.iter().enumerate() {
+            let dst = Operand::Id(param.name.0.clone());
+            self.convert(dst.clone(), Operand::Mem(Reg::BP, -3 - idx as i32), &param.type_);
+            self.locals.insert(param.name.0.clone(), (dst, param.type_.clone()));
+        }
+
+        self.body(body);
+
+        // a function which finishes without returning a value returns the default value of its
+        // return type
+        let exit = self.next_addr();
+        for idx in std::mem::take(&mut self.exits) {
+            self.patch(idx, exit);
+        }
+        if let Some(val) = Value::default_of(return_type) {
+            self.emit(Instr::Copy { dst: Operand::Reg(Reg::RET), src: Operand::Const(val) });
+        }
+
+        let epilogue = self.next_addr();
+        for idx in std::mem::take(&mut self.returns) {
+            self.patch(idx, epilogue);
+        }
+        self.epilogue();
+
+        // labels may be defined after the gotos that jump to them
+        for (idx, (label, _label_span)) in std::mem::take(&mut self.gotos) {
+            match self.labels.get(&label) {
+                Some(addr) => self.patch(idx, *addr),
+                None => self.instrs[idx] = Instr::Trap(format!("Undefined label `{}`", label.0)),
+            }
+        }
+    }
+
+    /// The procedure which initialises the global variables in the order they are defined. It
+    /// also marks every `static` local as not initialised yet
+    fn globals(&mut self, top_level: &[Spanned<ASTreeNode>]) {
+        self.begin(&Identifier::new(Program::GLOBALS), &Type::Void);
+        for flag in std::mem::take(&mut self.static_flags) {
+            self.emit(Instr::Copy { dst: Operand::Global(flag), src: Operand::Const(Value::Bool(false)) });
+        }
+        for (stmt, _span) in top_level {
+            match stmt {
+                ASTreeNode::VarDef { type_, name: (name, _name_span), value, .. } => {
+                    if let Some(src) = self.init_value(type_, value.as_ref()) {
+                        self.convert(Operand::Global(name.clone()), src, type_);
+                    }
+                }
+                ASTreeNode::Include((inc_path, _inc_span)) => {
+                    self.emit(Instr::Trap(format!("Cannot find included file `{}`", inc_path)));
+                }
+                ASTreeNode::FnDef { .. }
+                | ASTreeNode::RuleDef { .. }
+                | ASTreeNode::InfiniteLoopLimit(_)
+                | ASTreeNode::InfiniteRecursionLimit(_)
+                | ASTreeNode::Class { .. } => {}
+                _ => {
+                    self.emit(Instr::Trap("This statement is not allowed at the top level".to_string()));
+                }
+            }
+        }
+        self.epilogue();
+    }
+
+    /// Starts the procedure `name` by saving the frame of its caller and starting its own
+    fn begin(&mut self, name: &Identifier, return_type: &Type) {
+        self.proc = name.clone();
+        self.return_type = return_type.clone();
+        self.locals.clear();
+        self.next_id = 0;
+        self.labels.clear();
+
+        self.emit(Instr::Label(name.clone()));
+        self.emit(Instr::Push(Operand::Reg(Reg::LP)));
+        self.emit(Instr::Push(Operand::Reg(Reg::BP)));
+        self.emit(Instr::Copy { dst: Operand::Reg(Reg::BP), src: Operand::Reg(Reg::SP) });
+    }
+
+    /// Restores the frame of the caller and returns to it
+    fn epilogue(&mut self) {
+        self.emit(Instr::Copy { dst: Operand::Reg(Reg::SP), src: Operand::Reg(Reg::BP) });
+        self.emit(Instr::Pop(Operand::Reg(Reg::BP)));
+        self.emit(Instr::Pop(Operand::Reg(Reg::LP)));
+        self.emit(Instr::Ret);
+    }
+}
@@ -0,0 +1,319 @@
+use crate::codegen::pa::munch::Munch;
+use crate::codegen::pa::{BinOp, Instr, Operand, Reg, UnOp};
+use crate::dynamic::value::Value;
+use crate::parsing::ast::astree::{ASTreeNode, Body};
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::Spanned;
+
+impl Munch<'_> {
+    /// A sequence of statements (xsMmSeq)
+    pub(crate) fn body(&mut self, (body, _span): &Spanned<Body>) {
+        for stmt in body.0.iter() {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, (stmt, _span): &Spanned<ASTreeNode>) {
+        match stmt {
+            // the value is computed before the variable is defined, so it may still refer to a
+            // global of the same name (xsMmDef)
+            ASTreeNode::VarDef { is_static: false, type_, name: (name, _name_span), value, .. } => {
+                let src = self.init_value(type_, value.as_ref());
+                let dst = Operand::Id(name.clone());
+                self.locals.insert(name.clone(), (dst.clone(), type_.clone()));
+                if let Some(src) = src {
+                    self.convert(dst, src, type_);
+                }
+            }
+            // a static local is a global which is only initialised the first time its procedure
+            // runs
+            ASTreeNode::VarDef { is_static: true, type_, name: (name, _name_span), value, .. } => {
+                let global = Identifier(format!("{}::{}", self.proc.0, name.0));
+                let flag = Identifier(format!("{}::init", global.0));
+                if !self.static_flags.contains(&flag) {
+                    self.static_flags.push(flag.clone());
+                }
+
+                let is_init = self.new_id();
+                self.emit(Instr::Unary { dst: is_init.clone(), op: UnOp::Not, src: Operand::Global(flag.clone()) });
+                let skip = self.emit(Instr::Ifn { cond: is_init, addr: 0 });
+                if let Some(src) = self.init_value(type_, value.as_ref()) {
+                    self.convert(Operand::Global(global.clone()), src, type_);
+                }
+                self.emit(Instr::Copy { dst: Operand::Global(flag), src: Operand::Const(Value::Bool(true)) });
+                let end = self.next_addr();
+                self.patch(skip, end);
+
+                self.locals.insert(name.clone(), (Operand::Global(global), type_.clone()));
+            }
+            ASTreeNode::VarAssign { name: (name, _name_span), value } => {
+                let src = self.expr(value);
+                let (dst, type_) = self.var(name);
+                self.convert(dst, src, &type_);
+            }
+            // `return;` in a function which returns a value returns its default value
+            ASTreeNode::Return(value) => match value {
+                Some(value) => {
+                    let src = self.expr(value);
+                    let return_type = self.return_type.clone();
+                    self.convert(Operand::Reg(Reg::RET), src, &return_type);
+                    let idx = self.emit(Instr::Goto(0));
+                    self.returns.push(idx);
+                }
+                None => {
+                    let idx = self.emit(Instr::Goto(0));
+                    match self.return_type {
+                        Type::Void => self.returns.push(idx),
+                        _ => self.exits.push(idx),
+                    }
+                }
+            },
+            // (xsMmIfElse)
+            ASTreeNode::IfElse { condition, consequent, alternate } => {
+                let cond = self.expr(condition);
+                let to_else = self.emit(Instr::Ifn { cond, addr: 0 });
+                self.body(consequent);
+                let Some(alternate) = alternate else {
+                    let end = self.next_addr();
+                    self.patch(to_else, end);
+                    return;
+                };
+                let to_end = self.emit(Instr::Goto(0));
+                let else_addr = self.next_addr();
+                self.patch(to_else, else_addr);
+                self.body(alternate);
+                let end = self.next_addr();
+                self.patch(to_end, end);
+            }
+            // (xsMmWhile)
+            ASTreeNode::While { condition, body } => {
+                let counter = self.loop_counter();
+                let head = self.next_addr();
+                let cond = self.expr(condition);
+                let to_end = self.emit(Instr::Ifn { cond, addr: 0 });
+                self.count_iteration(counter);
+                self.loop_body(body, head);
+                self.emit(Instr::Goto(head));
+                let end = self.next_addr();
+                self.patch(to_end, end);
+                self.end_breaks(end);
+            }
+            // `for (X = E1; op E2) { S }` runs as `X = E1; while (X op E2) { S X++; }` with `X--`
+            // for `>` and `>=`. X is a new variable which only exists in the loop, and a
+            // `continue` skips the step (xsMmFor)
+            ASTreeNode::For { var, condition, body } => {
+                let (ASTreeNode::VarAssign { name: (name, _name_span), value }, _span) = var.as_ref() else {
+                    self.emit(Instr::Trap("A `for` loop must start with an assignment".to_string()));
+                    return;
+                };
+                let step_op = match &condition.0 {
+                    Expr::Lt(..) | Expr::Le(..) => BinOp::Add,
+                    _ => BinOp::Sub,
+                };
+
+                let init = self.expr(value);
+                let dst = self.fresh(&name.0);
+                self.convert(dst.clone(), init, &Type::Int);
+                let shadowed = self.locals.insert(name.clone(), (dst.clone(), Type::Int));
+
+                let counter = self.loop_counter();
+                let head = self.next_addr();
+                let cond = self.expr(condition);
+                let to_end = self.emit(Instr::Ifn { cond, addr: 0 });
+                self.count_iteration(counter);
+                self.loop_body(body, head);
+                self.emit(Instr::Binary {
+                    dst: dst.clone(),
+                    op: step_op,
+                    src1: dst,
+                    src2: Operand::Const(Value::Int(1)),
+                });
+                self.emit(Instr::Goto(head));
+                let end = self.next_addr();
+                self.patch(to_end, end);
+                self.end_breaks(end);
+
+                match shadowed {
+                    Some(local) => self.locals.insert(name.clone(), local),
+                    None => self.locals.remove(name),
+                };
+            }
+            // the clause is compared to each case in order, and the body of the first equal case
+            // runs, or the default if none are. Only a `break` leaves a body early (xsMmSwitch)
+            ASTreeNode::Switch { clause, cases } => {
+                let clause_src = self.expr(clause);
+                let clause_val = self.as_int(clause_src);
+
+                let mut to_cases = vec![];
+                for (idx, (case, _body)) in cases.iter().enumerate() {
+                    let Some(case) = case else {
+                        continue;
+                    };
+                    let case_src = self.expr(case);
+                    let case_val = self.as_int(case_src);
+                    let is_ne = self.new_id();
+                    self.emit(Instr::Binary {
+                        dst: is_ne.clone(),
+                        op: BinOp::Ne,
+                        src1: clause_val.clone(),
+                        src2: case_val,
+                    });
+                    to_cases.push((idx, self.emit(Instr::Ifn { cond: is_ne, addr: 0 })));
+                }
+                let to_default = self.emit(Instr::Goto(0));
+
+                self.breaks.push(vec![]);
+                let mut case_addrs = Vec::with_capacity(cases.len());
+                let mut to_end = vec![];
+                for (idx, (_case, body)) in cases.iter().enumerate() {
+                    case_addrs.push(self.next_addr());
+                    self.body(body);
+                    if idx + 1 < cases.len() {
+                        to_end.push(self.emit(Instr::Goto(0)));
+                    }
+                }
+                let end = self.next_addr();
+
+                for (idx, to_case) in to_cases {
+                    self.patch(to_case, case_addrs[idx]);
+                }
+                let default = cases.iter()
+                    .position(|(case, _body)| case.is_none())
+                    .map_or(end, |idx| case_addrs[idx]);
+                self.patch(to_default, default);
+                for idx in to_end {
+                    self.patch(idx, end);
+                }
+                self.end_breaks(end);
+            }
+            ASTreeNode::PostDPlus((name, _name_span)) => self.step(name, BinOp::Add),
+            ASTreeNode::PostDMinus((name, _name_span)) => self.step(name, BinOp::Sub),
+            // a `break` or `continue` outside a loop ends the procedure
+            ASTreeNode::Break => {
+                let idx = self.emit(Instr::Goto(0));
+                match self.breaks.last_mut() {
+                    Some(breaks) => breaks.push(idx),
+                    None => self.exits.push(idx),
+                }
+            }
+            ASTreeNode::Continue => match self.continues.last() {
+                Some(head) => {
+                    let head = *head;
+                    self.emit(Instr::Goto(head));
+                }
+                None => {
+                    let idx = self.emit(Instr::Goto(0));
+                    self.exits.push(idx);
+                }
+            },
+            ASTreeNode::LabelDef((name, _name_span)) => {
+                let addr = self.next_addr();
+                self.labels.insert(name.clone(), addr);
+            }
+            ASTreeNode::Goto(label) => {
+                let idx = self.emit(Instr::Goto(0));
+                self.gotos.push((idx, label.clone()));
+            }
+            // the value of a call is not copied out of `r_ret` when it is discarded
+            ASTreeNode::Discarded((expr, span)) => match expr {
+                Expr::FnCall { name: (name, _name_span), args } => self.call(name, args),
+                expr => {
+                    self.expr(&(expr.clone(), *span));
+                }
+            },
+            // the semantics of `dbg` are unknown
+            ASTreeNode::Debug(_) => {}
+            ASTreeNode::Breakpoint => {
+                self.emit(Instr::Trap("Execution paused at a breakpoint, which cannot be resumed".to_string()));
+            }
+            ASTreeNode::Include(_)
+            | ASTreeNode::InfiniteLoopLimit(_)
+            | ASTreeNode::InfiniteRecursionLimit(_)
+            | ASTreeNode::FnDef { .. }
+            | ASTreeNode::RuleDef { .. }
+            | ASTreeNode::Class { .. } => {
+                self.emit(Instr::Trap("This statement is only allowed at the top level".to_string()));
+            }
+        }
+    }
+
+    /// The value a variable is initialised with, the default value of its type if it has no
+    /// initialiser. `None` if the type has no default value
+    pub(crate) fn init_value(&mut self, type_: &Type, value: Option<&Spanned<Expr>>) -> Option<Operand> {
+        match value {
+            Some(value) => Some(self.expr(value)),
+            None => match Value::default_of(type_) {
+                Some(val) => Some(Operand::Const(val)),
+                None => {
+                    self.emit(Instr::Trap(format!("Cannot declare a variable of type `{}`", type_)));
+                    None
+                }
+            },
+        }
+    }
+
+    /// `src` converted to an `int`, like the clause and cases of a `switch` are
+    fn as_int(&mut self, src: Operand) -> Operand {
+        if let Operand::Const(val) = &src {
+            if let Some(val) = val.clone().convert(&Type::Int) {
+                return Operand::Const(val);
+            }
+        }
+        let dst = self.new_id();
+        self.convert(dst.clone(), src, &Type::Int);
+        dst
+    }
+
+    /// `X++` and `X--`
+    fn step(&mut self, name: &Identifier, op: BinOp) {
+        let (dst, _type) = self.var(name);
+        self.emit(Instr::Binary { dst: dst.clone(), op, src1: dst, src2: Operand::Const(Value::Int(1)) });
+    }
+
+    /// The body of a loop, where a `continue` jumps back to `head`
+    fn loop_body(&mut self, body: &Spanned<Body>, head: usize) {
+        self.breaks.push(vec![]);
+        self.continues.push(head);
+        self.body(body);
+        self.continues.pop();
+    }
+
+    /// Points the `break`s of the innermost loop or `switch` to `end`
+    fn end_breaks(&mut self, end: usize) {
+        for idx in self.breaks.pop().unwrap_or_default() {
+            self.patch(idx, end);
+        }
+    }
+
+    /// When the script sets an `infiniteLoopLimit`, every loop counts its iterations
+    fn loop_counter(&mut self) -> Option<Operand> {
+        self.loop_limit?;
+        let counter = self.new_id();
+        self.emit(Instr::Copy { dst: counter.clone(), src: Operand::Const(Value::Int(0)) });
+        Some(counter)
+    }
+
+    fn count_iteration(&mut self, counter: Option<Operand>) {
+        let (Some(counter), Some(limit)) = (counter, self.loop_limit) else {
+            return;
+        };
+        self.emit(Instr::Binary {
+            dst: counter.clone(),
+            op: BinOp::Add,
+            src1: counter.clone(),
+            src2: Operand::Const(Value::Int(1)),
+        });
+        let is_over = self.new_id();
+        self.emit(Instr::Binary {
+            dst: is_over.clone(),
+            op: BinOp::Gt,
+            src1: counter,
+            src2: Operand::Const(Value::Int(limit as i32)),
+        });
+        let after_trap = self.next_addr() + 2;
+        self.emit(Instr::Ifn { cond: is_over, addr: after_trap });
+        self.emit(Instr::Trap(format!("This loop exceeded the limit of {} iterations", limit)));
+    }
+}
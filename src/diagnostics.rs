@@ -0,0 +1,118 @@
+pub mod github;
+pub mod json;
+pub mod sarif;
+
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+use ariadne::{Color, Config, IndexType, Label, Report, ReportKind, Source};
+use crate::dynamic::runtime_error::RuntimeError;
+use crate::r#static::module_loader::ModuleLoader;
+use crate::r#static::xs_error::XSError;
+
+/// The path of a module as it should be shown to the user, relative to the working directory
+/// whenever possible
+pub fn display_path(path: &Path) -> String {
+    env::current_dir().ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or(path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// Writes a report for each error, showing the offending source lines with the primary span and
+/// any related spans underlined
+pub fn render(
+    loader: &ModuleLoader,
+    errs: &[XSError],
+    color: bool,
+    mut w: impl Write,
+) -> io::Result<()> {
+    let config = Config::default()
+        .with_color(color)
+        .with_index_type(IndexType::Byte);
+
+    for file_errs in errs.chunk_by(|err1, err2| err1.file_id == err2.file_id) {
+        let module = loader.module(file_errs[0].file_id);
+        let name = display_path(&module.path);
+        let mut src = (name.as_str(), Source::from(module.src.as_str()));
+
+        for err in file_errs {
+            let (kind, color) = if err.is_warning() {
+                (ReportKind::Warning, Color::Yellow)
+            } else {
+                (ReportKind::Error, Color::Red)
+            };
+
+            let mut report = Report::build(kind, (name.as_str(), err.span.into_range()))
+                .with_config(config)
+                .with_code(err.code)
+                .with_message(&err.msg)
+                .with_label(
+                    Label::new((name.as_str(), err.span.into_range()))
+                        .with_message(err.code.name())
+                        .with_color(color)
+                )
+                .with_labels(err.related.iter().map(|(label, span)| {
+                    Label::new((name.as_str(), span.into_range()))
+                        .with_message(label)
+                        .with_color(Color::Blue)
+                }));
+            report.with_notes(&err.notes);
+
+            report.finish().write(&mut src, &mut w)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a report for an error which stopped a script, followed by the calls which led to it
+pub fn render_runtime_error(
+    loader: &ModuleLoader,
+    err: &RuntimeError,
+    color: bool,
+    mut w: impl Write,
+) -> io::Result<()> {
+    let config = Config::default()
+        .with_color(color)
+        .with_index_type(IndexType::Byte);
+
+    let module = loader.module(err.file_id);
+    let name = display_path(&module.path);
+
+    let mut report = Report::build(ReportKind::Error, (name.as_str(), err.span.into_range()))
+        .with_config(config)
+        .with_message(&err.msg)
+        .with_label(
+            Label::new((name.as_str(), err.span.into_range()))
+                .with_message("the script stopped here")
+                .with_color(Color::Red)
+        );
+    // recursive calls are only written once
+    report.with_notes(err.trace.chunk_by(|call1, call2| call1 == call2).map(|calls| {
+        let (fn_name, file_id, span) = &calls[0];
+        let caller = loader.module(*file_id);
+        let (line, col) = caller.line_col(span.start);
+        let repeats = if calls.len() > 1 { format!(" ({} times)", calls.len()) } else { String::new() };
+        format!("in `{}`, called from {}:{}:{}{}", fn_name.0, display_path(&caller.path), line, col, repeats)
+    }));
+
+    report.finish().write((name.as_str(), Source::from(module.src.as_str())), &mut w)
+}
+
+/// e.g. `2 errors, 1 warning in 3 files`
+pub fn summary(errs: &[XSError], num_files: usize) -> String {
+    fn plural(n: usize, word: &str) -> String {
+        format!("{} {}{}", n, word, if n == 1 { "" } else { "s" })
+    }
+
+    let num_warnings = errs.iter().filter(|err| err.is_warning()).count();
+
+    format!(
+        "{}, {} in {}",
+        plural(errs.len() - num_warnings, "error"),
+        plural(num_warnings, "warning"),
+        plural(num_files, "file"),
+    )
+}
@@ -0,0 +1,42 @@
+use std::io::{self, Write};
+use crate::diagnostics::display_path;
+use crate::r#static::module_loader::ModuleLoader;
+use crate::r#static::xs_error::{Severity, XSError};
+
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(prop: &str) -> String {
+    escape_data(prop)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// Writes each error as a GitHub Actions workflow command, so that it is shown as an annotation
+/// on the offending lines
+pub fn write_github(loader: &ModuleLoader, errs: &[XSError], mut w: impl Write) -> io::Result<()> {
+    for err in errs {
+        let module = loader.module(err.file_id);
+        let (line, col) = module.line_col(err.span.start);
+        let (end_line, end_col) = module.line_col(err.span.end);
+        let cmd = match err.severity {
+            Severity::Warning => "warning",
+            Severity::Error   => "error",
+        };
+
+        writeln!(
+            w,
+            "::{} file={},line={},col={},endLine={},endColumn={},title={}::{}",
+            cmd,
+            escape_property(&display_path(&module.path)),
+            line, col, end_line, end_col,
+            escape_property(&format!("{} {}", err.code, err.code.name())),
+            escape_data(&err.msg),
+        )?;
+    }
+
+    Ok(())
+}
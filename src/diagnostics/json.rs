@@ -0,0 +1,99 @@
+use std::io::{self, Write};
+use serde::Serialize;
+use crate::diagnostics::display_path;
+use crate::parsing::span::Span;
+use crate::r#static::module_loader::{Module, ModuleLoader};
+use crate::r#static::xs_error::{Code, Severity, XSError};
+
+/// The version of the JSON output schema documented in `docs/json_output.md`. Fields may be added
+/// within a version, but existing fields are never removed, renamed or given a different meaning
+/// without incrementing it
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct ByteSpan {
+    start: usize,
+    end: usize,
+}
+
+/// 1-based, columns are counted in chars
+#[derive(Serialize)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+#[derive(Serialize)]
+struct Range {
+    start: Position,
+    end: Position,
+}
+
+#[derive(Serialize)]
+struct Location {
+    file: String,
+    span: ByteSpan,
+    range: Range,
+}
+
+impl Location {
+    fn new(module: &Module, span: &Span) -> Self {
+        let (start_line, start_col) = module.line_col(span.start);
+        let (end_line, end_col) = module.line_col(span.end);
+
+        Location {
+            file: display_path(&module.path),
+            span: ByteSpan { start: span.start, end: span.end },
+            range: Range {
+                start: Position { line: start_line, column: start_col },
+                end: Position { line: end_line, column: end_col },
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Related<'a> {
+    message: &'a str,
+    #[serde(flatten)]
+    location: Location,
+}
+
+#[derive(Serialize)]
+struct Diagnostic<'a> {
+    #[serde(flatten)]
+    location: Location,
+    severity: Severity,
+    code: Code,
+    message: &'a str,
+    related: Vec<Related<'a>>,
+    notes: &'a [String],
+}
+
+#[derive(Serialize)]
+struct Output<'a> {
+    version: u32,
+    diagnostics: Vec<Diagnostic<'a>>,
+}
+
+/// Writes all the errors as a single JSON document
+pub fn write_json(loader: &ModuleLoader, errs: &[XSError], mut w: impl Write) -> io::Result<()> {
+    let diagnostics = errs.iter()
+        .map(|err| {
+            let module = loader.module(err.file_id);
+            Diagnostic {
+                location: Location::new(module, &err.span),
+                severity: err.severity,
+                code: err.code,
+                message: &err.msg,
+                related: err.related.iter()
+                    .map(|(message, span)| Related { message, location: Location::new(module, span) })
+                    .collect(),
+                notes: &err.notes,
+            }
+        })
+        .collect();
+
+    serde_json::to_writer_pretty(&mut w, &Output { version: SCHEMA_VERSION, diagnostics })?;
+    writeln!(w)
+}
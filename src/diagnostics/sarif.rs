@@ -0,0 +1,87 @@
+use std::io::{self, Write};
+use serde_json::{json, Value};
+use crate::diagnostics::display_path;
+use crate::parsing::span::Span;
+use crate::r#static::module_loader::{Module, ModuleLoader};
+use crate::r#static::xs_error::{Code, Severity, XSError};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "warning",
+        Severity::Error   => "error",
+    }
+}
+
+fn location(module: &Module, span: &Span) -> Value {
+    let (start_line, start_col) = module.line_col(span.start);
+    let (end_line, end_col) = module.line_col(span.end);
+
+    json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": display_path(&module.path).replace('\\', "/") },
+            "region": {
+                "startLine": start_line,
+                "startColumn": start_col,
+                "endLine": end_line,
+                "endColumn": end_col,
+                "byteOffset": span.start,
+                "byteLength": span.end - span.start,
+            },
+        },
+    })
+}
+
+/// Writes all the errors as a SARIF 2.1.0 log with a single run
+pub fn write_sarif(loader: &ModuleLoader, errs: &[XSError], mut w: impl Write) -> io::Result<()> {
+    let rules = Code::ALL.iter()
+        .map(|code| json!({
+            "id": code.id(),
+            "name": code.name(),
+            "shortDescription": { "text": code.name() },
+            "defaultConfiguration": { "level": level(code.severity()) },
+        }))
+        .collect::<Vec<_>>();
+
+    let results = errs.iter()
+        .map(|err| {
+            let module = loader.module(err.file_id);
+            json!({
+                "ruleId": err.code.id(),
+                "ruleIndex": Code::ALL.iter().position(|code| *code == err.code),
+                "level": level(err.severity),
+                "message": { "text": err.msg },
+                "locations": [location(module, &err.span)],
+                "relatedLocations": err.related.iter()
+                    .enumerate()
+                    .map(|(idx, (label, span))| {
+                        let mut loc = location(module, span);
+                        loc["id"] = json!(idx);
+                        loc["message"] = json!({ "text": label });
+                        loc
+                    })
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let log = json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://divy1211.github.io/xs-check/",
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    });
+
+    serde_json::to_writer_pretty(&mut w, &log)?;
+    writeln!(w)
+}
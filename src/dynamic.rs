@@ -0,0 +1,7 @@
+pub mod value;
+pub mod runtime_error;
+pub mod interpreter;
+pub mod expression;
+pub mod statement;
+pub mod builtins;
+pub mod scheduler;
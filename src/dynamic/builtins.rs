@@ -0,0 +1,182 @@
+use crate::dynamic::interpreter::{Array, Interpreter};
+use crate::dynamic::runtime_error::RuntimeError;
+use crate::dynamic::scheduler::Scheduler;
+use crate::dynamic::value::{fmt_float, Value};
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::Span;
+
+/// `xsGetRandomNumber` returns a number in `[0, RAND_MAX)`
+const RAND_MAX: i32 = 32767;
+
+impl Interpreter<'_> {
+    /// Runs a builtin function. Only builtins which do not depend on the state of a game are
+    /// available, calling any other builtin stops the script. The rule builtins are run by the
+    /// [Scheduler]
+    pub(crate) fn builtin(
+        &mut self,
+        name: &Identifier,
+        args: Vec<Value>,
+        span: &Span,
+    ) -> Result<Option<Value>, RuntimeError> {
+        let Some(Type::Func { type_sign, is_variadic, .. }) = self.builtins.get(name).cloned() else {
+            return Err(self.error(&format!("Undefined function `{}`", name.0), span));
+        };
+        let num_params = type_sign.len() - 1;
+        if args.len() < num_params || (!is_variadic && args.len() > num_params) {
+            return Err(self.error(&format!(
+                "Function `{}` takes {} arguments, but {} were given", name.0, num_params, args.len()
+            ), span));
+        }
+
+        let mut params = Vec::with_capacity(args.len());
+        for (idx, arg) in args.into_iter().enumerate() {
+            match type_sign.get(idx).filter(|_| idx < num_params) {
+                Some(type_) => params.push(arg.convert(type_).ok_or_else(|| self.error(
+                    &format!("Argument {} of `{}` must be of type `{}`", idx + 1, name.0, type_), span
+                ))?),
+                None => params.push(arg),
+            }
+        }
+
+        if Scheduler::builtin_params(&name.0).is_some() {
+            return self.scheduler.builtin(&name.0, &params).map_err(|msg| self.error(&msg, span));
+        }
+
+        use Value::{Float, Int, Str};
+        let value = match (name.0.as_str(), params.as_slice()) {
+            ("xsChatData", [Str(fmt), fmt_args @ ..]) => {
+                let msg = format_chat(fmt, fmt_args);
+                writeln!(self.out, "{}", msg)
+                    .map_err(|err| self.error(&format!("Cannot write chat message: {}", err), span))?;
+                return Ok(None);
+            }
+            ("xsGetContextPlayer", []) => Int(self.context_player),
+            ("xsSetContextPlayer", [Int(player)]) => {
+                self.context_player = *player;
+                return Ok(None);
+            }
+            ("xsGetTime", []) => Int(self.scheduler.time),
+            ("xsGetGameTime", []) => Int(self.scheduler.time / 1000),
+
+            ("xsGetRandomNumber", []) => Int(self.random(RAND_MAX)),
+            ("xsGetRandomNumberMax", [Int(max)]) => Int(self.random(*max)),
+            ("xsGetRandomNumberLH", [Int(low), Int(high)]) => Int(low + self.random(high - low)),
+
+            ("xsArrayCreateInt" | "xsArrayCreateFloat" | "xsArrayCreateBool"
+            | "xsArrayCreateString" | "xsArrayCreateVector", [Int(size), default, Str(_name)]) => {
+                let size = self.array_idx(*size, span)?;
+                self.arrays.push(Array { default: default.clone(), values: vec![default.clone(); size] });
+                Int(self.arrays.len() as i32 - 1)
+            }
+            ("xsArraySetInt" | "xsArraySetFloat" | "xsArraySetBool"
+            | "xsArraySetString" | "xsArraySetVector", [Int(id), Int(idx), value]) => {
+                *self.array_slot(*id, *idx, value.type_(), span)? = value.clone();
+                Int(1)
+            }
+            ("xsArrayGetInt", [Int(id), Int(idx)]) => self.array_slot(*id, *idx, Type::Int, span)?.clone(),
+            ("xsArrayGetFloat", [Int(id), Int(idx)]) => self.array_slot(*id, *idx, Type::Float, span)?.clone(),
+            ("xsArrayGetBool", [Int(id), Int(idx)]) => self.array_slot(*id, *idx, Type::Bool, span)?.clone(),
+            ("xsArrayGetString", [Int(id), Int(idx)]) => self.array_slot(*id, *idx, Type::Str, span)?.clone(),
+            ("xsArrayGetVector", [Int(id), Int(idx)]) => self.array_slot(*id, *idx, Type::Vec, span)?.clone(),
+            ("xsArrayResizeInt" | "xsArrayResizeFloat" | "xsArrayResizeBool"
+            | "xsArrayResizeString" | "xsArrayResizeVector", [Int(id), Int(size)]) => {
+                let size = self.array_idx(*size, span)?;
+                let array = self.array(*id, span)?;
+                array.values.resize(size, array.default.clone());
+                Int(1)
+            }
+            ("xsArrayGetSize", [Int(id)]) => Int(self.array(*id, span)?.values.len() as i32),
+
+            ("xsVectorGetX", [Value::Vec([x, _, _])]) => Float(*x),
+            ("xsVectorGetY", [Value::Vec([_, y, _])]) => Float(*y),
+            ("xsVectorGetZ", [Value::Vec([_, _, z])]) => Float(*z),
+            ("xsVectorSetX", [Value::Vec([_, y, z]), Float(x)]) => Value::Vec([*x, *y, *z]),
+            ("xsVectorSetY", [Value::Vec([x, _, z]), Float(y)]) => Value::Vec([*x, *y, *z]),
+            ("xsVectorSetZ", [Value::Vec([x, y, _]), Float(z)]) => Value::Vec([*x, *y, *z]),
+            ("xsVectorSet", [Float(x), Float(y), Float(z)]) => Value::Vec([*x, *y, *z]),
+            ("xsVectorLength", [Value::Vec([x, y, z])]) => Float((x * x + y * y + z * z).sqrt()),
+            ("xsVectorNormalize", [Value::Vec([x, y, z])]) => {
+                let len = (x * x + y * y + z * z).sqrt();
+                if len == 0.0 { Value::Vec([*x, *y, *z]) } else { Value::Vec([x / len, y / len, z / len]) }
+            }
+
+            (name, _) => {
+                return Err(self.error(&format!("`{}` is not available outside of a game", name), span));
+            }
+        };
+
+        Ok(Some(value))
+    }
+
+    /// A pseudo random number in `[0, max)`, the same sequence is produced every run
+    fn random(&mut self, max: i32) -> i32 {
+        // xorshift32
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+
+        if max <= 0 { 0 } else { (self.rng % max as u32) as i32 }
+    }
+
+    fn array_idx(&self, size: i32, span: &Span) -> Result<usize, RuntimeError> {
+        usize::try_from(size).map_err(|_| self.error(&format!("Invalid array size {}", size), span))
+    }
+
+    fn array(&mut self, id: i32, span: &Span) -> Result<&mut Array, RuntimeError> {
+        let err = self.error(&format!("No array with ID {}", id), span);
+        usize::try_from(id).ok()
+            .and_then(|id| self.arrays.get_mut(id))
+            .ok_or(err)
+    }
+
+    /// The element at `idx` of an array of `type_`s
+    fn array_slot(&mut self, id: i32, idx: i32, type_: Type, span: &Span) -> Result<&mut Value, RuntimeError> {
+        let type_err = self.error(&format!("Array {} is not an array of type `{}`", id, type_), span);
+        let idx_err = self.error(&format!("Index {} is out of bounds for array {}", idx, id), span);
+
+        let array = self.array(id, span)?;
+        if array.default.type_() != type_ {
+            return Err(type_err);
+        }
+        usize::try_from(idx).ok()
+            .and_then(|idx| array.values.get_mut(idx))
+            .ok_or(idx_err)
+    }
+}
+
+/// Replaces each format specifier in `fmt` with the next format argument. `%d` writes an `int`,
+/// `%f` a `float` and any other specifier writes the argument as it would be concatenated to a
+/// string. `%%` writes a `%`
+fn format_chat(fmt: &str, args: &[Value]) -> String {
+    let mut msg = String::with_capacity(fmt.len());
+    let mut args = args.iter();
+    let mut chars = fmt.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            msg.push(c);
+            continue;
+        }
+        match chars.peek().copied() {
+            Some('%') => {
+                chars.next();
+                msg.push('%');
+            }
+            Some(spec) if spec.is_ascii_alphabetic() => {
+                chars.next();
+                let Some(arg) = args.next() else {
+                    continue;
+                };
+                let formatted = match (spec, arg.clone().convert(&Type::Float)) {
+                    ('d', _) => arg.as_int().map(|val| val.to_string()),
+                    ('f', Some(Value::Float(val))) => Some(fmt_float(val)),
+                    _ => None,
+                };
+                msg.push_str(&formatted.unwrap_or_else(|| arg.to_string()));
+            }
+            _ => msg.push('%'),
+        }
+    }
+
+    msg
+}
@@ -0,0 +1,154 @@
+use std::cmp::Ordering;
+use crate::dynamic::interpreter::Interpreter;
+use crate::dynamic::runtime_error::RuntimeError;
+use crate::dynamic::value::{arith, compare, equals, ArithOp, Value};
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::{Span, Spanned};
+
+impl Interpreter<'_> {
+    pub(crate) fn expr(&mut self, spanned_expr: &Spanned<Expr>) -> Result<Value, RuntimeError> {
+        let (expr, span) = spanned_expr;
+        match expr {
+            Expr::Literal(lit) => Ok(Value::from_lit(lit)),
+            Expr::Identifier(name) => self.lookup(name, span).cloned(),
+            Expr::Paren(expr) => self.expr(expr),
+            Expr::Vec { x, y, z } => Ok(Value::Vec([self.float(x)?, self.float(y)?, self.float(z)?])),
+            Expr::Member { expr, member: (member, _member_span) } => {
+                match (self.expr(expr)?, member.0.as_str()) {
+                    (Value::Vec([x, _, _]), "x") => Ok(Value::Float(x)),
+                    (Value::Vec([_, y, _]), "y") => Ok(Value::Float(y)),
+                    (Value::Vec([_, _, z]), "z") => Ok(Value::Float(z)),
+                    (value, member) => Err(self.error(&format!(
+                        "Type `{}` has no member `{}`", value.type_(), member
+                    ), span)),
+                }
+            }
+            Expr::FnCall { name: (name, _name_span), .. } => self.call_expr(spanned_expr)?
+                .ok_or_else(|| self.error(&format!("Function `{}` does not return a value", name.0), span)),
+
+            Expr::Neg(expr) => match self.expr(expr)? {
+                Value::Int(val) => Ok(Value::Int(val.wrapping_neg())),
+                Value::Float(val) => Ok(Value::Float(-val)),
+                value => Err(self.error(&format!("Cannot negate a value of type `{}`", value.type_()), span)),
+            },
+            Expr::Not(expr) => match self.expr(expr)? {
+                Value::Bool(val) => Ok(Value::Bool(!val)),
+                value => Err(self.error(&format!("Cannot negate a value of type `{}`", value.type_()), span)),
+            },
+
+            Expr::Star(expr1, expr2) => self.arith(ArithOp::Mul, expr1, expr2, span),
+            Expr::FSlash(expr1, expr2) => self.arith(ArithOp::Div, expr1, expr2, span),
+            Expr::PCent(expr1, expr2) => self.arith(ArithOp::Rem, expr1, expr2, span),
+            Expr::Plus(expr1, expr2) => self.arith(ArithOp::Add, expr1, expr2, span),
+            Expr::Minus(expr1, expr2) => self.arith(ArithOp::Sub, expr1, expr2, span),
+
+            Expr::Lt(expr1, expr2) => self.reln(expr1, expr2, span, Ordering::is_lt),
+            Expr::Gt(expr1, expr2) => self.reln(expr1, expr2, span, Ordering::is_gt),
+            Expr::Le(expr1, expr2) => self.reln(expr1, expr2, span, Ordering::is_le),
+            Expr::Ge(expr1, expr2) => self.reln(expr1, expr2, span, Ordering::is_ge),
+            Expr::Eq(expr1, expr2) => self.eq(expr1, expr2, span).map(Value::Bool),
+            Expr::Ne(expr1, expr2) => self.eq(expr1, expr2, span).map(|eq| Value::Bool(!eq)),
+
+            Expr::And(expr1, expr2) => self.logical(expr1, expr2, span, |val1, val2| val1 && val2),
+            Expr::Or(expr1, expr2) => self.logical(expr1, expr2, span, |val1, val2| val1 || val2),
+        }
+    }
+
+    /// Calls a user defined or builtin function, returning nothing for `void` functions
+    /// (xsBssFncExpr)
+    pub(crate) fn call_expr(&mut self, (expr, span): &Spanned<Expr>) -> Result<Option<Value>, RuntimeError> {
+        let Expr::FnCall { name: (name, name_span), args: arg_exprs } = expr else {
+            self.expr(&(expr.clone(), *span))?;
+            return Ok(None);
+        };
+
+        let param_types = match self.fns.get(name) {
+            Some(callable) => callable.params.iter().map(|param| param.type_.clone()).collect(),
+            None => match self.builtins.get(name) {
+                Some(Type::Func { type_sign, .. }) => type_sign[..type_sign.len() - 1].to_vec(),
+                _ => return Err(self.error(&format!("Undefined function `{}`", name.0), name_span)),
+            },
+        };
+
+        let mut args = Vec::with_capacity(arg_exprs.len());
+        for (idx, arg_expr) in arg_exprs.iter().enumerate() {
+            let value = self.expr(arg_expr)?;
+            args.push(match param_types.get(idx) {
+                Some(Type::Float) => no_float_promotion(value),
+                _ => value,
+            });
+        }
+
+        match self.fns.get(name).cloned() {
+            Some(callable) => self.call_fn(callable, args, span),
+            None => self.builtin(name, args, span),
+        }
+    }
+
+    fn float(&mut self, expr: &Spanned<Expr>) -> Result<f32, RuntimeError> {
+        match self.expr(expr)?.convert(&Type::Float) {
+            Some(Value::Float(val)) => Ok(val),
+            _ => Err(self.error("Expected a value of type `float`", &expr.1)),
+        }
+    }
+
+    /// Both operands are evaluated before the operation, see [`arith`]
+    fn arith(
+        &mut self,
+        op: ArithOp,
+        expr1: &Spanned<Expr>,
+        expr2: &Spanned<Expr>,
+        span: &Span,
+    ) -> Result<Value, RuntimeError> {
+        let (val1, val2) = (self.expr(expr1)?, self.expr(expr2)?);
+        arith(op, val1, val2).map_err(|msg| self.error(&msg, span))
+    }
+
+    /// Comparing `vector`s or `bool`s with anything but `==` and `!=` crashes XS, so it stops the
+    /// script here
+    fn reln(
+        &mut self,
+        expr1: &Spanned<Expr>,
+        expr2: &Spanned<Expr>,
+        span: &Span,
+        test: fn(Ordering) -> bool,
+    ) -> Result<Value, RuntimeError> {
+        let (val1, val2) = (self.expr(expr1)?, self.expr(expr2)?);
+        let ordering = compare(&val1, &val2).map_err(|msg| self.error(&msg, span))?;
+
+        // comparisons with NaN are always false
+        Ok(Value::Bool(ordering.is_some_and(test)))
+    }
+
+    fn eq(&mut self, expr1: &Spanned<Expr>, expr2: &Spanned<Expr>, span: &Span) -> Result<bool, RuntimeError> {
+        let (val1, val2) = (self.expr(expr1)?, self.expr(expr2)?);
+        equals(&val1, &val2).map_err(|msg| self.error(&msg, span))
+    }
+
+    /// Both operands are always evaluated (xsBssOp), XS does not short circuit `&&` and `||`
+    fn logical(
+        &mut self,
+        expr1: &Spanned<Expr>,
+        expr2: &Spanned<Expr>,
+        span: &Span,
+        op: fn(bool, bool) -> bool,
+    ) -> Result<Value, RuntimeError> {
+        match (self.expr(expr1)?, self.expr(expr2)?) {
+            (Value::Bool(val1), Value::Bool(val2)) => Ok(Value::Bool(op(val1, val2))),
+            (val1, val2) => Err(self.error(&format!(
+                "Cannot use a logical operator on types `{}` and `{}`", val1.type_(), val2.type_()
+            ), span)),
+        }
+    }
+}
+
+/// `int` and `bool` values passed to a `float` parameter are not converted, the parameter
+/// reinterprets their bits as a `float` instead
+pub(crate) fn no_float_promotion(value: Value) -> Value {
+    match value {
+        Value::Int(val) => Value::Float(f32::from_bits(val as u32)),
+        Value::Bool(val) => Value::Float(f32::from_bits(val as u32)),
+        value => value,
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
+use std::rc::Rc;
+use crate::dynamic::runtime_error::RuntimeError;
+use crate::dynamic::scheduler::Scheduler;
+use crate::dynamic::value::Value;
+use crate::parsing::ast::astree::{ASTreeNode, Body};
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::param::Param;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::module_loader::{FileId, Module, ModuleLoader};
+use crate::r#static::prelude::{consts, prelude, PreludeVersion};
+use crate::r#static::type_check::TypeEnv;
+
+/// A user defined function or rule. Running a rule is the same as calling a `void` function with
+/// no parameters
+#[derive(Debug)]
+pub struct Callable {
+    pub name: Identifier,
+    pub file_id: FileId,
+    pub params: Vec<Param>,
+    pub return_type: Type,
+    pub body: Spanned<Body>,
+}
+
+/// The local variables of a running function
+#[derive(Debug)]
+pub struct Frame {
+    pub name: Identifier,
+    pub locals: HashMap<Identifier, Value>,
+    /// locals declared `static`, which are written back when the function returns
+    pub statics: Vec<Identifier>,
+}
+
+/// How a statement finished running
+#[derive(Debug)]
+pub enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Option<Value>),
+    Goto(Spanned<Identifier>),
+}
+
+/// An XS array created by one of the `xsArrayCreate` builtins
+#[derive(Debug)]
+pub struct Array {
+    pub default: Value,
+    pub values: Vec<Value>,
+}
+
+/// Runs XS scripts following the big step semantics in `maths/docs/dynamic/xs_op_sem.md`. Scripts
+/// are assumed to be well typed, anything the type checker would reject is reported as a
+/// [RuntimeError] when it is run
+pub struct Interpreter<'out> {
+    pub(crate) builtins: TypeEnv,
+    pub(crate) globals: HashMap<Identifier, Value>,
+    pub(crate) fns: HashMap<Identifier, Rc<Callable>>,
+    pub(crate) rules: HashMap<Identifier, Rc<Callable>>,
+    /// when the rules run, and the game clock they run on
+    pub scheduler: Scheduler,
+    /// the values of `static` locals, by function and variable name
+    pub(crate) statics: HashMap<(Identifier, Identifier), Value>,
+    pub(crate) frames: Vec<Frame>,
+    /// the module whose code is currently running
+    pub(crate) file_id: FileId,
+    included: HashSet<PathBuf>,
+
+    pub(crate) arrays: Vec<Array>,
+    pub(crate) context_player: i32,
+    pub(crate) rng: u32,
+    /// where `xsChatData` messages are written, one per line
+    pub(crate) out: &'out mut dyn Write,
+
+    /// the number of times a single loop may repeat before the script is stopped
+    pub loop_limit: Option<usize>,
+    /// how deep function calls may be nested before the script is stopped
+    pub recursion_limit: usize,
+    /// the number of statements and loop iterations a call made by [Interpreter::load] or
+    /// [Interpreter::call] may run before the script is stopped, as a script which never finishes
+    /// would otherwise hang
+    pub max_steps: Option<u64>,
+    /// the number of statements and loop iterations run since the last call from outside the script
+    pub(crate) steps: u64,
+}
+
+impl<'out> Interpreter<'out> {
+    pub fn new(version: PreludeVersion, out: &'out mut dyn Write) -> Self {
+        let (consts, vec_consts) = consts(version);
+        let globals = consts.iter()
+            .map(|(name, lit)| (Identifier::new(name), Value::from_lit(lit)))
+            .chain(vec_consts.iter().map(|(name, [x, y, z])| {
+                (Identifier::new(name), Value::Vec([*x as f32, *y as f32, *z as f32]))
+            }))
+            .collect();
+
+        Interpreter {
+            builtins: prelude(version),
+            globals,
+            fns: HashMap::new(),
+            rules: HashMap::new(),
+            scheduler: Scheduler::default(),
+            statics: HashMap::new(),
+            frames: vec![],
+            file_id: FileId::default(),
+            included: HashSet::new(),
+            arrays: vec![],
+            context_player: 0,
+            rng: 1,
+            out,
+            loop_limit: None,
+            recursion_limit: 256,
+            max_steps: None,
+            steps: 0,
+        }
+    }
+
+    /// Runs the top level of a module and every module it includes, defining its global
+    /// variables, functions and rules. Each module is only run once
+    pub fn load(&mut self, module: Rc<Module>, loader: &mut ModuleLoader) -> Result<(), RuntimeError> {
+        if !self.included.insert(module.path.clone()) {
+            return Ok(());
+        }
+        let outer_file_id = self.file_id;
+        self.file_id = module.id;
+        self.steps = 0;
+        let result = self.run_top_level(&module, loader);
+        self.file_id = outer_file_id;
+
+        result
+    }
+
+    fn run_top_level(&mut self, module: &Module, loader: &mut ModuleLoader) -> Result<(), RuntimeError> {
+        for spanned_stmt in module.ast.iter() {
+            let (ASTreeNode::Include((inc_path, inc_span)), _span) = spanned_stmt else {
+                self.top_level_stmt(spanned_stmt)?;
+                continue;
+            };
+
+            let Some(inc_module) = loader.resolve(inc_path, &module.path)
+                .and_then(|path| loader.load(&path).ok()) else {
+                return Err(self.error(&format!("Cannot find included file `{}`", inc_path), inc_span));
+            };
+            self.load(inc_module, loader)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether a function or rule named `name` has been defined
+    pub fn is_callable(&self, name: &str) -> bool {
+        let name = Identifier::new(name);
+        self.fns.contains_key(&name) || self.rules.contains_key(&name)
+    }
+
+    /// Calls a function or runs a rule by name, returning the function's return value.
+    /// Parameters which are not given take their default values
+    pub fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+        let name = Identifier::new(name);
+        let callable = self.fns.get(&name)
+            .or_else(|| self.rules.get(&name))
+            .cloned()
+            .ok_or_else(|| RuntimeError::new(
+                &format!("Undefined function or rule `{}`", name.0), &Span::new(0, 0), self.file_id
+            ))?;
+        let span = callable.body.1;
+
+        self.scheduler.enter(&name.0);
+        self.steps = 0;
+        let result = self.call_fn(callable, args, &span);
+        self.scheduler.current = None;
+
+        // the call is not made from a script, so it has no call site to trace
+        result.map_err(|mut err| {
+            err.trace.pop();
+            err
+        })
+    }
+
+    pub(crate) fn error(&self, msg: &str, span: &Span) -> RuntimeError {
+        RuntimeError::new(msg, span, self.file_id)
+    }
+
+    pub(crate) fn lookup(&self, name: &Identifier, span: &Span) -> Result<&Value, RuntimeError> {
+        self.frames.last()
+            .and_then(|frame| frame.locals.get(name))
+            .or_else(|| self.globals.get(name))
+            .ok_or_else(|| self.error(&format!("Undefined name `{}`", name.0), span))
+    }
+
+    /// Assigns to a local or global variable, converting the value to the variable's type
+    pub(crate) fn assign(&mut self, name: &Identifier, value: Value, span: &Span) -> Result<(), RuntimeError> {
+        let type_ = self.lookup(name, span)?.type_();
+        let Some(value) = value.convert(&type_) else {
+            return Err(self.error(&format!("Cannot assign a value of this type to a `{}`", type_), span));
+        };
+
+        let slot = match self.frames.last_mut().and_then(|frame| frame.locals.get_mut(name)) {
+            Some(slot) => slot,
+            None => self.globals.get_mut(name).expect("checked by lookup"),
+        };
+        *slot = value;
+
+        Ok(())
+    }
+
+    /// Binds the arguments of a call to the parameters of `callable` and runs its body
+    pub(crate) fn call_fn(
+        &mut self,
+        callable: Rc<Callable>,
+        args: Vec<Value>,
+        call_span: &Span,
+    ) -> Result<Option<Value>, RuntimeError> {
+        if self.frames.len() >= self.recursion_limit {
+            return Err(self.error(
+                &format!("Exceeded the maximum call depth of {}", self.recursion_limit), call_span
+            ));
+        }
+        if args.len() > callable.params.len() {
+            return Err(self.error(&format!(
+                "Function `{}` takes {} arguments, but {} were given",
+                callable.name.0, callable.params.len(), args.len(),
+            ), call_span));
+        }
+
+        let caller_file_id = self.file_id;
+        self.file_id = callable.file_id;
+        let result = self.run_callable(&callable, args);
+        self.file_id = caller_file_id;
+
+        result.map_err(|mut err| {
+            err.trace.push((callable.name.clone(), caller_file_id, *call_span));
+            err
+        })
+    }
+
+    fn run_callable(&mut self, callable: &Callable, args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+        let mut locals = HashMap::with_capacity(callable.params.len());
+        let mut args = args.into_iter();
+        for param in callable.params.iter() {
+            let value = match args.next() {
+                Some(value) => value,
+                None => self.expr(&param.default)?,
+            };
+            let Some(value) = value.convert(&param.type_) else {
+                return Err(self.error(
+                    &format!("Expected a value of type `{}`", param.type_), &param.name.1
+                ));
+            };
+            locals.insert(param.name.0.clone(), value);
+        }
+
+        self.frames.push(Frame { name: callable.name.clone(), locals, statics: vec![] });
+        let flow = self.body(&callable.body);
+        let frame = self.frames.pop().expect("pushed above");
+        for name in frame.statics {
+            if let Some(value) = frame.locals.get(&name) {
+                self.statics.insert((frame.name.clone(), name), value.clone());
+            }
+        }
+
+        match flow? {
+            Flow::Return(Some(value)) => match value.convert(&callable.return_type) {
+                Some(value) => Ok(Some(value)),
+                None => Err(self.error(
+                    &format!("Expected a return value of type `{}`", callable.return_type),
+                    &callable.body.1,
+                )),
+            },
+            Flow::Goto((label, span)) => Err(self.error(&format!("Undefined label `{}`", label.0), &span)),
+            // a function which finishes without returning a value returns the default value of
+            // its return type
+            _ => Ok(Value::default_of(&callable.return_type)),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::span::Span;
+use crate::r#static::module_loader::FileId;
+
+/// An error which stops a running script
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub msg: String,
+    pub span: Span,
+    pub file_id: FileId,
+    /// the function calls being run when the error occurred, innermost first, as the name of the
+    /// function and the location it was called from
+    pub trace: Vec<(Identifier, FileId, Span)>,
+}
+
+impl RuntimeError {
+    pub fn new(msg: &str, span: &Span, file_id: FileId) -> Self {
+        RuntimeError { msg: msg.to_string(), span: *span, file_id, trace: vec![] }
+    }
+}
@@ -0,0 +1,348 @@
+use crate::dynamic::interpreter::Interpreter;
+use crate::dynamic::runtime_error::RuntimeError;
+use crate::dynamic::value::Value;
+use crate::parsing::ast::astree::RuleOpt;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::Span;
+
+/// The minimum interval of a rule without a `minInterval` or `highFrequency` option, in seconds
+pub const DEFAULT_MIN_INTERVAL: i32 = 1;
+
+/// An interval in seconds as milliseconds on the game clock. The clock is an `int`, so intervals
+/// too long for it are treated as never passing
+pub fn secs_to_ms(secs: i32) -> i32 {
+    secs.saturating_mul(1000)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchedulerConfig {
+    /// the length of a game tick in milliseconds. `highFrequency` rules run every tick
+    pub tick: i32,
+    /// the number of rules that may run in a single tick, rules which have waited past their
+    /// `maxInterval` go first. All due rules run when `None`
+    pub rules_per_tick: Option<usize>,
+    /// write a line to the output before each rule runs
+    pub trace: bool,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig { tick: 50, rules_per_tick: None, trace: false }
+    }
+}
+
+/// A rule run by the scheduler
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Firing {
+    pub tick: usize,
+    /// in milliseconds
+    pub time: i32,
+    pub rule: Identifier,
+}
+
+/// A rule and the options it is scheduled with, which may be changed by the rule builtins while
+/// the script runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: Identifier,
+    pub active: bool,
+    pub run_immediately: bool,
+    /// in seconds
+    pub min_interval: i32,
+    /// in seconds
+    pub max_interval: Option<i32>,
+    pub priority: i32,
+    pub group: Option<String>,
+    /// the earliest time the rule may run again, in milliseconds
+    pub next_run: i32,
+    /// the time by which the rule must have run again, in milliseconds
+    pub deadline: Option<i32>,
+}
+
+impl Rule {
+    /// An inactive rule with the default options
+    pub fn new(name: Identifier) -> Self {
+        Rule {
+            name,
+            active: false,
+            run_immediately: false,
+            min_interval: DEFAULT_MIN_INTERVAL,
+            max_interval: None,
+            priority: 0,
+            group: None,
+            next_run: 0,
+            deadline: None,
+        }
+    }
+
+    /// A rule with the options it is defined with
+    pub fn with_opts<'a>(name: Identifier, opts: impl IntoIterator<Item = &'a RuleOpt>) -> Self {
+        let mut rule = Rule::new(name);
+        for opt in opts {
+            match opt {
+                RuleOpt::Active => rule.active = true,
+                RuleOpt::Inactive => rule.active = false,
+                RuleOpt::RunImmediately => rule.run_immediately = true,
+                RuleOpt::HighFrequency => {
+                    rule.min_interval = 0;
+                    rule.max_interval = Some(0);
+                }
+                RuleOpt::MinInterval((secs, _span)) => rule.min_interval = *secs as i32,
+                RuleOpt::MaxInterval((secs, _span)) => rule.max_interval = Some(*secs as i32),
+                RuleOpt::Priority((priority, _span)) => rule.priority = *priority as i32,
+                RuleOpt::Group((group, _span)) => rule.group = Some(group.clone()),
+            }
+        }
+        rule
+    }
+
+    /// Schedules the rule's first run after it is activated at `now`. A rule waits for its minimum
+    /// interval before it first runs, unless it is `runImmediately`
+    pub fn schedule(&mut self, now: i32) {
+        let wait = if self.run_immediately { 0 } else { secs_to_ms(self.min_interval) };
+        self.next_run = now.saturating_add(wait);
+        self.deadline = self.max_interval.map(|secs| self.next_run.max(now.saturating_add(secs_to_ms(secs))));
+    }
+
+    /// Schedules the rule's next run after it ran at `now`. A rule runs at most once a tick
+    fn reschedule(&mut self, now: i32, tick: i32) {
+        self.next_run = now.saturating_add(secs_to_ms(self.min_interval).max(tick));
+        self.deadline = self.max_interval.map(|secs| now.saturating_add(secs_to_ms(secs)));
+    }
+
+    fn is_due(&self, now: i32) -> bool {
+        self.active && self.next_run <= now
+    }
+
+    fn is_overdue(&self, now: i32) -> bool {
+        self.deadline.is_some_and(|deadline| deadline <= now)
+    }
+}
+
+/// The rules of a script and the game clock they run on. The interpreter and the VM only differ in
+/// how they run the body of a rule, see [RuleRunner]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scheduler {
+    /// in the order they were defined
+    pub rules: Vec<Rule>,
+    /// milliseconds since the start of the game, as returned by `xsGetTime`
+    pub time: i32,
+    /// the index of the rule which is running, used by the `Self` rule builtins
+    pub current: Option<usize>,
+}
+
+/// Runs the bodies of the rules of a [Scheduler], which is shared by the interpreter and the VM
+pub trait RuleRunner {
+    type Error;
+
+    fn scheduler(&mut self) -> &mut Scheduler;
+
+    /// Runs the body of the rule `name`
+    fn run_rule(&mut self, name: &Identifier) -> Result<(), Self::Error>;
+
+    /// Writes a line which traces the rules as they run
+    fn trace(&mut self, line: &str) -> Result<(), Self::Error>;
+}
+
+/// Runs the active rules on a simulated game clock for `duration` milliseconds, starting from the
+/// current time. In each tick, due rules run in order of descending priority, and then in the order
+/// they were defined
+pub fn simulate<R: RuleRunner>(
+    runner: &mut R,
+    duration: i32,
+    cfg: &SchedulerConfig,
+) -> Result<Vec<Firing>, R::Error> {
+    let mut firings = vec![];
+    let start = runner.scheduler().time;
+    let num_ticks = (duration / cfg.tick.max(1)).max(0) as usize;
+
+    for tick in 0..num_ticks {
+        let now = start.saturating_add(tick as i32 * cfg.tick);
+        let scheduler = runner.scheduler();
+        scheduler.time = now;
+
+        let mut due = (0..scheduler.rules.len())
+            .filter(|idx| scheduler.rules[*idx].is_due(now))
+            .collect::<Vec<usize>>();
+        // the sort is stable, so rules of the same priority keep their definition order
+        due.sort_by_key(|idx| (!scheduler.rules[*idx].is_overdue(now), -scheduler.rules[*idx].priority));
+        if let Some(limit) = cfg.rules_per_tick {
+            due.truncate(limit);
+        }
+
+        for idx in due {
+            let rule = &runner.scheduler().rules[idx];
+            // an earlier rule in this tick may have disabled this one
+            if !rule.active {
+                continue;
+            }
+            let name = rule.name.clone();
+            if cfg.trace {
+                runner.trace(&format!("[{:.3}s tick {}] rule {}", now as f32 / 1000.0, tick, name.0))?;
+            }
+            firings.push(Firing { tick, time: now, rule: name.clone() });
+
+            runner.run_rule(&name)?;
+
+            runner.scheduler().rules[idx].reschedule(now, cfg.tick);
+        }
+    }
+
+    runner.scheduler().time = start.saturating_add(duration);
+    Ok(firings)
+}
+
+impl Scheduler {
+    /// Adds a rule and schedules it, replacing an earlier definition of a rule of the same name
+    pub fn define(&mut self, mut rule: Rule) {
+        rule.schedule(self.time);
+        match self.rule_idx(&rule.name.0) {
+            Ok(idx) => self.rules[idx] = rule,
+            Err(_) => self.rules.push(rule),
+        }
+    }
+
+    /// Whether a rule called `name` is defined
+    pub fn is_rule(&self, name: &str) -> bool {
+        self.rule_idx(name).is_ok()
+    }
+
+    /// Marks `name` as the running rule, or clears it if `name` is a function
+    pub fn enter(&mut self, name: &str) {
+        self.current = self.rule_idx(name).ok();
+    }
+
+    /// The parameters of the rule builtin `name`, `None` if it is not one. The rule builtins
+    /// change how the rules are scheduled, so they are run by the scheduler
+    pub fn builtin_params(name: &str) -> Option<&'static [Type]> {
+        match name {
+            "xsDisableSelf" => Some(&[]),
+            "xsEnableRule" | "xsDisableRule" | "xsEnableRuleGroup" | "xsDisableRuleGroup"
+            | "xsIsRuleEnabled" | "xsIsRuleGroupEnabled" => Some(&[Type::Str]),
+            "xsSetRulePriority" | "xsSetRuleMinInterval" | "xsSetRuleMaxInterval" => {
+                Some(&[Type::Str, Type::Int])
+            }
+            "xsSetRulePrioritySelf" | "xsSetRuleMinIntervalSelf" | "xsSetRuleMaxIntervalSelf" => {
+                Some(&[Type::Int])
+            }
+            _ => None,
+        }
+    }
+
+    /// Runs a rule builtin, see [Scheduler::builtin_params], with its arguments converted to the
+    /// types of its parameters
+    pub fn builtin(&mut self, name: &str, args: &[Value]) -> Result<Option<Value>, String> {
+        use Value::{Bool, Int, Str};
+        let value = match (name, args) {
+            ("xsEnableRule", [Str(rule)]) => {
+                let idx = self.rule_idx(rule)?;
+                self.set_active(idx, true);
+                return Ok(None);
+            }
+            ("xsDisableRule", [Str(rule)]) => {
+                let idx = self.rule_idx(rule)?;
+                self.set_active(idx, false);
+                return Ok(None);
+            }
+            ("xsDisableSelf", []) => {
+                let idx = self.current_rule()?;
+                self.set_active(idx, false);
+                return Ok(None);
+            }
+            ("xsEnableRuleGroup" | "xsDisableRuleGroup", [Str(group)]) => {
+                for idx in self.rule_group(group) {
+                    self.set_active(idx, name == "xsEnableRuleGroup");
+                }
+                return Ok(None);
+            }
+            ("xsIsRuleEnabled", [Str(rule)]) => Bool(self.rules[self.rule_idx(rule)?].active),
+            // a group is enabled when all of its rules are
+            ("xsIsRuleGroupEnabled", [Str(group)]) => Bool(
+                self.rule_group(group).into_iter().all(|idx| self.rules[idx].active)
+            ),
+            ("xsSetRulePriority", [Str(rule), Int(priority)]) => {
+                let idx = self.rule_idx(rule)?;
+                self.rules[idx].priority = *priority;
+                return Ok(None);
+            }
+            ("xsSetRuleMinInterval", [Str(rule), Int(secs)]) => {
+                let idx = self.rule_idx(rule)?;
+                self.rules[idx].min_interval = *secs;
+                return Ok(None);
+            }
+            ("xsSetRuleMaxInterval", [Str(rule), Int(secs)]) => {
+                let idx = self.rule_idx(rule)?;
+                self.rules[idx].max_interval = Some(*secs);
+                return Ok(None);
+            }
+            ("xsSetRulePrioritySelf", [Int(priority)]) => {
+                let idx = self.current_rule()?;
+                self.rules[idx].priority = *priority;
+                return Ok(None);
+            }
+            ("xsSetRuleMinIntervalSelf", [Int(secs)]) => {
+                let idx = self.current_rule()?;
+                self.rules[idx].min_interval = *secs;
+                return Ok(None);
+            }
+            ("xsSetRuleMaxIntervalSelf", [Int(secs)]) => {
+                let idx = self.current_rule()?;
+                self.rules[idx].max_interval = Some(*secs);
+                return Ok(None);
+            }
+            _ => unreachable!("the arguments of a rule builtin are converted to its parameter types"),
+        };
+        Ok(Some(value))
+    }
+
+    fn rule_idx(&self, name: &str) -> Result<usize, String> {
+        self.rules.iter()
+            .position(|rule| rule.name.0 == name)
+            .ok_or_else(|| format!("Undefined rule `{}`", name))
+    }
+
+    fn current_rule(&self) -> Result<usize, String> {
+        self.current.ok_or_else(|| "This function can only be called from a rule".to_string())
+    }
+
+    /// The indices of the rules in a group, in the order they were defined
+    fn rule_group(&self, group: &str) -> Vec<usize> {
+        (0..self.rules.len())
+            .filter(|idx| self.rules[*idx].group.as_deref() == Some(group))
+            .collect()
+    }
+
+    fn set_active(&mut self, idx: usize, active: bool) {
+        let now = self.time;
+        let rule = &mut self.rules[idx];
+        if active && !rule.active {
+            rule.schedule(now);
+        }
+        rule.active = active;
+    }
+}
+
+impl RuleRunner for Interpreter<'_> {
+    type Error = RuntimeError;
+
+    fn scheduler(&mut self) -> &mut Scheduler {
+        &mut self.scheduler
+    }
+
+    fn run_rule(&mut self, name: &Identifier) -> Result<(), RuntimeError> {
+        self.call(&name.0, vec![]).map(|_| ())
+    }
+
+    fn trace(&mut self, line: &str) -> Result<(), RuntimeError> {
+        writeln!(self.out, "{}", line)
+            .map_err(|err| self.error(&format!("Cannot write trace: {}", err), &Span::new(0, 0)))
+    }
+}
+
+impl Interpreter<'_> {
+    /// Runs the active rules of the loaded script on a simulated game clock, see [simulate]
+    pub fn simulate(&mut self, duration: i32, cfg: &SchedulerConfig) -> Result<Vec<Firing>, RuntimeError> {
+        simulate(self, duration, cfg)
+    }
+}
@@ -0,0 +1,301 @@
+use std::rc::Rc;
+use crate::dynamic::interpreter::{Callable, Flow, Interpreter};
+use crate::dynamic::runtime_error::RuntimeError;
+use crate::dynamic::scheduler::Rule;
+use crate::dynamic::value::Value;
+use crate::parsing::ast::astree::{ASTreeNode, Body};
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::{Span, Spanned};
+
+impl Interpreter<'_> {
+    /// Defines a global variable, function or rule (xsBssFn, xsBssRule, xsBssClsDef)
+    pub(crate) fn top_level_stmt(&mut self, (stmt, span): &Spanned<ASTreeNode>) -> Result<(), RuntimeError> {
+        match stmt {
+            ASTreeNode::VarDef { type_, name: (name, _name_span), value, .. } => {
+                let value = self.init_value(type_, value.as_ref(), span)?;
+                self.globals.insert(name.clone(), value);
+            }
+            // a mutable function may be redefined, the last definition is the one that is called
+            ASTreeNode::FnDef { return_type, name: (name, _name_span), params, body, .. } => {
+                self.fns.insert(name.clone(), Rc::new(Callable {
+                    name: name.clone(),
+                    file_id: self.file_id,
+                    params: params.clone(),
+                    return_type: return_type.clone(),
+                    body: body.clone(),
+                }));
+            }
+            ASTreeNode::RuleDef { name: (name, _name_span), rule_opts, body, .. } => {
+                let callable = Rc::new(Callable {
+                    name: name.clone(),
+                    file_id: self.file_id,
+                    params: vec![],
+                    return_type: Type::Void,
+                    body: body.clone(),
+                });
+                let rule = Rule::with_opts(name.clone(), rule_opts.iter().map(|(opt, _span)| opt));
+                self.scheduler.define(rule);
+                self.rules.insert(name.clone(), callable);
+            }
+            // a limit below 1 is a type error, it is ignored so that such a script can still be run
+            ASTreeNode::InfiniteLoopLimit((limit, _limit_span)) if *limit > 0 => {
+                self.loop_limit = Some((*limit).min(i32::MAX as i64) as usize);
+            }
+            ASTreeNode::InfiniteRecursionLimit((limit, _limit_span)) if *limit > 0 => {
+                self.recursion_limit = (*limit).min(i32::MAX as i64) as usize;
+            }
+            ASTreeNode::InfiniteLoopLimit(_) | ASTreeNode::InfiniteRecursionLimit(_) => {}
+            // classes cannot be instantiated
+            ASTreeNode::Class { .. } => {}
+            _ => {
+                return Err(self.error("This statement is not allowed at the top level", span));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs a sequence of statements (xsBssSeq). A `goto` jumps to its label if the label is in
+    /// this sequence, otherwise it is passed on to the enclosing sequence (xsBssGoto)
+    pub(crate) fn body(&mut self, (body, _span): &Spanned<Body>) -> Result<Flow, RuntimeError> {
+        let mut idx = 0;
+        while let Some(spanned_stmt) = body.0.get(idx) {
+            match self.stmt(spanned_stmt)? {
+                Flow::Normal => idx += 1,
+                Flow::Goto((label, span)) => {
+                    let Some(label_idx) = body.0.iter().position(|(stmt, _span)| {
+                        matches!(stmt, ASTreeNode::LabelDef((name, _span)) if *name == label)
+                    }) else {
+                        return Ok(Flow::Goto((label, span)));
+                    };
+                    idx = label_idx;
+                }
+                flow => return Ok(flow),
+            }
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    pub(crate) fn stmt(&mut self, (stmt, span): &Spanned<ASTreeNode>) -> Result<Flow, RuntimeError> {
+        self.count_step(span)?;
+        match stmt {
+            ASTreeNode::VarDef { is_static, type_, name: (name, _name_span), value, .. } => {
+                let frame_name = self.frames.last().map(|frame| frame.name.clone());
+                let static_key = frame_name.filter(|_| *is_static).map(|frame_name| (frame_name, name.clone()));
+
+                // a static local is only initialised the first time its function is called
+                let value = match static_key.as_ref().and_then(|key| self.statics.get(key)) {
+                    Some(value) => value.clone(),
+                    None => self.init_value(type_, value.as_ref(), span)?,
+                };
+
+                let Some(frame) = self.frames.last_mut() else {
+                    self.globals.insert(name.clone(), value);
+                    return Ok(Flow::Normal);
+                };
+                frame.locals.insert(name.clone(), value);
+                if static_key.is_some() && !frame.statics.contains(name) {
+                    frame.statics.push(name.clone());
+                }
+            }
+            ASTreeNode::VarAssign { name: (name, name_span), value } => {
+                let value = self.expr(value)?;
+                self.assign(name, value, name_span)?;
+            }
+            ASTreeNode::Return(value) => {
+                let value = match value {
+                    Some(value) => Some(self.expr(value)?),
+                    None => None,
+                };
+                return Ok(Flow::Return(value));
+            }
+            ASTreeNode::IfElse { condition, consequent, alternate } => {
+                if self.cond(condition)? {
+                    return self.body(consequent);
+                }
+                if let Some(alternate) = alternate {
+                    return self.body(alternate);
+                }
+            }
+            ASTreeNode::While { condition, body } => {
+                let mut iterations = 0;
+                while self.cond(condition)? {
+                    self.count_iteration(&mut iterations, span)?;
+                    match self.body(body)? {
+                        Flow::Normal | Flow::Continue => {}
+                        Flow::Break => break,
+                        flow => return Ok(flow),
+                    }
+                }
+            }
+            // `for (X = E1; op E2) { S }` runs as `X = E1; while (X op E2) { S X++; }` with `X--`
+            // for `>` and `>=`, after which X is undefined again (xsBssForInc, xsBssForDec)
+            ASTreeNode::For { var, condition, body } => {
+                let (ASTreeNode::VarAssign { name: (name, name_span), value }, _span) = var.as_ref() else {
+                    return Err(self.error("A `for` loop must start with an assignment", span));
+                };
+                let step = match &condition.0 {
+                    Expr::Lt(..) | Expr::Le(..) => 1,
+                    _ => -1,
+                };
+
+                let init = self.expr(value)?;
+                let Some(init) = init.as_int() else {
+                    return Err(self.error("Expected a value of type `int`", &value.1));
+                };
+                let Some(frame) = self.frames.last_mut() else {
+                    return Err(self.error("`for` statements are only allowed inside a local scope", span));
+                };
+                frame.locals.insert(name.clone(), Value::Int(init));
+
+                let flow = self.for_loop((name, name_span), condition, body, step, span);
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.locals.remove(name);
+                }
+                match flow? {
+                    Flow::Normal | Flow::Break => {}
+                    flow => return Ok(flow),
+                }
+            }
+            // the body of the first case equal to the clause is run, or the default if none are
+            // (xsBssSwitchC, xsBssSwitchD). A `break` only leaves the switch
+            ASTreeNode::Switch { clause, cases } => {
+                let clause_val = self.int(clause)?;
+                let mut matched = None;
+                for (case, body) in cases {
+                    let Some(case) = case else {
+                        continue;
+                    };
+                    if self.int(case)? == clause_val {
+                        matched = Some(body);
+                        break;
+                    }
+                }
+                let matched = matched.or_else(|| {
+                    cases.iter().find(|(case, _body)| case.is_none()).map(|(_case, body)| body)
+                });
+
+                if let Some(body) = matched {
+                    match self.body(body)? {
+                        Flow::Normal | Flow::Break => {}
+                        flow => return Ok(flow),
+                    }
+                }
+            }
+            ASTreeNode::PostDPlus((name, name_span)) => self.step(name, 1, name_span)?,
+            ASTreeNode::PostDMinus((name, name_span)) => self.step(name, -1, name_span)?,
+            ASTreeNode::Break => return Ok(Flow::Break),
+            ASTreeNode::Continue => return Ok(Flow::Continue),
+            ASTreeNode::LabelDef(_) => {}
+            ASTreeNode::Goto(label) => return Ok(Flow::Goto(label.clone())),
+            ASTreeNode::Discarded(expr) => {
+                self.call_expr(expr)?;
+            }
+            // the semantics of `dbg` are unknown
+            ASTreeNode::Debug(_) => {}
+            ASTreeNode::Breakpoint => {
+                return Err(self.error("Execution paused at a breakpoint, which cannot be resumed", span));
+            }
+            ASTreeNode::Include(_)
+            | ASTreeNode::InfiniteLoopLimit(_)
+            | ASTreeNode::InfiniteRecursionLimit(_)
+            | ASTreeNode::FnDef { .. }
+            | ASTreeNode::RuleDef { .. }
+            | ASTreeNode::Class { .. } => {
+                return Err(self.error("This statement is only allowed at the top level", span));
+            }
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    fn for_loop(
+        &mut self,
+        (name, name_span): (&Identifier, &Span),
+        condition: &Spanned<Expr>,
+        body: &Spanned<Body>,
+        step: i32,
+        span: &Span,
+    ) -> Result<Flow, RuntimeError> {
+        let mut iterations = 0;
+        while self.cond(condition)? {
+            self.count_iteration(&mut iterations, span)?;
+            match self.body(body)? {
+                Flow::Normal => self.step(name, step, name_span)?,
+                // the step is part of the loop's body, so a `continue` skips it
+                Flow::Continue => {}
+                flow => return Ok(flow),
+            }
+        }
+
+        Ok(Flow::Normal)
+    }
+
+    /// `X++` and `X--` (xsBssPostInc, xsBssPostDec)
+    fn step(&mut self, name: &Identifier, step: i32, span: &Span) -> Result<(), RuntimeError> {
+        let value = match self.lookup(name, span)? {
+            Value::Int(val) => Value::Int(val.wrapping_add(step)),
+            Value::Float(val) => Value::Float(val + step as f32),
+            value => {
+                return Err(self.error(&format!("Cannot increment a value of type `{}`", value.type_()), span));
+            }
+        };
+        self.assign(name, value, span)
+    }
+
+    fn count_iteration(&mut self, iterations: &mut usize, span: &Span) -> Result<(), RuntimeError> {
+        self.count_step(span)?;
+        *iterations += 1;
+        match self.loop_limit {
+            Some(limit) if *iterations > limit => Err(self.error(
+                &format!("This loop exceeded the limit of {} iterations", limit), span
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a statement or loop iteration towards [Interpreter::max_steps]
+    fn count_step(&mut self, span: &Span) -> Result<(), RuntimeError> {
+        self.steps += 1;
+        match self.max_steps {
+            Some(max) if self.steps > max => Err(self.error(
+                &format!("Stopped after {} steps, the script may never finish", max), span
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The initial value of a variable, converted to its type
+    pub(crate) fn init_value(
+        &mut self,
+        type_: &Type,
+        value: Option<&Spanned<Expr>>,
+        span: &Span,
+    ) -> Result<Value, RuntimeError> {
+        let value = match value {
+            Some(value) => self.expr(value)?,
+            None => Value::default_of(type_)
+                .ok_or_else(|| self.error(&format!("Cannot declare a variable of type `{}`", type_), span))?,
+        };
+
+        value.convert(type_)
+            .ok_or_else(|| self.error(&format!("Expected a value of type `{}`", type_), span))
+    }
+
+    fn cond(&mut self, expr: &Spanned<Expr>) -> Result<bool, RuntimeError> {
+        match self.expr(expr)? {
+            Value::Bool(val) => Ok(val),
+            _ => Err(self.error("Conditional expression must be a boolean value", &expr.1)),
+        }
+    }
+
+    /// An expression used by a `switch`, where `float`s and `bool`s are cast to `int`s
+    fn int(&mut self, expr: &Spanned<Expr>) -> Result<i32, RuntimeError> {
+        self.expr(expr)?
+            .as_int()
+            .ok_or_else(|| self.error("Expected a value of type `int`", &expr.1))
+    }
+}
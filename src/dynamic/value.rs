@@ -0,0 +1,185 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::parsing::ast::literal::Literal;
+use crate::parsing::ast::type_::Type;
+
+/// A runtime XS value. XS `int`s and `float`s are 32 bits wide
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Float(f32),
+    Bool(bool),
+    Str(String),
+    Vec([f32; 3]),
+}
+
+impl Value {
+    pub fn from_lit(lit: &Literal) -> Self {
+        match lit {
+            Literal::Int(val)   => Value::Int(*val as i32),
+            Literal::Float(val) => Value::Float(*val as f32),
+            Literal::Bool(val)  => Value::Bool(*val),
+            Literal::Str(val)   => Value::Str(val.clone()),
+        }
+    }
+
+    /// The value of a variable of type `type_` declared without an initializer
+    pub fn default_of(type_: &Type) -> Option<Self> {
+        match type_ {
+            Type::Int   => Some(Value::Int(0)),
+            Type::Float => Some(Value::Float(0.0)),
+            Type::Bool  => Some(Value::Bool(false)),
+            Type::Str   => Some(Value::Str(String::new())),
+            Type::Vec   => Some(Value::Vec([0.0; 3])),
+            _           => None,
+        }
+    }
+
+    pub fn type_(&self) -> Type {
+        match self {
+            Value::Int(_)   => Type::Int,
+            Value::Float(_) => Type::Float,
+            Value::Bool(_)  => Type::Bool,
+            Value::Str(_)   => Type::Str,
+            Value::Vec(_)   => Type::Vec,
+        }
+    }
+
+    /// Converts a value as it is when assigned to a variable of type `type_`. `float`s are
+    /// truncated towards zero when converted to an `int`, and `bool`s become `0` or `1`
+    pub fn convert(self, type_: &Type) -> Option<Self> {
+        match (self, type_) {
+            (Value::Int(val), Type::Int)     => Some(Value::Int(val)),
+            (Value::Float(val), Type::Int)   => Some(Value::Int(val as i32)),
+            (Value::Bool(val), Type::Int)    => Some(Value::Int(val as i32)),
+            (Value::Int(val), Type::Float)   => Some(Value::Float(val as f32)),
+            (Value::Float(val), Type::Float) => Some(Value::Float(val)),
+            (Value::Bool(val), Type::Float)  => Some(Value::Float(val as i32 as f32)),
+            (Value::Bool(val), Type::Bool)   => Some(Value::Bool(val)),
+            (Value::Str(val), Type::Str)     => Some(Value::Str(val)),
+            (Value::Vec(val), Type::Vec)     => Some(Value::Vec(val)),
+            _                                => None,
+        }
+    }
+
+    /// The value as used by a `switch` or a `for` loop, which cast `float`s and `bool`s to `int`s
+    pub fn as_int(&self) -> Option<i32> {
+        match self.clone().convert(&Type::Int)? {
+            Value::Int(val) => Some(val),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithOp {
+    Mul,
+    Div,
+    Rem,
+    Add,
+    Sub,
+}
+
+/// The type of the result of an arithmetic operation is the type of its first operand, the second
+/// operand is converted to that type before the operation. Adding a string to anything
+/// concatenates them. `vector`s are added and subtracted component wise, and may be multiplied or
+/// divided by a number
+pub fn arith(op: ArithOp, val1: Value, val2: Value) -> Result<Value, String> {
+    match (val1, val2) {
+        (Value::Str(val1), val2) if op == ArithOp::Add => Ok(Value::Str(format!("{}{}", val1, val2))),
+        (val1, Value::Str(val2)) if op == ArithOp::Add => Ok(Value::Str(format!("{}{}", val1, val2))),
+
+        (Value::Vec([x1, y1, z1]), Value::Vec([x2, y2, z2])) if op == ArithOp::Add => {
+            Ok(Value::Vec([x1 + x2, y1 + y2, z1 + z2]))
+        }
+        (Value::Vec([x1, y1, z1]), Value::Vec([x2, y2, z2])) if op == ArithOp::Sub => {
+            Ok(Value::Vec([x1 - x2, y1 - y2, z1 - z2]))
+        }
+        (Value::Vec([x, y, z]), val2 @ (Value::Int(_) | Value::Float(_)))
+            if matches!(op, ArithOp::Mul | ArithOp::Div) => {
+            let Some(Value::Float(val2)) = val2.convert(&Type::Float) else { unreachable!() };
+            Ok(Value::Vec(match op {
+                ArithOp::Mul => [x * val2, y * val2, z * val2],
+                _ => [x / val2, y / val2, z / val2],
+            }))
+        }
+
+        (Value::Int(val1), val2 @ (Value::Int(_) | Value::Float(_))) => {
+            let Some(Value::Int(val2)) = val2.convert(&Type::Int) else { unreachable!() };
+            let result = match op {
+                ArithOp::Mul => Some(val1.wrapping_mul(val2)),
+                ArithOp::Div => val1.checked_div(val2),
+                ArithOp::Rem => val1.checked_rem(val2),
+                ArithOp::Add => Some(val1.wrapping_add(val2)),
+                ArithOp::Sub => Some(val1.wrapping_sub(val2)),
+            };
+            result.map(Value::Int).ok_or_else(|| "Integer division by zero".to_string())
+        }
+        (Value::Float(val1), val2 @ (Value::Int(_) | Value::Float(_))) => {
+            let Some(Value::Float(val2)) = val2.convert(&Type::Float) else { unreachable!() };
+            Ok(Value::Float(match op {
+                ArithOp::Mul => val1 * val2,
+                ArithOp::Div => val1 / val2,
+                ArithOp::Rem => val1 % val2,
+                ArithOp::Add => val1 + val2,
+                ArithOp::Sub => val1 - val2,
+            }))
+        }
+
+        (val1, val2) => Err(format!(
+            "Cannot use an arithmetic operator on types `{}` and `{}`", val1.type_(), val2.type_()
+        )),
+    }
+}
+
+/// Strings are compared lexically. Comparing `vector`s or `bool`s with anything but `==` and `!=`
+/// crashes XS. `None` when either operand is NaN
+pub fn compare(val1: &Value, val2: &Value) -> Result<Option<Ordering>, String> {
+    match (val1, val2) {
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            Ok(num(val1).partial_cmp(&num(val2)))
+        }
+        (Value::Str(val1), Value::Str(val2)) => Ok(Some(val1.cmp(val2))),
+        (Value::Vec(_), Value::Vec(_)) | (Value::Bool(_), Value::Bool(_)) => {
+            Err("This comparison causes a silent XS crash".to_string())
+        }
+        _ => Err(format!("Cannot compare types `{}` and `{}`", val1.type_(), val2.type_())),
+    }
+}
+
+pub fn equals(val1: &Value, val2: &Value) -> Result<bool, String> {
+    match (val1, val2) {
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => Ok(num(val1) == num(val2)),
+        (Value::Str(_), Value::Str(_))
+        | (Value::Vec(_), Value::Vec(_))
+        | (Value::Bool(_), Value::Bool(_)) => Ok(val1 == val2),
+        _ => Err(format!("Cannot compare types `{}` and `{}`", val1.type_(), val2.type_())),
+    }
+}
+
+fn num(value: &Value) -> f64 {
+    match value {
+        Value::Int(val) => *val as f64,
+        Value::Float(val) => *val as f64,
+        _ => f64::NAN,
+    }
+}
+
+/// Floats are written like C's `%f`
+pub fn fmt_float(val: f32) -> String {
+    format!("{:.6}", val)
+}
+
+/// How a value is written when it is concatenated to a string
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(val)   => write!(f, "{}", val),
+            Value::Float(val) => write!(f, "{}", fmt_float(*val)),
+            Value::Bool(val)  => write!(f, "{}", val),
+            Value::Str(val)   => write!(f, "{}", val),
+            Value::Vec([x, y, z]) => write!(f, "({}, {}, {})", fmt_float(*x), fmt_float(*y), fmt_float(*z)),
+        }
+    }
+}
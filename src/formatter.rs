@@ -0,0 +1,170 @@
+pub mod statement;
+pub mod expression;
+
+use clap::ValueEnum;
+use crate::parsing::ast::comment::Comment;
+use crate::parsing::span::Spanned;
+use crate::r#static::module_loader::Module;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    Spaces(usize),
+    Tabs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BraceStyle {
+    /// `if (x) {`
+    SameLine,
+    /// `if (x)` with the `{` on the following line
+    NextLine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FmtConfig {
+    pub indent: Indent,
+    pub brace_style: BraceStyle,
+    /// `if (x)` instead of `if(x)`, likewise for `while`, `for` and `switch`
+    pub space_after_keyword: bool,
+}
+
+impl Default for FmtConfig {
+    fn default() -> Self {
+        FmtConfig {
+            indent: Indent::Spaces(4),
+            brace_style: BraceStyle::SameLine,
+            space_after_keyword: true,
+        }
+    }
+}
+
+/// Writes formatted source line by line. Comments are kept alongside the AST rather than in it, so
+/// they are interleaved with the statements by their position in the original source
+pub struct Printer<'src> {
+    src: &'src str,
+    cfg: &'src FmtConfig,
+    comments: &'src [Spanned<Comment>],
+    /// index of the first comment not written yet
+    next_comment: usize,
+    depth: usize,
+    /// the end of the last statement or comment written in the current block, used to keep
+    /// blank lines. `None` at the start of a block
+    last_end: Option<usize>,
+    out: String,
+}
+
+/// Pretty prints a module. Must only be used on modules without parse errors, as statements which
+/// could not be parsed are missing from the AST
+pub fn format_module(module: &Module, cfg: &FmtConfig) -> String {
+    let mut printer = Printer {
+        src: &module.src,
+        cfg,
+        comments: &module.comments,
+        next_comment: 0,
+        depth: 0,
+        last_end: None,
+        out: String::with_capacity(module.src.len()),
+    };
+
+    for stmt in module.ast.iter() {
+        printer.stmt(stmt);
+    }
+    printer.comments_before(module.src.len());
+
+    printer.out
+}
+
+impl Printer<'_> {
+    fn line(&mut self, text: &str) {
+        if !text.is_empty() {
+            match self.cfg.indent {
+                Indent::Spaces(width) => self.out.push_str(&" ".repeat(width * self.depth)),
+                Indent::Tabs => self.out.push_str(&"\t".repeat(self.depth)),
+            }
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// Keeps a single blank line before the item starting at `start` if the source had any
+    fn blank_line_before(&mut self, start: usize) {
+        let Some(last_end) = self.last_end else {
+            return;
+        };
+        if self.src[last_end..start].matches('\n').count() > 1 {
+            self.line("");
+        }
+    }
+
+    /// Writes all the remaining comments that start before `pos` on their own lines
+    fn comments_before(&mut self, pos: usize) {
+        while let Some((cmt, span)) = self.comments.get(self.next_comment) {
+            if span.start >= pos {
+                break;
+            }
+            self.blank_line_before(span.start);
+            self.line(cmt.0.trim_end());
+            self.last_end = Some(span.end);
+            self.next_comment += 1;
+        }
+    }
+
+    /// Moves the comments inside the item ending at `end` which were not written yet, e.g. one
+    /// between the operands of an expression, and a comment that followed the item on the same
+    /// line to the end of the last written line
+    fn trailing_comment(&mut self, end: usize) {
+        while let Some((cmt, span)) = self.comments.get(self.next_comment) {
+            if span.start >= end && self.src[end..span.start].contains('\n') {
+                return;
+            }
+            self.out.pop();
+            self.out.push(' ');
+            self.out.push_str(cmt.0.trim_end());
+            self.out.push('\n');
+            self.last_end = Some(end.max(span.end));
+            self.next_comment += 1;
+            if span.start >= end {
+                return;
+            }
+        }
+    }
+
+    /// `kw` followed by a space if configured, to be followed by a parenthesised expression
+    fn keyword(&self, kw: &str) -> String {
+        if self.cfg.space_after_keyword {
+            format!("{} ", kw)
+        } else {
+            kw.to_string()
+        }
+    }
+
+    fn open(&mut self, header: &str) {
+        match self.cfg.brace_style {
+            BraceStyle::SameLine => self.line(&format!("{} {{", header)),
+            BraceStyle::NextLine => {
+                self.line(header);
+                self.line("{");
+            }
+        }
+        self.depth += 1;
+        self.last_end = None;
+    }
+
+    fn close(&mut self, trailer: &str) {
+        self.depth -= 1;
+        self.line(&format!("}}{}", trailer));
+    }
+
+    /// Continues a block that was just closed with another one, e.g. `} else {`
+    fn reopen(&mut self, header: &str) {
+        match self.cfg.brace_style {
+            BraceStyle::SameLine => {
+                self.out.pop();
+                self.out.push_str(&format!(" {} {{\n", header));
+                self.depth += 1;
+                self.last_end = None;
+            }
+            BraceStyle::NextLine => self.open(header),
+        }
+    }
+}
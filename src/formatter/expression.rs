@@ -0,0 +1,47 @@
+use crate::formatter::Printer;
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::span::Spanned;
+
+impl Printer<'_> {
+    pub fn expr(&self, (expr, span): &Spanned<Expr>) -> String {
+        let bin_op = |lhs: &Spanned<Expr>, op: &str, rhs: &Spanned<Expr>| {
+            format!("{} {} {}", self.expr(lhs), op, self.expr(rhs))
+        };
+
+        match expr {
+            // literals are copied from the source to keep how they were written, e.g. `1.` or
+            // escapes in strings
+            Expr::Literal(_) => self.src[span.into_range()].to_string(),
+            Expr::Identifier(name) => name.0.clone(),
+            Expr::Paren(expr) => format!("({})", self.expr(expr)),
+            Expr::Vec { x, y, z } => format!(
+                "vector({}, {}, {})", self.expr(x), self.expr(y), self.expr(z)
+            ),
+            Expr::FnCall { name: (name, _span), args } => format!(
+                "{}({})",
+                name.0,
+                args.iter().map(|arg| self.expr(arg)).collect::<Vec<String>>().join(", "),
+            ),
+            Expr::Member { expr, member: (member, _span) } => format!("{}.{}", self.expr(expr), member.0),
+            Expr::Neg(expr) => match self.expr(expr) {
+                // `--` would be lexed as a decrement
+                expr if expr.starts_with('-') => format!("- {}", expr),
+                expr => format!("-{}", expr),
+            },
+            Expr::Not(expr) => format!("!{}", self.expr(expr)),
+            Expr::Star(lhs, rhs) => bin_op(lhs, "*", rhs),
+            Expr::FSlash(lhs, rhs) => bin_op(lhs, "/", rhs),
+            Expr::PCent(lhs, rhs) => bin_op(lhs, "%", rhs),
+            Expr::Plus(lhs, rhs) => bin_op(lhs, "+", rhs),
+            Expr::Minus(lhs, rhs) => bin_op(lhs, "-", rhs),
+            Expr::Lt(lhs, rhs) => bin_op(lhs, "<", rhs),
+            Expr::Gt(lhs, rhs) => bin_op(lhs, ">", rhs),
+            Expr::Le(lhs, rhs) => bin_op(lhs, "<=", rhs),
+            Expr::Ge(lhs, rhs) => bin_op(lhs, ">=", rhs),
+            Expr::Eq(lhs, rhs) => bin_op(lhs, "==", rhs),
+            Expr::Ne(lhs, rhs) => bin_op(lhs, "!=", rhs),
+            Expr::And(lhs, rhs) => bin_op(lhs, "&&", rhs),
+            Expr::Or(lhs, rhs) => bin_op(lhs, "||", rhs),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use crate::formatter::Printer;
+use crate::parsing::ast::astree::{ASTreeNode, Body, RuleOpt};
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::span::Spanned;
+
+impl Printer<'_> {
+    pub fn stmt(&mut self, (stmt, span): &Spanned<ASTreeNode>) {
+        self.comments_before(span.start);
+        self.blank_line_before(span.start);
+
+        match stmt {
+            ASTreeNode::Include((_path, path_span)) => {
+                self.line(&format!("include {};", &self.src[path_span.into_range()]));
+            }
+            ASTreeNode::InfiniteLoopLimit((val, _val_span)) => {
+                self.line(&format!("infiniteLoopLimit {};", val));
+            }
+            ASTreeNode::InfiniteRecursionLimit((val, _val_span)) => {
+                self.line(&format!("infiniteRecursionLimit {};", val));
+            }
+            ASTreeNode::VarDef { .. } => {
+                self.line(&format!("{};", self.var_def(stmt)));
+            }
+            ASTreeNode::VarAssign { name: (name, _name_span), value } => {
+                self.line(&format!("{} = {};", name.0, self.expr(value)));
+            }
+            ASTreeNode::RuleDef { name: (name, _name_span), rule_opts, body, .. } => {
+                let header = format!("rule {}", name.0);
+                if rule_opts.is_empty() {
+                    self.open(&header);
+                } else {
+                    // rules with options are written with each option on its own line, so the
+                    // brace always goes on the next line
+                    self.line(&header);
+                    self.depth += 1;
+                    for (opt, _opt_span) in rule_opts {
+                        let opt = self.rule_opt(opt);
+                        self.line(&opt);
+                    }
+                    self.depth -= 1;
+                    self.line("{");
+                    self.depth += 1;
+                    self.last_end = None;
+                }
+                self.body(body);
+                self.close("");
+            }
+            ASTreeNode::FnDef {
+                is_extern, is_export, is_mutable, return_type, name: (name, _name_span), params, body, ..
+            } => {
+                let params = params.iter()
+                    .map(|param| format!("{} {} = {}", param.type_, param.name.0.0, self.expr(&param.default)))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                self.open(&format!(
+                    "{}{}{}{} {}({})",
+                    if *is_extern { "extern " } else { "" },
+                    if *is_export { "export " } else { "" },
+                    if *is_mutable { "mutable " } else { "" },
+                    return_type,
+                    name.0,
+                    params,
+                ));
+                self.body(body);
+                self.close("");
+            }
+            ASTreeNode::Return(value) => match value {
+                Some(value) => self.line(&format!("return {};", self.expr(value))),
+                None => self.line("return;"),
+            },
+            ASTreeNode::IfElse { .. } => {
+                let mut stmt = stmt;
+                let mut header = format!("{}({})", self.keyword("if"), self.cond(stmt));
+                self.open(&header);
+                // `else { if (...) {} }` is written as `else if (...) {}`
+                loop {
+                    let ASTreeNode::IfElse { consequent, alternate, .. } = stmt else {
+                        unreachable!();
+                    };
+                    self.body(consequent);
+                    self.close("");
+
+                    let Some(alternate) = alternate else {
+                        break;
+                    };
+                    match &alternate.0.0[..] {
+                        [(else_if @ ASTreeNode::IfElse { .. }, _else_if_span)] => {
+                            stmt = else_if;
+                            header = format!("else {}({})", self.keyword("if"), self.cond(stmt));
+                            self.reopen(&header);
+                        }
+                        _ => {
+                            self.reopen("else");
+                            self.body(alternate);
+                            self.close("");
+                            break;
+                        }
+                    }
+                }
+            }
+            ASTreeNode::While { condition, body } => {
+                self.open(&format!("{}({})", self.keyword("while"), self.expr(condition)));
+                self.body(body);
+                self.close("");
+            }
+            ASTreeNode::For { var, condition, body } => {
+                let (ASTreeNode::VarAssign { name: (name, _name_span), value }, _span) = var.as_ref() else {
+                    unreachable!("for loops always start with an assignment");
+                };
+                let (op, bound) = match &condition.0 {
+                    Expr::Lt(_var, bound) => ("<", bound),
+                    Expr::Le(_var, bound) => ("<=", bound),
+                    Expr::Gt(_var, bound) => (">", bound),
+                    Expr::Ge(_var, bound) => (">=", bound),
+                    _ => unreachable!("for loop conditions are always comparisons"),
+                };
+                self.open(&format!(
+                    "{}({} = {}; {} {})",
+                    self.keyword("for"), name.0, self.expr(value), op, self.expr(bound),
+                ));
+                self.body(body);
+                self.close("");
+            }
+            ASTreeNode::Switch { clause, cases } => {
+                self.open(&format!("{}({})", self.keyword("switch"), self.expr(clause)));
+                for (case, body) in cases {
+                    let header = match case {
+                        Some(case) => {
+                            self.comments_before(case.1.start);
+                            format!("case {}:", self.expr(case))
+                        }
+                        None => "default:".to_string(),
+                    };
+                    self.open(&header);
+                    self.body(body);
+                    self.close("");
+                    self.last_end = Some(body.1.end);
+                    self.trailing_comment(body.1.end);
+                }
+                self.comments_before(span.end);
+                self.close("");
+            }
+            ASTreeNode::PostDPlus((name, _span)) => self.line(&format!("{}++;", name.0)),
+            ASTreeNode::PostDMinus((name, _span)) => self.line(&format!("{}--;", name.0)),
+            ASTreeNode::Break => self.line("break;"),
+            ASTreeNode::Continue => self.line("continue;"),
+            ASTreeNode::LabelDef((name, _span)) => self.line(&format!("label {};", name.0)),
+            ASTreeNode::Goto((name, _span)) => self.line(&format!("goto {};", name.0)),
+            ASTreeNode::Discarded(expr) => self.line(&format!("{};", self.expr(expr))),
+            ASTreeNode::Debug((name, _span)) => self.line(&format!("dbg {};", name.0)),
+            ASTreeNode::Breakpoint => self.line("breakpoint;"),
+            ASTreeNode::Class { name: (name, _name_span), member_vars } => {
+                self.open(&format!("class {}", name.0));
+                for (var, var_span) in member_vars {
+                    self.comments_before(var_span.start);
+                    self.blank_line_before(var_span.start);
+                    self.line(&format!("{};", self.var_def(var)));
+                    self.last_end = Some(var_span.end);
+                    self.trailing_comment(var_span.end);
+                }
+                self.comments_before(span.end);
+                self.close(";");
+            }
+        }
+
+        self.last_end = Some(span.end);
+        self.trailing_comment(span.end);
+    }
+
+    /// The statements of a block and any comments before its closing brace
+    fn body(&mut self, (body, span): &Spanned<Body>) {
+        for stmt in body.0.iter() {
+            self.stmt(stmt);
+        }
+        self.comments_before(span.end);
+    }
+
+    fn cond(&self, stmt: &ASTreeNode) -> String {
+        let ASTreeNode::IfElse { condition, .. } = stmt else {
+            unreachable!();
+        };
+        self.expr(condition)
+    }
+
+    fn var_def(&self, stmt: &ASTreeNode) -> String {
+        let ASTreeNode::VarDef { is_extern, is_const, is_static, type_, name: (name, _span), value, .. } = stmt else {
+            unreachable!();
+        };
+
+        let mut def = String::new();
+        for (is_set, modifier) in [(is_extern, "extern "), (is_static, "static "), (is_const, "const ")] {
+            if *is_set {
+                def.push_str(modifier);
+            }
+        }
+        def.push_str(&format!("{} {}", type_, name.0));
+        if let Some(value) = value {
+            def.push_str(&format!(" = {}", self.expr(value)));
+        }
+
+        def
+    }
+
+    fn rule_opt(&self, opt: &RuleOpt) -> String {
+        match opt {
+            RuleOpt::Active => "active".to_string(),
+            RuleOpt::Inactive => "inactive".to_string(),
+            RuleOpt::RunImmediately => "runImmediately".to_string(),
+            RuleOpt::HighFrequency => "highFrequency".to_string(),
+            RuleOpt::MinInterval((val, _span)) => format!("minInterval {}", val),
+            RuleOpt::MaxInterval((val, _span)) => format!("maxInterval {}", val),
+            RuleOpt::Priority((val, _span)) => format!("priority {}", val),
+            RuleOpt::Group((_grp, span)) => format!("group {}", &self.src[span.into_range()]),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+pub mod api;
+pub mod project;
+
+pub(crate) mod checker;
+pub(crate) mod codegen;
+pub(crate) mod diagnostics;
+pub(crate) mod dynamic;
+pub(crate) mod formatter;
+pub(crate) mod lsp;
+pub(crate) mod parsing;
+pub(crate) mod r#static;
+pub(crate) mod vm;
+
+pub use api::{check_file, check_project, check_source, Diagnostic, Options, Report};
+pub use project::Project;
+
+/// The modules behind [api], for the `xs-check` binaries and the tests. They expose the parser's
+/// types, so they are not part of the public API and may change in any release
+#[doc(hidden)]
+pub mod internal {
+    pub mod checker { pub use crate::checker::*; }
+    pub mod codegen { pub use crate::codegen::*; }
+    pub mod diagnostics { pub use crate::diagnostics::*; }
+    pub mod dynamic { pub use crate::dynamic::*; }
+    pub mod formatter { pub use crate::formatter::*; }
+    pub mod lsp { pub use crate::lsp::*; }
+    pub mod parsing { pub use crate::parsing::*; }
+    pub mod r#static { pub use crate::r#static::*; }
+    pub mod vm { pub use crate::vm::*; }
+}
@@ -0,0 +1,270 @@
+pub mod convert;
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as LspNotification, PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest, Request as LspRequest};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, InitializeParams, Location, MarkupContent, MarkupKind, OneOf,
+    PublishDiagnosticsParams, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    Url,
+};
+use serde_json::Value;
+use crate::api::Options;
+use crate::checker::finish_diagnostics;
+use crate::lsp::convert::{diagnostic, offset, range};
+use crate::parsing::ast::type_::Type;
+use crate::parsing::lexer::keyword::KEYWORDS;
+use crate::project::Project;
+use crate::r#static::module_loader::{xs_tc_module, FileId, ModuleLoader};
+use crate::r#static::prelude::prelude;
+use crate::r#static::symbols::{index_module, SymbolKind, SymbolTable};
+
+type LspResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// The result of checking an open document
+struct Analysis {
+    loader: ModuleLoader,
+    file_id: FileId,
+    symbols: SymbolTable,
+}
+
+struct Server {
+    search_roots: Vec<PathBuf>,
+    /// the contents of every open document
+    docs: HashMap<Url, String>,
+    analyses: HashMap<Url, Analysis>,
+}
+
+/// Runs a language server over stdio until the client asks it to exit. Additional include search
+/// roots may be given as `{ "includeDirs": [...] }` in the client's initialization options
+pub fn run() -> LspResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        ..Default::default()
+    };
+    let params: InitializeParams = serde_json::from_value(
+        connection.initialize(serde_json::to_value(capabilities)?)?
+    )?;
+
+    let search_roots = params.initialization_options
+        .as_ref()
+        .and_then(|opts| opts.get("includeDirs"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(PathBuf::from)
+        .collect();
+
+    let mut server = Server { search_roots, docs: HashMap::new(), analyses: HashMap::new() };
+    for msg in &connection.receiver {
+        match msg {
+            Message::Request(req) => {
+                if connection.handle_shutdown(&req)? {
+                    break;
+                }
+                connection.sender.send(Message::Response(server.request(req)))?;
+            }
+            Message::Notification(notif) => {
+                for notif in server.notification(notif)? {
+                    connection.sender.send(Message::Notification(notif))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+
+    // the writer thread only stops once the connection is dropped
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn dispatch<R: LspRequest>(
+    params: Value,
+    handler: impl FnOnce(R::Params) -> R::Result,
+) -> serde_json::Result<Value> {
+    serde_json::to_value(handler(serde_json::from_value(params)?))
+}
+
+impl Server {
+    fn request(&self, req: Request) -> Response {
+        let result = match req.method.as_str() {
+            HoverRequest::METHOD => dispatch::<HoverRequest>(req.params, |params| self.hover(params)),
+            GotoDefinition::METHOD => dispatch::<GotoDefinition>(req.params, |params| self.definition(params)),
+            Completion::METHOD => dispatch::<Completion>(req.params, |params| self.completion(params)),
+            method => {
+                return Response::new_err(
+                    req.id, ErrorCode::MethodNotFound as i32, format!("Unknown method `{}`", method)
+                );
+            }
+        };
+
+        match result {
+            Ok(result) => Response::new_ok(req.id, result),
+            Err(err) => Response::new_err(req.id, ErrorCode::InvalidParams as i32, err.to_string()),
+        }
+    }
+
+    /// Returns the diagnostics to publish in response to the notification
+    fn notification(&mut self, notif: Notification) -> LspResult<Vec<Notification>> {
+        match notif.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: <DidOpenTextDocument as LspNotification>::Params = serde_json::from_value(notif.params)?;
+                self.docs.insert(params.text_document.uri, params.text_document.text);
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: <DidChangeTextDocument as LspNotification>::Params = serde_json::from_value(notif.params)?;
+                // only full document sync is advertised
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.docs.insert(params.text_document.uri, change.text);
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: <DidCloseTextDocument as LspNotification>::Params = serde_json::from_value(notif.params)?;
+                self.docs.remove(&params.text_document.uri);
+                self.analyses.remove(&params.text_document.uri);
+                return Ok(vec![publish(params.text_document.uri, vec![])?]);
+            }
+            _ => return Ok(vec![]),
+        }
+
+        // open documents may include each other, so they are all checked again
+        self.analyses.clear();
+        let uris = self.docs.keys().cloned().collect::<Vec<Url>>();
+        let mut notifs = vec![];
+        for uri in uris {
+            if let Some(diagnostics) = self.analyse(&uri) {
+                notifs.push(publish(uri, diagnostics)?);
+            }
+        }
+
+        Ok(notifs)
+    }
+
+    /// How the document at `path` is checked, the settings of the nearest project file in or above
+    /// its directory with the client's include directories added
+    fn options(&self, path: &Path) -> Options {
+        let project = path.ancestors()
+            .skip(1)
+            .find_map(Project::find)
+            .and_then(|config| Project::load(&config).map_err(|err| eprintln!("error: {}", err)).ok());
+        let mut options = project.map(|project| project.options).unwrap_or_default();
+        options.search_roots.extend(self.search_roots.iter().cloned());
+        options
+    }
+
+    /// Type checks and indexes an open document, returning its diagnostics
+    fn analyse(&mut self, uri: &Url) -> Option<Vec<lsp_types::Diagnostic>> {
+        let path = uri.to_file_path().ok()?;
+
+        let options = self.options(&path);
+        let mut loader = ModuleLoader::new(options.search_roots.clone());
+        for (doc_uri, text) in self.docs.iter() {
+            if let Ok(doc_path) = doc_uri.to_file_path() {
+                loader.set_source(&doc_path, text.clone());
+            }
+        }
+
+        let mut type_env = prelude(options.prelude);
+        let mut errs = vec![];
+        xs_tc_module(&path, &mut loader, &mut type_env, &mut HashSet::new(), &mut errs).ok()?;
+        finish_diagnostics(&mut errs, &options);
+
+        let module = loader.load(&path).ok()?;
+        let symbols = index_module(&path, &mut loader, &type_env).ok()?;
+
+        let diagnostics = errs.iter()
+            .filter(|err| err.file_id == module.id)
+            .map(|err| diagnostic(&module, uri, err))
+            .collect();
+
+        self.analyses.insert(uri.clone(), Analysis { loader, file_id: module.id, symbols });
+        Some(diagnostics)
+    }
+
+    /// The analysis of a document and the byte offset of `pos` in it
+    fn locate(&self, uri: &Url, pos: lsp_types::Position) -> Option<(&Analysis, usize)> {
+        let analysis = self.analyses.get(uri)?;
+        let src = &analysis.loader.module(analysis.file_id).src;
+
+        Some((analysis, offset(src, pos)))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let doc_pos = params.text_document_position_params;
+        let (analysis, offset) = self.locate(&doc_pos.text_document.uri, doc_pos.position)?;
+        let symbol = analysis.symbols.symbol_at(analysis.file_id, offset)?;
+
+        let mut value = format!("```xs\n{}\n```\n---\ntype: `{}`", symbol.detail, symbol.type_);
+        if let Some(doc) = &symbol.doc {
+            value.push_str(&format!("\n\n{}", doc));
+        }
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value }),
+            range: None,
+        })
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let doc_pos = params.text_document_position_params;
+        let (analysis, offset) = self.locate(&doc_pos.text_document.uri, doc_pos.position)?;
+        let (file_id, span) = analysis.symbols.symbol_at(analysis.file_id, offset)?.def?;
+
+        let module = analysis.loader.module(file_id);
+        let uri = Url::from_file_path(&module.path).ok()?;
+
+        Some(GotoDefinitionResponse::Scalar(Location::new(uri, range(&module.src, &span))))
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let doc_pos = params.text_document_position;
+        let (analysis, offset) = self.locate(&doc_pos.text_document.uri, doc_pos.position)?;
+
+        let keywords = KEYWORDS.iter()
+            .chain(&["true", "false"])
+            .map(|kw| CompletionItem {
+                label: kw.to_string(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                ..Default::default()
+            });
+
+        let symbols = analysis.symbols.visible_at(analysis.file_id, offset)
+            .into_iter()
+            .map(|symbol| CompletionItem {
+                label: symbol.name.0.clone(),
+                kind: Some(match (symbol.kind, &symbol.type_) {
+                    (SymbolKind::Fn, _) | (SymbolKind::Builtin, Type::Func { .. }) => CompletionItemKind::FUNCTION,
+                    (SymbolKind::Const | SymbolKind::Builtin, _) => CompletionItemKind::CONSTANT,
+                    (SymbolKind::Var | SymbolKind::Param, _) => CompletionItemKind::VARIABLE,
+                    (SymbolKind::Rule, _) => CompletionItemKind::EVENT,
+                    (SymbolKind::Label, _) => CompletionItemKind::REFERENCE,
+                    (SymbolKind::Class, _) => CompletionItemKind::CLASS,
+                }),
+                detail: Some(symbol.detail.clone()),
+                ..Default::default()
+            });
+
+        Some(CompletionResponse::Array(keywords.chain(symbols).collect()))
+    }
+}
+
+fn publish(uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> serde_json::Result<Notification> {
+    Ok(Notification::new(
+        PublishDiagnostics::METHOD.to_string(),
+        serde_json::to_value(PublishDiagnosticsParams { uri, diagnostics, version: None })?,
+    ))
+}
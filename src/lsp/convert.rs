@@ -0,0 +1,66 @@
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString,
+    Position, Range, Url,
+};
+use crate::parsing::span::Span;
+use crate::r#static::module_loader::Module;
+use crate::r#static::xs_error::{Severity, XSError};
+
+/// The LSP position of a byte offset. LSP columns are counted in UTF-16 code units
+pub fn position(src: &str, offset: usize) -> Position {
+    let before = &src[..offset.min(src.len())];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    let character = before[line_start..].encode_utf16().count();
+
+    Position::new(line as u32, character as u32)
+}
+
+/// The byte offset of an LSP position, clamped to the end of its line
+pub fn offset(src: &str, pos: Position) -> usize {
+    let line_start = src.split_inclusive('\n')
+        .take(pos.line as usize)
+        .map(str::len)
+        .sum::<usize>();
+
+    let mut units = 0;
+    for (idx, ch) in src[line_start..].char_indices() {
+        if units >= pos.character as usize || ch == '\n' {
+            return line_start + idx;
+        }
+        units += ch.len_utf16();
+    }
+
+    src.len()
+}
+
+pub fn range(src: &str, span: &Span) -> Range {
+    Range::new(position(src, span.start), position(src, span.end))
+}
+
+pub fn diagnostic(module: &Module, uri: &Url, err: &XSError) -> Diagnostic {
+    let message = [err.msg.as_str()].into_iter()
+        .chain(err.notes.iter().map(String::as_str))
+        .collect::<Vec<&str>>()
+        .join("\n");
+
+    let related = err.related.iter()
+        .map(|(label, span)| DiagnosticRelatedInformation {
+            location: Location::new(uri.clone(), range(&module.src, span)),
+            message: label.clone(),
+        })
+        .collect();
+
+    Diagnostic {
+        range: range(&module.src, &err.span),
+        severity: Some(match err.severity {
+            Severity::Warning => DiagnosticSeverity::WARNING,
+            Severity::Error   => DiagnosticSeverity::ERROR,
+        }),
+        code: Some(NumberOrString::String(err.code.id().to_string())),
+        source: Some(env!("CARGO_PKG_NAME").to_string()),
+        message,
+        related_information: Some(related),
+        ..Default::default()
+    }
+}
@@ -1,42 +1,337 @@
-pub mod parsing;
-pub mod r#static;
-
-use std::{env, fs};
-use std::collections::{HashMap, HashSet};
-use chumsky::prelude::*;
-use crate::parsing::lexer::lexer;
-use crate::parsing::parser::parser;
-use crate::r#static::type_check::statements::xs_tc;
-
-fn main() {
-    let src = fs::read_to_string(
-        env::args().nth(1).expect("Filename not provided")
-    ).expect("Failed to read file");
-    
-    let (tokens, errs) = lexer()
-        .parse(src.as_str())
-        .into_output_errors();
-    
-    let Some(tokens) = tokens else {
-        println!("TokenizationErrors: {:?}", errs);
-        return;
+mod cli;
+
+use std::collections::HashSet;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::fs;
+use std::fs::FileType;
+use clap::Parser;
+use crate::cli::{CheckArgs, Cli, Command, Emit, FmtArgs, Format, RunArgs};
+use xs_check::api::Options;
+use xs_check::project::{Project, CONFIG_FILE};
+use xs_check::internal::checker::check_entries;
+use xs_check::internal::codegen::bc::lower::lower_program;
+use xs_check::internal::codegen::bc::Bytecode;
+use xs_check::internal::codegen::pa::munch::lower_module;
+use xs_check::internal::diagnostics::github::write_github;
+use xs_check::internal::diagnostics::json::write_json;
+use xs_check::internal::diagnostics::sarif::write_sarif;
+use xs_check::internal::diagnostics::{display_path, render, render_runtime_error, summary};
+use xs_check::internal::dynamic::interpreter::Interpreter;
+use xs_check::internal::dynamic::scheduler::SchedulerConfig;
+use xs_check::internal::formatter::{format_module, FmtConfig, Indent};
+use xs_check::internal::r#static::cfg::build::module_cfgs;
+use xs_check::internal::r#static::module_loader::{xs_tc_module, ModuleLoader};
+use xs_check::internal::r#static::prelude::{prelude, PreludeVersion};
+use xs_check::internal::vm::machine::Vm;
+
+/// Adds `path` to `files` if it is a file, or every `.xs` file under it if it is a directory.
+/// Symlinked directories under `path` are not followed and a file reached through several paths is
+/// only added once, so links cannot make a file be checked more than once
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>, seen: &mut HashSet<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        // a file which cannot be canonicalised does not exist, it is reported when it is read
+        if seen.insert(path.canonicalize().unwrap_or_else(|_| path.to_path_buf())) {
+            files.push(path.to_path_buf());
+        }
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.and_then(|entry| Ok((entry.path(), entry.file_type()?))))
+        .collect::<io::Result<Vec<(PathBuf, FileType)>>>()?;
+    entries.sort_by(|(path1, _), (path2, _)| path1.cmp(path2));
+
+    for (entry, file_type) in entries {
+        // unlike `Path::is_dir`, the file type of a directory entry does not follow symlinks
+        let is_xs_file = entry.extension().is_some_and(|ext| ext == "xs") && !entry.is_dir();
+        if file_type.is_dir() || is_xs_file {
+            collect_files(&entry, files, seen)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// All the files given on the command line, and whether any of the paths could not be read
+fn collect_all(paths: &[PathBuf]) -> (Vec<PathBuf>, bool) {
+    let mut files = vec![];
+    let mut seen = HashSet::new();
+    let mut io_err = false;
+    for path in paths {
+        if let Err(err) = collect_files(path, &mut files, &mut seen) {
+            eprintln!("error: cannot read `{}`: {}", display_path(path), err);
+            io_err = true;
+        }
+    }
+
+    (files, io_err)
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Fmt(args)) => fmt(args),
+        Some(Command::Run(args)) => run(args),
+        None => check(cli.check),
+    }
+}
+
+/// The paths to check and how to check them, from the project file if no paths were given
+fn project_or_paths(args: &CheckArgs) -> Result<(Vec<PathBuf>, Options), ExitCode> {
+    let project_path = match &args.project {
+        Some(path) => path.clone(),
+        None if !args.paths.is_empty() => {
+            let options = Options { search_roots: args.search_roots.clone(), ..Options::default() };
+            return Ok((args.paths.clone(), options));
+        }
+        None => match Project::find(Path::new(".")) {
+            Some(path) => path,
+            None => {
+                eprintln!("error: no files were given and there is no `{}` in this directory", CONFIG_FILE);
+                return Err(ExitCode::from(2));
+            }
+        },
     };
 
-    let (ast, parse_errors) = parser()
-        .map_with(|ast, e| (ast, e.span()))
-        .parse(tokens.as_slice().spanned((src.len()..src.len()).into()))
-        .into_output_errors();
+    match Project::load(&project_path) {
+        Ok(mut project) => {
+            project.options.search_roots.extend(args.search_roots.iter().cloned());
+            Ok((project.entries, project.options))
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            Err(ExitCode::from(2))
+        }
+    }
+}
 
-    let Some((ast, _span)) = ast else {
-        println!("ParsingErrors: {:?}", parse_errors);
-        return;
+fn check(args: CheckArgs) -> ExitCode {
+    let (paths, options) = match project_or_paths(&args) {
+        Ok(found) => found,
+        Err(code) => return code,
     };
-    
-    let mut type_env = HashMap::new();
-    let mut groups = HashSet::new();
+    if let Some(form) = args.emit {
+        return emit(&paths, &options, form);
+    }
+
+    let (files, mut io_err) = collect_all(&paths);
+
+    // one loader is shared by all the files, so the files they include are only parsed once
+    let mut loader = ModuleLoader::new(options.search_roots.clone());
+    let paths = files.iter().inspect(|path| if args.verbose {
+        eprintln!("Checking {}", display_path(path));
+    });
+    let mut errs = check_entries(&mut loader, paths, &options, |path, err| {
+        eprintln!("error: cannot read `{}`: {}", display_path(path), err);
+        io_err = true;
+    });
+
+    let num_errs = errs.iter().filter(|err| !err.is_warning()).count();
+    let failed = io_err || num_errs > 0 || (args.deny_warnings && num_errs < errs.len());
+
+    if args.quiet {
+        errs.retain(|err| !err.is_warning());
+    }
+
+    let written = match args.format {
+        Format::Human => render(&loader, &errs, io::stderr().is_terminal(), io::stderr()),
+        Format::Json => write_json(&loader, &errs, io::stdout()),
+        Format::Sarif => write_sarif(&loader, &errs, io::stdout()),
+        Format::Github => write_github(&loader, &errs, io::stdout()),
+    };
+    if let Err(err) = written {
+        eprintln!("error: failed to write diagnostics: {}", err);
+        return ExitCode::from(2);
+    }
+
+    if !args.quiet {
+        eprintln!("{}", summary(&errs, loader.modules().count()));
+    }
+
+    match (io_err, failed) {
+        (true, _) => ExitCode::from(2),
+        (_, true) => ExitCode::FAILURE,
+        _ => ExitCode::SUCCESS,
+    }
+}
+
+fn emit(paths: &[PathBuf], options: &Options, form: Emit) -> ExitCode {
+    let (files, mut io_err) = collect_all(paths);
+    let mut loader = ModuleLoader::new(options.search_roots.clone());
+    let mut failed = false;
+    for path in files.iter() {
+        let module = match loader.load(path) {
+            Ok(module) => module,
+            Err(err) => {
+                eprintln!("error: cannot read `{}`: {}", display_path(path), err);
+                io_err = true;
+                continue;
+            }
+        };
+
+        if !module.errs.is_empty() {
+            let _ = render(&loader, &module.errs, io::stderr().is_terminal(), io::stderr());
+            eprintln!("error: cannot lower `{}` as it could not be parsed", display_path(path));
+            failed = true;
+            continue;
+        }
+
+        match form {
+            Emit::Cfg => for cfg in module_cfgs(&module.ast) {
+                print!("{}", cfg.to_dot(&module.src));
+            },
+            Emit::Pa => print!("{}", lower_module(&module, &mut loader, &prelude(options.prelude))),
+            Emit::Bc => {
+                let program = lower_module(&module, &mut loader, &prelude(options.prelude));
+                print!("{}", lower_program(&program));
+            }
+        }
+    }
+
+    match (io_err, failed) {
+        (true, _) => ExitCode::from(2),
+        (_, true) => ExitCode::FAILURE,
+        _ => ExitCode::SUCCESS,
+    }
+}
+
+fn fmt(args: FmtArgs) -> ExitCode {
+    let cfg = FmtConfig {
+        indent: if args.tabs { Indent::Tabs } else { Indent::Spaces(args.indent_width) },
+        brace_style: args.brace_style,
+        space_after_keyword: !args.no_space_after_keyword,
+    };
+
+    let (files, mut io_err) = collect_all(&args.paths);
+    let mut loader = ModuleLoader::new(vec![]);
+    let mut failed = false;
+    for path in files.iter() {
+        let module = match loader.load(path) {
+            Ok(module) => module,
+            Err(err) => {
+                eprintln!("error: cannot read `{}`: {}", display_path(path), err);
+                io_err = true;
+                continue;
+            }
+        };
+
+        if !module.errs.is_empty() {
+            let _ = render(&loader, &module.errs, io::stderr().is_terminal(), io::stderr());
+            eprintln!("error: cannot format `{}` as it could not be parsed", display_path(path));
+            failed = true;
+            continue;
+        }
+
+        let formatted = format_module(&module, &cfg);
+        if formatted == module.src {
+            continue;
+        }
+
+        if args.check {
+            println!("{}", display_path(path));
+            failed = true;
+        } else if let Err(err) = fs::write(path, formatted) {
+            eprintln!("error: cannot write `{}`: {}", display_path(path), err);
+            io_err = true;
+        }
+    }
+
+    match (io_err, failed) {
+        (true, _) => ExitCode::from(2),
+        (_, true) => ExitCode::FAILURE,
+        _ => ExitCode::SUCCESS,
+    }
+}
+
+fn run(args: RunArgs) -> ExitCode {
+    let mut loader = ModuleLoader::new(args.search_roots.clone());
     let mut errs = vec![];
+    let mut type_env = prelude(PreludeVersion::LATEST);
+    if let Err(err) = xs_tc_module(&args.path, &mut loader, &mut type_env, &mut HashSet::new(), &mut errs) {
+        eprintln!("error: cannot read `{}`: {}", display_path(&args.path), err);
+        return ExitCode::from(2);
+    }
+
+    // warnings are left to `xs-check` itself, only errors stop a script from running
+    errs.retain(|err| !err.is_warning());
+    if !errs.is_empty() {
+        let _ = render(&loader, &errs, io::stderr().is_terminal(), io::stderr());
+        eprintln!("error: cannot run `{}` as it has errors", display_path(&args.path));
+        return ExitCode::FAILURE;
+    }
+
+    let module = match loader.load(&args.path) {
+        Ok(module) => module,
+        Err(err) => {
+            eprintln!("error: cannot read `{}`: {}", display_path(&args.path), err);
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.vm {
+        let program = lower_module(&module, &mut loader, &prelude(PreludeVersion::LATEST));
+        return run_on_vm(&args, &lower_program(&program));
+    }
+
+    let mut stdout = io::stdout().lock();
+    let mut interpreter = Interpreter::new(PreludeVersion::LATEST, &mut stdout);
+    interpreter.max_steps = (args.max_steps > 0).then_some(args.max_steps);
+    let result = interpreter.load(module, &mut loader).and_then(|_| {
+        // scripts which only consist of rules need no entry when simulated
+        let has_entry = interpreter.is_callable(&args.entry);
+        if has_entry {
+            interpreter.call(&args.entry, vec![])?;
+        }
+        if let Some(secs) = args.simulate {
+            let cfg = SchedulerConfig { tick: args.tick, rules_per_tick: args.rules_per_tick, trace: true };
+            interpreter.simulate(secs * 1000, &cfg)?;
+        }
+        Ok(has_entry || args.simulate.is_some())
+    });
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => {
+            eprintln!("error: `{}` has no function or rule named `{}`", display_path(&args.path), args.entry);
+            ExitCode::FAILURE
+        }
+        Err(err) => {
+            let _ = render_runtime_error(&loader, &err, io::stderr().is_terminal(), io::stderr());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs a script like [run], on the VM with the interpreter as the host of the builtins
+fn run_on_vm(args: &RunArgs, bc: &Bytecode) -> ExitCode {
+    let mut stdout = io::stdout().lock();
+    let mut host = Interpreter::new(PreludeVersion::LATEST, &mut stdout);
+    let mut vm = Vm::new(bc, &mut host, PreludeVersion::LATEST);
+    vm.max_steps = (args.max_steps > 0).then_some(args.max_steps);
+    let result = vm.load().and_then(|_| {
+        let has_entry = vm.is_callable(&args.entry);
+        if has_entry {
+            vm.call(&args.entry, vec![])?;
+        }
+        if let Some(secs) = args.simulate {
+            let cfg = SchedulerConfig { tick: args.tick, rules_per_tick: args.rules_per_tick, trace: true };
+            vm.simulate(secs * 1000, &cfg)?;
+        }
+        Ok(has_entry || args.simulate.is_some())
+    });
 
-    xs_tc(&ast, &mut type_env, &mut groups, &mut errs);
-    println!("TypeEnv: {:?}", type_env);
-    println!("Errors: {:?}", errs);
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => {
+            eprintln!("error: `{}` has no function or rule named `{}`", display_path(&args.path), args.entry);
+            ExitCode::FAILURE
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
 }
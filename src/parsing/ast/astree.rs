@@ -22,6 +22,8 @@ pub enum RuleOpt {
 #[derive(Debug, Clone)]
 pub enum ASTreeNode {
     Include(Spanned<String>),
+    InfiniteLoopLimit(Spanned<i64>),
+    InfiniteRecursionLimit(Spanned<i64>),
     VarDef {
         is_extern: bool,              // no extern inside locals
         is_const: bool,               // only literals can be assigned to consts, no exprs allowed
@@ -29,6 +31,7 @@ pub enum ASTreeNode {
         type_: Type,
         name: Spanned<Identifier>,
         value: Option<Spanned<Expr>>, // only literals allowed in top level, strings are bugged, vecs are fine. Top levels can't be decls
+        doc: Option<Spanned<String>>, // only for top level vars
     },
     VarAssign {
         name: Spanned<Identifier>,
@@ -38,13 +41,17 @@ pub enum ASTreeNode {
         name: Spanned<Identifier>,
         rule_opts: Vec<Spanned<RuleOpt>>,
         body: Spanned<Body>,
+        doc: Option<Spanned<String>>,
     },
     FnDef {
+        is_extern: bool,
+        is_export: bool,
         is_mutable: bool,
         return_type: Type,
         name: Spanned<Identifier>,
         params: Vec<Param>,
         body: Spanned<Body>,
+        doc: Option<Spanned<String>>,
     },
     Return (Option<Spanned<Expr>>), // must always be a parenthesized expr
     IfElse {
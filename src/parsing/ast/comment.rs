@@ -1,4 +1,7 @@
+use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::span::{Span, Spanned};
 
 #[derive(Debug, Clone)]
 pub struct Comment(pub String);
@@ -7,7 +10,7 @@ pub struct Comment(pub String);
 impl Hash for Comment {
     fn hash<H: Hasher>(&self, state: &mut H) {
         state.write_i32(6);
-        state.finish();
+        let _ = state.finish();
     }
 }
 
@@ -23,4 +26,156 @@ impl Comment {
     pub fn new(cmt: &str) -> Self {
         Comment(cmt.to_string())
     }
+
+    /// `/// ...` and `/** ... */` comments document the definition which follows them. Comments
+    /// starting with more slashes or stars are regular comments, like `//// ...` or `/*** ... */`
+    pub fn is_doc(&self) -> bool {
+        let cmt = self.0.as_str();
+        (cmt.starts_with("///") && !cmt.starts_with("////"))
+            || (cmt.starts_with("/**") && !cmt.starts_with("/***") && cmt != "/**/")
+    }
+
+    /// The text of a doc comment without its markers. The leading `*` of each line of a block
+    /// comment is removed too
+    pub fn doc_text(&self) -> String {
+        let cmt = self.0.as_str();
+        if let Some(line) = cmt.strip_prefix("///") {
+            return line.strip_prefix(' ').unwrap_or(line).trim_end().to_string();
+        }
+        let block = cmt.strip_prefix("/**").unwrap_or(cmt);
+        let block = block.strip_suffix("*/").unwrap_or(block);
+        let lines = block.lines()
+            .map(|line| {
+                let line = line.trim();
+                let line = line.strip_prefix('*').unwrap_or(line);
+                line.strip_prefix(' ').unwrap_or(line).trim_end()
+            })
+            .collect::<Vec<&str>>();
+        let start = lines.iter().position(|line| !line.is_empty()).unwrap_or(lines.len());
+        let end = lines.iter().rposition(|line| !line.is_empty()).map_or(start, |end| end + 1);
+
+        lines[start..end].join("\n")
+    }
+}
+
+/// The comments attached to a statement
+#[derive(Debug, Clone, Default)]
+pub struct Attached {
+    /// comments between the previous statement in the same block and this one
+    pub leading: Vec<Spanned<Comment>>,
+    /// a comment following the statement on the line it ends on
+    pub trailing: Option<Spanned<Comment>>,
+    /// comments inside the statement which are not in one of its blocks, e.g. in a condition
+    pub inner: Vec<Spanned<Comment>>,
+}
+
+/// The comments attached to each statement, keyed by the span of the statement
+pub type AttachedComments = HashMap<Span, Attached>;
+
+/// Attaches each comment to the nearest statement. A comment on the same line as the end of a
+/// statement trails it, any other comment leads the next statement in the same block. Comments
+/// after the last statement of a block are not attached to anything
+pub fn attach_comments(
+    stmts: &[Spanned<ASTreeNode>],
+    comments: &[Spanned<Comment>],
+    src: &str,
+) -> AttachedComments {
+    let mut attached = AttachedComments::new();
+    attach_block(stmts, comments, src, &mut attached);
+    attached
+}
+
+fn attach_block(
+    stmts: &[Spanned<ASTreeNode>],
+    comments: &[Spanned<Comment>],
+    src: &str,
+    attached: &mut AttachedComments,
+) {
+    // comments inside each statement, by the index of the statement
+    let mut nested: HashMap<usize, Vec<Spanned<Comment>>> = HashMap::new();
+    let mut idx = 0;
+    for cmt in comments {
+        while stmts.get(idx).is_some_and(|(_stmt, span)| span.end <= cmt.1.start) {
+            idx += 1;
+        }
+        if stmts.get(idx).is_some_and(|(_stmt, span)| span.start <= cmt.1.start) {
+            nested.entry(idx).or_default().push(cmt.clone());
+            continue;
+        }
+        if let Some((_prev, prev_span)) = idx.checked_sub(1).map(|prev| &stmts[prev]) {
+            let has_trailing = attached.get(prev_span).is_some_and(|prev| prev.trailing.is_some());
+            if !has_trailing && !src[prev_span.end..cmt.1.start].contains('\n') {
+                attached.entry(*prev_span).or_default().trailing = Some(cmt.clone());
+                continue;
+            }
+        }
+        if let Some((_next, next_span)) = stmts.get(idx) {
+            attached.entry(*next_span).or_default().leading.push(cmt.clone());
+        }
+    }
+
+    for (idx, comments) in nested {
+        let (stmt, span) = &stmts[idx];
+        let blocks = blocks(stmt, span);
+        let mut block_comments = vec![vec![]; blocks.len()];
+        let mut inner = vec![];
+        for cmt in comments {
+            let block = blocks.iter()
+                .position(|(_stmts, span)| span.start <= cmt.1.start && cmt.1.end <= span.end);
+            match block {
+                Some(block) => block_comments[block].push(cmt),
+                None => inner.push(cmt),
+            }
+        }
+        for ((stmts, _span), comments) in blocks.iter().zip(block_comments) {
+            attach_block(stmts, &comments, src, attached);
+        }
+        if !inner.is_empty() {
+            attached.entry(*span).or_default().inner = inner;
+        }
+    }
+}
+
+/// The statements in each block of `stmt`, with the span of the block
+fn blocks<'a>(stmt: &'a ASTreeNode, span: &Span) -> Vec<(&'a [Spanned<ASTreeNode>], Span)> {
+    match stmt {
+        ASTreeNode::RuleDef { body: (body, body_span), .. }
+        | ASTreeNode::FnDef { body: (body, body_span), .. }
+        | ASTreeNode::While { body: (body, body_span), .. }
+        | ASTreeNode::For { body: (body, body_span), .. } => vec![(&body.0, *body_span)],
+        ASTreeNode::IfElse { consequent, alternate, .. } => [Some(consequent), alternate.as_ref()].into_iter()
+            .flatten()
+            .map(|(body, body_span)| (body.0.as_slice(), *body_span))
+            .collect(),
+        ASTreeNode::Switch { cases, .. } => cases.iter()
+            .map(|(_case, (body, body_span))| (body.0.as_slice(), *body_span))
+            .collect(),
+        ASTreeNode::Class { member_vars, .. } => vec![(member_vars, *span)],
+        _ => vec![],
+    }
+}
+
+/// The doc comment of a top level `FnDef`, `RuleDef` or `VarDef` is made of the doc comments
+/// leading it which are not followed by a regular comment
+pub fn attach_docs(ast: &mut [Spanned<ASTreeNode>], attached: &AttachedComments) {
+    for (stmt, span) in ast.iter_mut() {
+        let (
+            ASTreeNode::FnDef { doc, .. } | ASTreeNode::RuleDef { doc, .. } | ASTreeNode::VarDef { doc, .. }
+        ) = stmt else {
+            continue;
+        };
+        let Some(Attached { leading, .. }) = attached.get(span) else {
+            continue;
+        };
+        let start = leading.iter().rposition(|(cmt, _span)| !cmt.is_doc()).map_or(0, |idx| idx + 1);
+        let docs = &leading[start..];
+        let (Some((_first, first_span)), Some((_last, last_span))) = (docs.first(), docs.last()) else {
+            continue;
+        };
+        let text = docs.iter()
+            .map(|(cmt, _span)| cmt.doc_text())
+            .collect::<Vec<String>>()
+            .join("\n");
+        *doc = Some((text, Span::new(first_span.start, last_span.end)));
+    }
 }
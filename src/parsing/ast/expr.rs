@@ -12,6 +12,10 @@ pub enum Expr {
         name: Spanned<Identifier>,
         args: Vec<Spanned<Expr>>
     },
+    Member {
+        expr: Box<Spanned<Expr>>,
+        member: Spanned<Identifier>,
+    },
 
     Neg(Box<Spanned<Expr>>),
     Not(Box<Spanned<Expr>>),
@@ -5,6 +5,7 @@ pub enum Literal {
     Int(i64),
     Float(f64),
     Bool(bool),
+    /// the unescaped contents, without the quotes
     Str(String),
 }
 
@@ -30,12 +31,6 @@ impl Hash for Literal {
             Literal::Bool(val)  => val.hash(state),
             Literal::Str(val)   => val.hash(state),
         }
-        state.finish();
+        let _ = state.finish();
     }
 }
-
-impl Literal {
-    pub fn str(val: &str) -> Self {
-        Literal::Str(val.to_string())
-    }
-}
\ No newline at end of file
@@ -15,7 +15,7 @@ pub enum Type {
     // not real types in XS
     Label,
     Rule,
-    Func { is_mutable: bool, type_sign: Vec<Type> },
+    Func { is_mutable: bool, is_variadic: bool, type_sign: Vec<Type> },
     Class,
 }
 
@@ -45,16 +45,21 @@ impl Display for Type {
 
             Type::Label => write!(f, "label"),
             Type::Rule => write!(f, "rule"),
-            Type::Func { is_mutable, type_sign} => write!(
-                f,
-                "{}{}",
-                if *is_mutable { "mut " } else { "" },
-                type_sign
+            Type::Func { is_mutable, is_variadic, type_sign} => {
+                let mut types = type_sign
                     .iter()
                     .map(|type_| type_.to_string())
-                    .collect::<Vec<String>>()
-                    .join(" -> ")
-            ),
+                    .collect::<Vec<String>>();
+                if *is_variadic {
+                    types.insert(types.len()-1, "...".to_string());
+                }
+                write!(
+                    f,
+                    "{}{}",
+                    if *is_mutable { "mut " } else { "" },
+                    types.join(" -> ")
+                )
+            },
             Type::Class => write!(f, "class"),
         }
     }
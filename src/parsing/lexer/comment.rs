@@ -8,7 +8,10 @@ pub fn comment<'src>() -> impl Parser<
     'src, &'src str, Token, extra::Err<Rich<'src, char, Span>>
 > {
     just("//").ignore_then(none_of("\r\n").repeated())
-        .or(just("/*").ignore_then(none_of("*/").repeated()))
+        .or(just("/*")
+            .then(any().and_is(just("*/").not()).repeated())
+            .then(just("*/"))
+            .ignored())
         .to_slice()
         .map(|val: &str| Token::Comment(Comment::new(val)))
 }
\ No newline at end of file
@@ -3,6 +3,48 @@ use crate::parsing::ast::identifier::Identifier;
 use crate::parsing::span::{Span};
 use crate::parsing::lexer::token::Token;
 
+pub const KEYWORDS: &[&str] = &[
+    "vector",
+    "include",
+    "switch",
+    "case",
+    "while",
+    "break",
+    "default",
+    "rule",
+    "if",
+    "then",
+    "else",
+    "goto",
+    "label",
+    "for",
+    "dbg",
+    "return",
+    "void",
+    "int",
+    "bool",
+    "float",
+    "string",
+    "const",
+    "priority",
+    "minInterval",
+    "maxInterval",
+    "highFrequency",
+    "active",
+    "inactive",
+    "group",
+    "infiniteLoopLimit",
+    "infiniteRecursionLimit",
+    "breakpoint",
+    "static",
+    "continue",
+    "extern",
+    "export",
+    "runImmediately",
+    "mutable",
+    "class",
+];
+
 pub fn keyword<'src>() -> impl Parser<
     'src, &'src str, Token, extra::Err<Rich<'src, char, Span>>
 > {
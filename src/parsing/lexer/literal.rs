@@ -31,11 +31,10 @@ pub fn literal<'src>() -> impl Parser<
                     '"' => '"',
                     _ => c,
                 })
-            )).repeated()
+            )).repeated().collect::<String>()
         )
         .then_ignore(just('"'))
-        .to_slice()
-        .map(|val: &str| Token::Literal(Literal::str(val)));
+        .map(|val| Token::Literal(Literal::Str(val)));
     
     choice((
         float,
@@ -50,8 +50,17 @@ pub fn expression<'tokens>() -> impl Parser<
             id,
         )).boxed();
 
+        let member = expr7.clone()
+            .foldl_with(
+                just(Token::Dot).ignore_then(
+                    select! { Token::Identifier(id) => id }
+                        .map_with(|id, info| (id, info.span()))
+                ).repeated(),
+                |exp, member, info| (Expr::Member { expr: Box::new(exp), member }, info.span())
+            ).boxed();
+
         let unary = one_of([Token::Minus, Token::Excl]).or_not()
-            .then(expr7)
+            .then(member)
             .map_with(|(sign, exp), info| match sign {
                 Some(Token::Minus) => (Expr::Neg(Box::new(exp)), info.span()),
                 Some(_)  =>           (Expr::Not(Box::new(exp)), info.span()),
@@ -6,6 +6,7 @@ use crate::parsing::parser::parser_input::ParserInput;
 use crate::parsing::parser::statement::fn_def::fn_def;
 use crate::parsing::parser::statement::for_::for_;
 use crate::parsing::parser::statement::include::include;
+use crate::parsing::parser::statement::limit::limit;
 use crate::parsing::parser::statement::if_else::if_else;
 use crate::parsing::parser::statement::break_or_continue::break_or_continue_or_breakpt;
 use crate::parsing::parser::statement::class_def::class_def;
@@ -36,6 +37,7 @@ pub mod postfix;
 pub mod label_def_or_goto_or_dbg;
 pub mod discarded_expr;
 pub mod class_def;
+pub mod limit;
 
 pub fn statement<'tokens>() -> impl Parser<
     'tokens,
@@ -46,6 +48,7 @@ pub fn statement<'tokens>() -> impl Parser<
     recursive(|statement| {
         choice((
             include(),
+            limit(),
             var_def(),
             var_assign(),
             if_else(statement.clone()),
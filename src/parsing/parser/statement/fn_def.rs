@@ -35,7 +35,27 @@ pub fn fn_def<'tokens>(
             default
         });
     
-    just(Token::Mutable).or_not()
+    // a repeated modifier or `export` with `extern` is reported, but the definition is still parsed
+    let modifiers = one_of([Token::Extern, Token::Mutable, Token::Export])
+        .map_with(|tok, info| (tok, info.span()))
+        .repeated()
+        .collect::<Vec<Spanned<Token>>>()
+        .validate(|mods, _info, emitter| {
+            for (idx, (tok, span)) in mods.iter().enumerate() {
+                if mods[..idx].iter().any(|(other, _span)| other == tok) {
+                    emitter.emit(Rich::custom(*span, format!("`{}` may only be given once", tok)));
+                }
+            }
+            let find = |tok: Token| mods.iter()
+                .find(|(other, _span)| *other == tok)
+                .map(|(_tok, span)| *span);
+            if let (Some(_), Some(span)) = (find(Token::Extern), find(Token::Export)) {
+                emitter.emit(Rich::custom(span, "A function cannot be both `export` and `extern`"));
+            }
+            (find(Token::Extern).is_some(), find(Token::Mutable).is_some(), find(Token::Export).is_some())
+        });
+
+    modifiers
         .then(one_of([
             Token::Int, Token::Bool, Token::Float, Token::String, Token::Vector, Token::Void]
         )).then(
@@ -49,15 +69,18 @@ pub fn fn_def<'tokens>(
                 .delimited_by(just(Token::LParen), just(Token::RParen))
         ).then(body(statement))
         .map_with(|
-            ((((mutable, return_type), name), params), body),
+            (((((is_extern, is_mutable, is_export), return_type), name), params), body),
              info
         | (
             ASTreeNode::FnDef {
-                is_mutable: mutable.is_some(),
+                is_extern,
+                is_export,
+                is_mutable,
                 return_type: Type::from_tok(return_type),
                 name,
                 params,
                 body,
+                doc: None,
             },
             info.span(),
         ))
@@ -0,0 +1,35 @@
+use chumsky::prelude::*;
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::ast::literal::Literal;
+use crate::parsing::lexer::token::Token;
+use crate::parsing::parser::parser_input::ParserInput;
+use crate::parsing::span::{Span, Spanned};
+
+pub fn limit<'tokens>() -> impl Parser<
+    'tokens,
+    ParserInput<'tokens>,
+    Spanned<ASTreeNode>,
+    extra::Err<Rich<'tokens, Token, Span>>,
+> + Clone {
+    let int = just(Token::Minus).or_not()
+        .then(select! { Token::Literal(Literal::Int(val)) => val })
+        .map(|(sign, val)| if sign.is_some() { -val } else { val });
+
+    // a limit which is not positive still parses, so the checker can say why it is wrong
+    let limit = int.clone()
+        .foldl(
+            one_of([Token::Plus, Token::Minus]).then(int).repeated(),
+            |a, (op, b)| match op {
+                Token::Plus => a.saturating_add(b),
+                _           => a.saturating_sub(b),
+            }
+        ).map_with(|val, info| (val, info.span()));
+
+    one_of([Token::InfiniteLoopLimit, Token::InfiniteRecursionLimit])
+        .then(limit)
+        .then_ignore(just(Token::SColon))
+        .map_with(|(tok, val), info| (match tok {
+            Token::InfiniteLoopLimit => ASTreeNode::InfiniteLoopLimit(val),
+            _                        => ASTreeNode::InfiniteRecursionLimit(val),
+        }, info.span()))
+}
@@ -60,6 +60,7 @@ pub fn rule_def<'tokens>(
                 name,
                 rule_opts,
                 body,
+                doc: None,
             },
             info.span(),
         ))
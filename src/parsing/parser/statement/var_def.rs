@@ -28,6 +28,7 @@ pub fn var_def<'tokens>() -> impl Parser<
                 type_: Type::from_tok(type_),
                 name,
                 value,
+                doc: None,
             }, info.span())
         })
 }
\ No newline at end of file
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use crate::api::{LintLevel, Options};
+use crate::diagnostics::display_path;
+use crate::r#static::prelude::PreludeVersion;
+use crate::r#static::xs_error::{Code, Severity};
+
+/// The name of the file which makes a directory an XS project
+pub const CONFIG_FILE: &str = "xs-check.toml";
+
+/// The contents of a project file, e.g.
+///
+/// ```toml
+/// entries = ["main.xs", "campaign/scenario1.xs"]
+/// include-dirs = ["lib"]
+/// prelude = "latest"
+///
+/// [lints]
+/// XS1015 = "off"
+/// XS1001 = "error"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Config {
+    entries: Vec<PathBuf>,
+    #[serde(default)]
+    include_dirs: Vec<PathBuf>,
+    prelude: Option<String>,
+    #[serde(default)]
+    lints: HashMap<String, LintLevel>,
+}
+
+/// A set of entry scripts which are checked together, sharing the files they include
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    /// the project file
+    pub path: PathBuf,
+    /// the scripts which are run by the game, files which are only included are not entries
+    pub entries: Vec<PathBuf>,
+    pub options: Options,
+}
+
+/// A project file which cannot be read or is not valid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectError {
+    pub path: PathBuf,
+    pub msg: String,
+}
+
+impl Display for ProjectError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid project file `{}`: {}", display_path(&self.path), self.msg)
+    }
+}
+
+impl Project {
+    /// The project file in `dir`, if there is one
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        Some(dir.join(CONFIG_FILE)).filter(|path| path.is_file())
+    }
+
+    /// Reads the project file at `path`. Entries and include directories are relative to the
+    /// directory of the project file
+    pub fn load(path: &Path) -> Result<Project, ProjectError> {
+        let err = |msg: String| ProjectError { path: path.to_path_buf(), msg };
+        let src = fs::read_to_string(path).map_err(|io_err| err(io_err.to_string()))?;
+        let config = toml::from_str::<Config>(&src)
+            .map_err(|de_err| err(de_err.to_string().trim_end().to_string()))?;
+        if config.entries.is_empty() {
+            return Err(err("`entries` must name at least one script".to_string()));
+        }
+
+        let prelude = match config.prelude.as_deref() {
+            None | Some("latest") => PreludeVersion::LATEST,
+            Some("v1") => PreludeVersion::V1,
+            Some(version) => return Err(err(format!(
+                "unknown prelude version `{}`, expected `latest` or `v1`", version
+            ))),
+        };
+
+        let mut lints = HashMap::with_capacity(config.lints.len());
+        for (id, level) in config.lints {
+            let Some(code) = Code::from_id(&id) else {
+                return Err(err(format!("unknown lint `{}`", id)));
+            };
+            if code.severity() == Severity::Error {
+                return Err(err(format!("`{}` is an error, only warnings can be configured", id)));
+            }
+            lints.insert(code, level);
+        }
+
+        let root = path.parent().unwrap_or(Path::new(""));
+        Ok(Project {
+            path: path.to_path_buf(),
+            entries: config.entries.iter().map(|entry| root.join(entry)).collect(),
+            options: Options {
+                search_roots: config.include_dirs.iter().map(|dir| root.join(dir)).collect(),
+                prelude,
+                lints,
+            },
+        })
+    }
+}
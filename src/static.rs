@@ -1,2 +1,7 @@
 pub mod type_check;
 pub mod xs_error;
+pub mod module_loader;
+pub mod prelude;
+pub mod symbols;
+pub mod cfg;
+pub mod const_eval;
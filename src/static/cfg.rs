@@ -0,0 +1,172 @@
+use crate::dynamic::value::Value;
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::const_eval::{const_eval, Consts};
+
+pub mod build;
+pub mod dot;
+pub mod liveness;
+pub mod assignment;
+
+pub type BlockId = usize;
+
+/// A straight line piece of a body. Statements with bodies of their own are split up, only their
+/// condition or clause is kept
+#[derive(Debug, Clone)]
+pub enum Instr<'src> {
+    /// a statement without a body
+    Stmt(&'src Spanned<ASTreeNode>),
+    /// the condition of an `if`, `while` or `for`, the block branches on its value
+    Cond(&'src Spanned<Expr>),
+    /// the clause of a `switch`, the block branches to the matching case
+    Clause(&'src Spanned<Expr>),
+    /// the `X++` or `X--` a `for` loop runs after its body
+    Step { name: &'src Spanned<Identifier>, step: i32 },
+}
+
+impl<'src> Instr<'src> {
+    pub fn span(&self) -> Span {
+        match self {
+            Instr::Stmt((_, span)) | Instr::Cond((_, span)) | Instr::Clause((_, span)) => *span,
+            Instr::Step { name: (_, span), .. } => *span,
+        }
+    }
+
+    /// The variables this instruction reads, in the order they are read
+    pub fn uses(&self) -> Vec<Spanned<&'src Identifier>> {
+        let mut uses = vec![];
+        match self {
+            Instr::Stmt((stmt, _span)) => match stmt {
+                ASTreeNode::VarDef { value: Some(expr), .. }
+                | ASTreeNode::VarAssign { value: expr, .. }
+                | ASTreeNode::Return(Some(expr))
+                | ASTreeNode::Discarded(expr) => expr_uses(expr, &mut uses),
+                ASTreeNode::PostDPlus((name, span))
+                | ASTreeNode::PostDMinus((name, span))
+                | ASTreeNode::Debug((name, span)) => uses.push((name, *span)),
+                _ => {}
+            },
+            Instr::Cond(expr) | Instr::Clause(expr) => expr_uses(expr, &mut uses),
+            Instr::Step { name: (name, span), .. } => uses.push((name, *span)),
+        }
+
+        uses
+    }
+
+    /// The variable this instruction writes to, after it has read its uses
+    pub fn def(&self) -> Option<&'src Spanned<Identifier>> {
+        match self {
+            Instr::Stmt((stmt, _span)) => match stmt {
+                ASTreeNode::VarDef { name, .. }
+                | ASTreeNode::VarAssign { name, .. }
+                | ASTreeNode::PostDPlus(name)
+                | ASTreeNode::PostDMinus(name) => Some(name),
+                _ => None,
+            },
+            Instr::Step { name, .. } => Some(name),
+            Instr::Cond(_) | Instr::Clause(_) => None,
+        }
+    }
+}
+
+fn expr_uses<'src>((expr, span): &'src Spanned<Expr>, uses: &mut Vec<Spanned<&'src Identifier>>) {
+    match expr {
+        Expr::Literal(_) => {}
+        Expr::Identifier(name) => uses.push((name, *span)),
+        Expr::FnCall { args, .. } => {
+            for arg in args {
+                expr_uses(arg, uses);
+            }
+        }
+        Expr::Vec { x, y, z } => {
+            expr_uses(x, uses);
+            expr_uses(y, uses);
+            expr_uses(z, uses);
+        }
+        Expr::Paren(expr) | Expr::Neg(expr) | Expr::Not(expr) | Expr::Member { expr, .. } => {
+            expr_uses(expr, uses)
+        }
+        Expr::Star(expr1, expr2) | Expr::FSlash(expr1, expr2) | Expr::PCent(expr1, expr2)
+        | Expr::Plus(expr1, expr2) | Expr::Minus(expr1, expr2)
+        | Expr::Lt(expr1, expr2) | Expr::Gt(expr1, expr2) | Expr::Le(expr1, expr2) | Expr::Ge(expr1, expr2)
+        | Expr::Eq(expr1, expr2) | Expr::Ne(expr1, expr2)
+        | Expr::And(expr1, expr2) | Expr::Or(expr1, expr2) => {
+            expr_uses(expr1, uses);
+            expr_uses(expr2, uses);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Jump,
+    True,
+    False,
+    /// to the body of the case at this index
+    Case(usize),
+    /// to the `default` case, or past the `switch` when it has none
+    Default,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Block<'src> {
+    pub instrs: Vec<Instr<'src>>,
+    /// the span of the statement each instruction was lowered from, like the whole `for` loop
+    /// for its condition
+    pub stmts: Vec<Span>,
+    pub succs: Vec<(BlockId, Edge)>,
+    pub preds: Vec<BlockId>,
+}
+
+/// The control flow graph of a function or rule body. Control enters at [Cfg::ENTRY] and a
+/// `return` or the end of the body jumps to [Cfg::EXIT], which is always empty
+#[derive(Debug, Clone)]
+pub struct Cfg<'src> {
+    pub name: Spanned<Identifier>,
+    pub blocks: Vec<Block<'src>>,
+}
+
+impl<'src> Cfg<'src> {
+    pub const ENTRY: BlockId = 0;
+    pub const EXIT: BlockId = 1;
+
+    /// The value of the condition a block ends with, if it only depends on literals
+    pub fn const_cond(&self, id: BlockId) -> Option<bool> {
+        let Some(Instr::Cond(expr)) = self.blocks[id].instrs.last() else {
+            return None;
+        };
+        match const_eval(expr, &Consts::new()) {
+            Some(Value::Bool(val)) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Whether control can flow along an edge out of a block. A constant condition only ever takes
+    /// one of its edges
+    pub fn is_taken(&self, id: BlockId, edge: Edge) -> bool {
+        match (self.const_cond(id), edge) {
+            (Some(val), Edge::True) => val,
+            (Some(val), Edge::False) => !val,
+            _ => true,
+        }
+    }
+
+    /// The blocks which can be reached from the entry
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut stack = vec![Cfg::ENTRY];
+        while let Some(id) = stack.pop() {
+            if reachable[id] {
+                continue;
+            }
+            reachable[id] = true;
+            stack.extend(self.blocks[id].succs.iter()
+                .filter(|(_succ, edge)| self.is_taken(id, *edge))
+                .map(|(succ, _edge)| *succ));
+        }
+
+        reachable
+    }
+}
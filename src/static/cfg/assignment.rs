@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::ast::identifier::Identifier;
+use crate::r#static::cfg::{Cfg, Instr};
+
+impl<'src> Cfg<'src> {
+    /// The variables which have been assigned a value on every path from the entry to the start
+    /// of each block. Only the predecessors which are `reachable` are considered. A declaration
+    /// without a value leaves its variable unassigned
+    pub fn assigned_in(&self, reachable: &[bool]) -> Vec<HashSet<&'src Identifier>> {
+        // `None` is the set of all variables, for blocks which have not been visited yet
+        let mut assigned_out: Vec<Option<HashSet<&Identifier>>> = vec![None; self.blocks.len()];
+        let mut assigned_in = vec![HashSet::new(); self.blocks.len()];
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (id, block) in self.blocks.iter().enumerate().filter(|(id, _block)| reachable[*id]) {
+                let in_ = if id == Cfg::ENTRY {
+                    HashSet::new()
+                } else {
+                    let in_ = block.preds.iter()
+                        .filter(|pred| reachable[**pred])
+                        .filter_map(|pred| assigned_out[*pred].as_ref())
+                        .fold(None, |acc: Option<HashSet<&Identifier>>, out| match acc {
+                            None => Some(out.clone()),
+                            Some(acc) => Some(acc.intersection(out).copied().collect()),
+                        });
+                    // wait until a predecessor has been visited, the sets must only ever shrink
+                    let Some(in_) = in_ else {
+                        continue;
+                    };
+                    in_
+                };
+
+                let mut out = in_.clone();
+                for instr in block.instrs.iter() {
+                    assign(instr, &mut out);
+                }
+
+                if assigned_out[id].as_ref() != Some(&out) {
+                    assigned_out[id] = Some(out);
+                    changed = true;
+                }
+                assigned_in[id] = in_;
+            }
+        }
+
+        assigned_in
+    }
+}
+
+/// Updates the assigned variables after an instruction
+pub fn assign<'src>(instr: &Instr<'src>, assigned: &mut HashSet<&'src Identifier>) {
+    match (instr, instr.def()) {
+        (Instr::Stmt((ASTreeNode::VarDef { value: None, .. }, _)), Some((name, _))) => {
+            assigned.remove(name);
+        }
+        (_, Some((name, _))) => {
+            assigned.insert(name);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use crate::parsing::ast::astree::{ASTreeNode, Body};
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::cfg::{Block, BlockId, Cfg, Edge, Instr};
+
+struct Builder<'src> {
+    blocks: Vec<Block<'src>>,
+    current: BlockId,
+    /// the span of the innermost statement being lowered
+    stmt: Span,
+    /// where a `break` or `continue` jumps to, innermost last
+    breaks: Vec<BlockId>,
+    continues: Vec<BlockId>,
+    labels: HashMap<&'src Identifier, BlockId>,
+    /// gotos are resolved once all labels are known, as they may jump forwards
+    gotos: Vec<(BlockId, &'src Identifier)>,
+}
+
+/// The control flow graphs of all the functions and rules in a module, in the order they are
+/// defined
+pub fn module_cfgs(ast: &[Spanned<ASTreeNode>]) -> Vec<Cfg<'_>> {
+    ast.iter()
+        .filter_map(|(stmt, _span)| match stmt {
+            ASTreeNode::FnDef { name, body, .. } | ASTreeNode::RuleDef { name, body, .. } => {
+                Some(build_cfg(name, body))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Lowers a function or rule body. A `for` loop is lowered as the `while` loop it desugars to,
+/// only the first matching case of a `switch` runs and a `break` in a case leaves the `switch`
+pub fn build_cfg<'src>(name: &Spanned<Identifier>, (body, span): &'src Spanned<Body>) -> Cfg<'src> {
+    let mut builder = Builder {
+        blocks: vec![Block::default(), Block::default()],
+        current: Cfg::ENTRY,
+        stmt: *span,
+        breaks: vec![],
+        continues: vec![],
+        labels: HashMap::new(),
+        gotos: vec![],
+    };
+
+    builder.body(body);
+    builder.edge(Cfg::EXIT, Edge::Jump);
+
+    // a goto to a label which does not exist ends the path
+    for (from, label) in std::mem::take(&mut builder.gotos) {
+        if let Some(&to) = builder.labels.get(label) {
+            builder.blocks[from].succs.push((to, Edge::Jump));
+        }
+    }
+
+    let mut blocks = builder.blocks;
+    for id in 0..blocks.len() {
+        for (succ, _edge) in blocks[id].succs.clone() {
+            blocks[succ].preds.push(id);
+        }
+    }
+
+    Cfg { name: name.clone(), blocks }
+}
+
+impl<'src> Builder<'src> {
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(Block::default());
+        self.blocks.len() - 1
+    }
+
+    /// Adds an edge from the current block
+    fn edge(&mut self, to: BlockId, edge: Edge) {
+        self.blocks[self.current].succs.push((to, edge));
+    }
+
+    fn push(&mut self, instr: Instr<'src>) {
+        self.blocks[self.current].instrs.push(instr);
+        self.blocks[self.current].stmts.push(self.stmt);
+    }
+
+    /// Ends the current block with a jump. Anything after the jump goes in a new block without
+    /// predecessors
+    fn jump(&mut self, to: Option<BlockId>) {
+        if let Some(to) = to {
+            self.edge(to, Edge::Jump);
+        }
+        self.current = self.new_block();
+    }
+
+    fn body(&mut self, Body(stmts): &'src Body) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, spanned_stmt: &'src Spanned<ASTreeNode>) {
+        let (stmt, span) = spanned_stmt;
+        let outer = std::mem::replace(&mut self.stmt, *span);
+        match stmt {
+            ASTreeNode::Return(_) => {
+                self.push(Instr::Stmt(spanned_stmt));
+                self.jump(Some(Cfg::EXIT));
+            }
+            ASTreeNode::Break => {
+                self.push(Instr::Stmt(spanned_stmt));
+                self.jump(self.breaks.last().copied());
+            }
+            ASTreeNode::Continue => {
+                self.push(Instr::Stmt(spanned_stmt));
+                self.jump(self.continues.last().copied());
+            }
+            ASTreeNode::Goto((label, _)) => {
+                self.push(Instr::Stmt(spanned_stmt));
+                self.gotos.push((self.current, label));
+                self.jump(None);
+            }
+            ASTreeNode::LabelDef((label, _)) => {
+                let block = self.new_block();
+                self.edge(block, Edge::Jump);
+                self.current = block;
+                self.push(Instr::Stmt(spanned_stmt));
+                self.labels.insert(label, block);
+            }
+            ASTreeNode::IfElse { condition, consequent: (consequent, _), alternate } => {
+                self.push(Instr::Cond(condition));
+                let (branch, after) = (self.current, self.new_block());
+
+                let then = self.new_block();
+                self.blocks[branch].succs.push((then, Edge::True));
+                self.current = then;
+                self.body(consequent);
+                self.edge(after, Edge::Jump);
+
+                match alternate {
+                    Some((alternate, _)) => {
+                        let else_ = self.new_block();
+                        self.blocks[branch].succs.push((else_, Edge::False));
+                        self.current = else_;
+                        self.body(alternate);
+                        self.edge(after, Edge::Jump);
+                    }
+                    None => self.blocks[branch].succs.push((after, Edge::False)),
+                }
+                self.current = after;
+            }
+            ASTreeNode::While { condition, body: (body, _) } => {
+                let head = self.new_block();
+                self.edge(head, Edge::Jump);
+                self.current = head;
+                self.push(Instr::Cond(condition));
+                self.loop_(head, body, None);
+            }
+            // for (X = E1; op E2) { S } is while (X op E2) { S; X++; } when op is < or <=, and
+            // X-- otherwise
+            ASTreeNode::For { var, condition, body: (body, _) } => {
+                self.push(Instr::Stmt(var));
+
+                let head = self.new_block();
+                self.edge(head, Edge::Jump);
+                self.current = head;
+                self.push(Instr::Cond(condition));
+
+                let step = match condition.0 {
+                    Expr::Lt(..) | Expr::Le(..) => 1,
+                    _ => -1,
+                };
+                let step = match &var.0 {
+                    ASTreeNode::VarAssign { name, .. } => Some(Instr::Step { name, step }),
+                    _ => None,
+                };
+                // the step is part of the loop's body, so a `continue` skips it
+                self.loop_(head, body, step);
+            }
+            ASTreeNode::Switch { clause, cases } => {
+                self.push(Instr::Clause(clause));
+                let (branch, after) = (self.current, self.new_block());
+
+                self.breaks.push(after);
+                for (idx, (expr, (body, _))) in cases.iter().enumerate() {
+                    let case = self.new_block();
+                    let edge = if expr.is_some() { Edge::Case(idx) } else { Edge::Default };
+                    self.blocks[branch].succs.push((case, edge));
+                    self.current = case;
+                    self.body(body);
+                    self.edge(after, Edge::Jump);
+                }
+                self.breaks.pop();
+
+                if cases.iter().all(|(expr, _body)| expr.is_some()) {
+                    self.blocks[branch].succs.push((after, Edge::Default));
+                }
+                self.current = after;
+            }
+            _ => self.push(Instr::Stmt(spanned_stmt)),
+        }
+        self.stmt = outer;
+    }
+
+    /// Lowers the body of a loop whose condition is the last instruction of `head`. A `continue`
+    /// jumps back to the condition
+    fn loop_(&mut self, head: BlockId, body: &'src Body, step: Option<Instr<'src>>) {
+        let (entry, after) = (self.new_block(), self.new_block());
+        self.blocks[head].succs.push((entry, Edge::True));
+        self.blocks[head].succs.push((after, Edge::False));
+
+        self.breaks.push(after);
+        self.continues.push(head);
+        self.current = entry;
+        self.body(body);
+        self.breaks.pop();
+        self.continues.pop();
+
+        if let Some(step) = step {
+            self.push(step);
+        }
+        self.edge(head, Edge::Jump);
+        self.current = after;
+    }
+}
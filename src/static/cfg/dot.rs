@@ -0,0 +1,63 @@
+use std::fmt::Write;
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::r#static::cfg::{Cfg, Edge, Instr};
+
+impl Cfg<'_> {
+    /// The graph in the Graphviz DOT language. Each block is labelled with the source of its
+    /// instructions, `src` must be the source the graph was built from. Empty blocks left behind
+    /// after a jump are not drawn
+    pub fn to_dot(&self, src: &str) -> String {
+        let is_drawn = |id: usize| {
+            let block = &self.blocks[id];
+            id == Cfg::ENTRY || id == Cfg::EXIT || !block.instrs.is_empty() || !block.preds.is_empty()
+        };
+
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph \"{}\" {{", escape(&self.name.0.0));
+        let _ = writeln!(dot, "    node [shape=box, fontname=monospace];");
+
+        for (id, block) in self.blocks.iter().enumerate().filter(|(id, _block)| is_drawn(*id)) {
+            let label = match id {
+                Cfg::ENTRY if block.instrs.is_empty() => "entry".to_string(),
+                Cfg::EXIT => "exit".to_string(),
+                _ => block.instrs.iter()
+                    .map(|instr| format!("{}\\l", escape(&instr_src(instr, src))))
+                    .collect(),
+            };
+            let _ = writeln!(dot, "    b{} [label=\"{}\"];", id, label);
+        }
+
+        for (id, block) in self.blocks.iter().enumerate().filter(|(id, _block)| is_drawn(*id)) {
+            for (succ, edge) in block.succs.iter() {
+                let label = match edge {
+                    Edge::Jump => String::new(),
+                    Edge::True => " [label=\"true\"]".to_string(),
+                    Edge::False => " [label=\"false\"]".to_string(),
+                    Edge::Case(idx) => format!(" [label=\"case {}\"]", idx),
+                    Edge::Default => " [label=\"default\"]".to_string(),
+                };
+                let _ = writeln!(dot, "    b{} -> b{}{};", id, succ, label);
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// The source of an instruction on a single line
+fn instr_src(instr: &Instr, src: &str) -> String {
+    let text = match instr {
+        Instr::Step { name: (name, _), step } => {
+            return format!("{}{}", name.0, if *step > 0 { "++" } else { "--" });
+        }
+        Instr::Stmt((ASTreeNode::LabelDef((name, _)), _)) => return format!("label {}:", name.0),
+        instr => src.get(instr.span().into_range()).unwrap_or_default(),
+    };
+
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,42 @@
+use std::collections::HashSet;
+use crate::parsing::ast::identifier::Identifier;
+use crate::r#static::cfg::{Block, Cfg};
+
+impl<'src> Cfg<'src> {
+    /// The variables which may be read again after the end of each block, before they are written
+    /// to. Names are not resolved, so globals are included
+    pub fn live_out(&self) -> Vec<HashSet<&'src Identifier>> {
+        let mut live_in = vec![HashSet::new(); self.blocks.len()];
+        let mut live_out = vec![HashSet::new(); self.blocks.len()];
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for id in (0..self.blocks.len()).rev() {
+                let out = self.blocks[id].succs.iter()
+                    .flat_map(|(succ, _edge)| live_in[*succ].iter().copied())
+                    .collect::<HashSet<&Identifier>>();
+                let in_ = live_before(&self.blocks[id], out.clone());
+
+                if in_ != live_in[id] || out != live_out[id] {
+                    (live_in[id], live_out[id]) = (in_, out);
+                    changed = true;
+                }
+            }
+        }
+
+        live_out
+    }
+}
+
+/// The variables live before a block, given those live after it
+fn live_before<'src>(block: &Block<'src>, mut live: HashSet<&'src Identifier>) -> HashSet<&'src Identifier> {
+    for instr in block.instrs.iter().rev() {
+        if let Some((name, _span)) = instr.def() {
+            live.remove(name);
+        }
+        live.extend(instr.uses().into_iter().map(|(name, _span)| name));
+    }
+
+    live
+}
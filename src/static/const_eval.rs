@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use crate::dynamic::value::{arith, compare, equals, ArithOp, Value};
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::xs_error::{Code, XSError};
+
+/// The values of the `const` variables in scope
+pub type Consts = HashMap<Identifier, Value>;
+
+/// The value of `expr` if it can be computed at compile time, following the same semantics as the
+/// interpreter. Only literals and `const` variables are constant, function calls never are
+pub fn const_eval(expr: &Spanned<Expr>, consts: &Consts) -> Option<Value> {
+    fold(expr, consts, &mut vec![])
+}
+
+/// Like [const_eval], but reports `int` overflows and divisions by zero in constant
+/// subexpressions of `expr`. Every subexpression is folded, even when `expr` is not constant
+pub fn fold((expr, span): &Spanned<Expr>, consts: &Consts, errs: &mut Vec<XSError>) -> Option<Value> {
+    match expr {
+        Expr::Literal(lit) => Some(Value::from_lit(lit)),
+        Expr::Identifier(id) => consts.get(id).cloned(),
+        Expr::Paren(expr) => fold(expr, consts, errs),
+        Expr::Vec { x, y, z } => {
+            let [x, y, z] = [x, y, z].map(|expr| {
+                match fold(expr, consts, errs)?.convert(&Type::Float)? {
+                    Value::Float(val) => Some(val),
+                    _ => None,
+                }
+            });
+            Some(Value::Vec([x?, y?, z?]))
+        }
+        Expr::FnCall { args, .. } => {
+            for arg in args {
+                fold(arg, consts, errs);
+            }
+            None
+        }
+        Expr::Member { expr, member: (member, _member_span) } => match fold(expr, consts, errs)? {
+            Value::Vec([x, y, z]) => Some(Value::Float(match member.0.as_str() {
+                "x" => x,
+                "y" => y,
+                "z" => z,
+                _ => return None,
+            })),
+            _ => None,
+        },
+
+        Expr::Neg(expr) => match fold(expr, consts, errs)? {
+            Value::Int(val) => Some(Value::Int(val.wrapping_neg())),
+            Value::Float(val) => Some(Value::Float(-val)),
+            _ => None,
+        },
+        Expr::Not(expr) => match fold(expr, consts, errs)? {
+            Value::Bool(val) => Some(Value::Bool(!val)),
+            _ => None,
+        },
+
+        Expr::Star(expr1, expr2) => fold_arith(ArithOp::Mul, expr1, expr2, span, consts, errs),
+        Expr::FSlash(expr1, expr2) => fold_arith(ArithOp::Div, expr1, expr2, span, consts, errs),
+        Expr::PCent(expr1, expr2) => fold_arith(ArithOp::Rem, expr1, expr2, span, consts, errs),
+        Expr::Plus(expr1, expr2) => fold_arith(ArithOp::Add, expr1, expr2, span, consts, errs),
+        Expr::Minus(expr1, expr2) => fold_arith(ArithOp::Sub, expr1, expr2, span, consts, errs),
+
+        Expr::Lt(expr1, expr2) | Expr::Gt(expr1, expr2) | Expr::Le(expr1, expr2) | Expr::Ge(expr1, expr2) => {
+            let (val1, val2) = (fold(expr1, consts, errs), fold(expr2, consts, errs));
+            let ordering = compare(&val1?, &val2?).ok()?;
+            // comparisons with NaN are always false
+            Some(Value::Bool(ordering.is_some_and(|ordering| match expr {
+                Expr::Lt(..) => ordering.is_lt(),
+                Expr::Gt(..) => ordering.is_gt(),
+                Expr::Le(..) => ordering.is_le(),
+                _ => ordering.is_ge(),
+            })))
+        }
+        Expr::Eq(expr1, expr2) | Expr::Ne(expr1, expr2) => {
+            let (val1, val2) = (fold(expr1, consts, errs), fold(expr2, consts, errs));
+            let is_eq = equals(&val1?, &val2?).ok()?;
+            Some(Value::Bool(is_eq == matches!(expr, Expr::Eq(..))))
+        }
+
+        // XS does not short circuit, so both operands must be constant
+        Expr::And(expr1, expr2) | Expr::Or(expr1, expr2) => {
+            let (val1, val2) = (fold(expr1, consts, errs), fold(expr2, consts, errs));
+            let (Value::Bool(val1), Value::Bool(val2)) = (val1?, val2?) else {
+                return None;
+            };
+            Some(Value::Bool(if matches!(expr, Expr::And(..)) { val1 && val2 } else { val1 || val2 }))
+        }
+    }
+}
+
+/// `int` arithmetic wraps around on overflow like it does in XS, but it is reported as it is
+/// rarely intended, as is a result with more digits than an `int` literal may have. A division by
+/// zero stops the script, so it is not constant
+fn fold_arith(
+    op: ArithOp,
+    expr1: &Spanned<Expr>,
+    expr2: &Spanned<Expr>,
+    span: &Span,
+    consts: &Consts,
+    errs: &mut Vec<XSError>,
+) -> Option<Value> {
+    let (val1, val2) = (fold(expr1, consts, errs), fold(expr2, consts, errs));
+    let is_div = matches!(op, ArithOp::Div | ArithOp::Rem);
+    let is_zero = |val: &Value| match val {
+        Value::Int(val) => *val == 0,
+        Value::Float(val) => *val == 0.0,
+        _ => false,
+    };
+
+    // a divisor of zero is zero whatever the type of the dividend is
+    let Some(val1) = val1 else {
+        if is_div && val2.as_ref().is_some_and(is_zero) {
+            report_div(XSError::new(Code::DivByZero, "This divides by zero", span), errs);
+        }
+        return None;
+    };
+    let val2 = val2?;
+
+    match (&val1, &val2) {
+        (Value::Int(int1), Value::Int(_) | Value::Float(_)) => {
+            // int first arithmetic, the divisor is truncated
+            let int2 = val2.as_int()?;
+            if is_div && int2 == 0 {
+                let err = XSError::new(Code::DivByZero, "This divides by zero", span);
+                report_div(if matches!(val2, Value::Float(_)) {
+                    err.with_note("The divisor is converted to an `int` first, as the dividend is an `int`")
+                } else {
+                    err
+                }, errs);
+                return None;
+            }
+
+            let (result, is_overflow) = match op {
+                ArithOp::Mul => int1.overflowing_mul(int2),
+                ArithOp::Div => int1.overflowing_div(int2),
+                ArithOp::Rem => int1.overflowing_rem(int2),
+                ArithOp::Add => int1.overflowing_add(int2),
+                ArithOp::Sub => int1.overflowing_sub(int2),
+            };
+            if is_overflow {
+                errs.push(XSError::new(
+                    Code::IntOverflow, "The result of this operation does not fit in an `int`", span,
+                ).with_note(&format!(
+                    "`int`s are 32 bits wide and wrap around, so this evaluates to `{}`", result
+                )));
+            } else if !is_int_lit(result.into()) && is_int_lit((*int1).into()) && is_int_lit(int2.into()) {
+                errs.push(XSError::new(
+                    Code::IntOverflow, "The result of this operation is too large for an `int` literal", span,
+                ).with_note(&format!(
+                    "`int` literals cannot have more than 9 digits, so `{}` cannot be written in the script",
+                    result
+                )));
+            }
+            Some(Value::Int(result))
+        }
+        (Value::Float(_), Value::Int(_) | Value::Float(_)) if is_div && is_zero(&val2) => {
+            report_div(XSError::new(Code::DivByZero, "This divides by zero", span), errs);
+            arith(op, val1, val2).ok()
+        }
+        _ => arith(op, val1, val2).ok(),
+    }
+}
+
+/// Whether `val` can be written as an `int` literal, which has at most 9 digits
+pub fn is_int_lit(val: i64) -> bool {
+    (-999_999_999..=999_999_999).contains(&val)
+}
+
+/// The sign analysis may have reported this division already
+fn report_div(err: XSError, errs: &mut Vec<XSError>) {
+    if !errs.iter().any(|other| other.code == Code::DivByZero && other.span == err.span) {
+        errs.push(err);
+    }
+}
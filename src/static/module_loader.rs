@@ -0,0 +1,270 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use chumsky::prelude::*;
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::ast::comment::{attach_comments, attach_docs, AttachedComments, Comment};
+use crate::parsing::lexer::lexer;
+use crate::parsing::lexer::token::Token;
+use crate::parsing::parser::parser;
+use crate::parsing::span::Spanned;
+use crate::r#static::const_eval::Consts;
+use crate::r#static::type_check::{Groups, TypeEnv};
+use crate::r#static::type_check::statements::xs_tc;
+use crate::r#static::xs_error::{Code, XSError};
+
+/// Identifies a module loaded by a [ModuleLoader]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(pub usize);
+
+/// A lexed and parsed XS file
+#[derive(Debug)]
+pub struct Module {
+    pub id: FileId,
+    pub path: PathBuf,
+    pub src: String,
+    /// byte offset of the start of each line
+    line_starts: Vec<usize>,
+    pub ast: Vec<Spanned<ASTreeNode>>,
+    pub comments: Vec<Spanned<Comment>>,
+    /// the comments attached to each statement
+    pub attached: AttachedComments,
+    /// lexer and parser errors
+    pub errs: Vec<XSError>,
+}
+
+impl Module {
+    /// The 1-based line and column of a byte offset, columns are counted in chars
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.src.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let col = self.src[self.line_starts[line]..offset].chars().count();
+
+        (line + 1, col + 1)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ModuleLoader {
+    search_roots: Vec<PathBuf>,
+    modules: Vec<Rc<Module>>,
+    ids: HashMap<PathBuf, FileId>,
+    /// contents used in place of the file on disk, e.g. unsaved editor buffers
+    sources: HashMap<PathBuf, String>,
+}
+
+impl ModuleLoader {
+    pub fn new(search_roots: Vec<PathBuf>) -> Self {
+        ModuleLoader { search_roots, modules: vec![], ids: HashMap::new(), sources: HashMap::new() }
+    }
+
+    /// Makes subsequent loads of `path` use `src` instead of reading the file. The file does not
+    /// need to exist on disk
+    pub fn set_source(&mut self, path: &Path, src: String) {
+        self.sources.insert(normalise(path), src);
+    }
+
+    pub fn module(&self, id: FileId) -> &Rc<Module> {
+        &self.modules[id.0]
+    }
+
+    /// Every module loaded so far, in the order they were loaded in
+    pub fn modules(&self) -> impl Iterator<Item = &Rc<Module>> {
+        self.modules.iter()
+    }
+
+    /// Lexes and parses the file at `path`. Each file is only read once, subsequent loads
+    /// reuse the cached module
+    pub fn load(&mut self, path: &Path) -> io::Result<Rc<Module>> {
+        let path = normalise(path);
+        if let Some(id) = self.ids.get(&path) {
+            return Ok(self.module(*id).clone());
+        }
+
+        let src = match self.sources.get(&path) {
+            Some(src) => src.clone(),
+            None => fs::read_to_string(&path)?,
+        };
+        let id = FileId(self.modules.len());
+        let Parsed { ast, comments, attached, mut errs } = parse_src(&src);
+        for err in errs.iter_mut() {
+            err.file_id = id;
+        }
+        let line_starts = [0].into_iter()
+            .chain(src.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        let module = Rc::new(Module {
+            id, path: path.clone(), src, line_starts, ast, comments, attached, errs
+        });
+        self.modules.push(module.clone());
+        self.ids.insert(path, id);
+
+        Ok(module)
+    }
+
+    /// An include path is first resolved relative to the directory of the including file, and
+    /// then relative to each search root in order
+    pub fn resolve(&self, inc_path: &str, includer: &Path) -> Option<PathBuf> {
+        includer.parent()
+            .into_iter()
+            .chain(self.search_roots.iter().map(PathBuf::as_path))
+            .map(|dir| dir.join(inc_path))
+            .find(|path| path.is_file() || self.sources.contains_key(&normalise(path)))
+    }
+}
+
+/// The path modules and sources are stored under. Files which are only open in an editor cannot be
+/// canonicalised, so the directory they are in is canonicalised instead
+fn normalise(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    let path = path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect::<PathBuf>();
+    match (path.parent().map(Path::canonicalize), path.file_name()) {
+        (Some(Ok(dir)), Some(name)) => dir.join(name),
+        _ => path,
+    }
+}
+
+/// A lexed and parsed source file
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub ast: Vec<Spanned<ASTreeNode>>,
+    pub comments: Vec<Spanned<Comment>>,
+    /// the comments attached to each statement
+    pub attached: AttachedComments,
+    /// lexer and parser errors
+    pub errs: Vec<XSError>,
+}
+
+/// Lexes and parses `src`. Comments are returned separately, only doc comments are kept in the AST
+pub fn parse_src(src: &str) -> Parsed {
+    let (tokens, lex_errs) = lexer()
+        .parse(src)
+        .into_output_errors();
+
+    let mut errs = lex_errs.iter()
+        .map(|err| XSError::new(Code::InvalidToken, &err.to_string(), err.span()))
+        .collect::<Vec<XSError>>();
+
+    let Some(tokens) = tokens else {
+        return Parsed { errs, ..Parsed::default() };
+    };
+
+    // comments carry no meaning for the parser
+    let (comments, tokens): (Vec<Spanned<Token>>, Vec<Spanned<Token>>) = tokens.into_iter()
+        .partition(|(tok, _span)| matches!(tok, Token::Comment(_)));
+    let comments = comments.into_iter()
+        .filter_map(|(tok, span)| match tok {
+            Token::Comment(cmt) => Some((cmt, span)),
+            _ => None,
+        })
+        .collect::<Vec<Spanned<Comment>>>();
+
+    let (ast, parse_errs) = parser()
+        .parse(tokens.as_slice().spanned((src.len()..src.len()).into()))
+        .into_output_errors();
+
+    // the parser can report the same error more than once when it backtracks
+    let mut seen = HashSet::new();
+    errs.extend(parse_errs.iter()
+        .filter(|err| seen.insert((*err.span(), err.to_string())))
+        .map(|err| XSError::new(Code::UnexpectedToken, &err.to_string(), err.span())));
+
+    let mut ast = ast.unwrap_or_default();
+    let attached = attach_comments(&ast, &comments, src);
+    attach_docs(&mut ast, &attached);
+
+    Parsed { ast, comments, attached, errs }
+}
+
+/// Type checks the file at `path` after loading all the files it (transitively) includes and
+/// merging their top level definitions into `type_env`. Errors are reported in `errs` grouped by
+/// module, in the order in which the modules were checked. An error is only returned if the file
+/// at `path` itself cannot be read, problems with included files are reported in `errs`
+pub fn xs_tc_module(
+    path: &Path,
+    loader: &mut ModuleLoader,
+    type_env: &mut TypeEnv,
+    groups: &mut Groups,
+    errs: &mut Vec<XSError>,
+) -> io::Result<()> {
+    let module = loader.load(path)?;
+
+    let mut included = HashSet::from([module.path.clone()]);
+    let mut consts = Consts::new();
+    xs_tc_included(module, loader, type_env, groups, &mut consts, errs, &mut included, &mut vec![]);
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn xs_tc_included(
+    module: Rc<Module>,
+    loader: &mut ModuleLoader,
+    type_env: &mut TypeEnv,
+    groups: &mut Groups,
+    consts: &mut Consts,
+    errs: &mut Vec<XSError>,
+    included: &mut HashSet<PathBuf>,
+    stack: &mut Vec<PathBuf>,
+) {
+    let mut module_errs = module.errs.clone();
+    stack.push(module.path.clone());
+
+    for (stmt, _span) in module.ast.iter() {
+        let ASTreeNode::Include((inc_path, inc_span)) = stmt else {
+            continue;
+        };
+
+        let Some(resolved) = loader.resolve(inc_path, &module.path) else {
+            module_errs.push(XSError::new(
+                Code::IncludeNotFound,
+                &format!("Cannot find included file `{:}`", inc_path),
+                inc_span,
+            ));
+            continue;
+        };
+        let inc_module = match loader.load(&resolved) {
+            Ok(inc_module) => inc_module,
+            Err(err) => {
+                module_errs.push(XSError::new(
+                    Code::IncludeUnreadable,
+                    &format!("Cannot read included file `{:}`: {:}", inc_path, err),
+                    inc_span,
+                ));
+                continue;
+            }
+        };
+
+        if let Some(pos) = stack.iter().position(|path| *path == inc_module.path) {
+            let cycle = stack[pos..].iter()
+                .chain([&inc_module.path])
+                .map(|path| path.file_name().unwrap_or_default().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            module_errs.push(XSError::new(
+                Code::IncludeCycle, &format!("Include cycle detected: {:}", cycle), inc_span
+            ));
+            continue;
+        }
+
+        if !included.insert(inc_module.path.clone()) {
+            continue;
+        }
+
+        xs_tc_included(inc_module, loader, type_env, groups, consts, errs, included, stack);
+    }
+
+    stack.pop();
+
+    xs_tc(&module.ast, type_env, groups, consts, &mut module_errs);
+    for err in module_errs.iter_mut() {
+        err.file_id = module.id;
+    }
+    errs.extend(module_errs);
+}
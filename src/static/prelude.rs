@@ -0,0 +1,240 @@
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::literal::Literal;
+use crate::parsing::ast::type_::Type;
+use crate::r#static::type_check::TypeEnv;
+
+use Type::{Bool, Float, Int, Str, Vec as Vector, Void};
+
+/// Whether `name` follows the naming of the game's builtins, `xs` functions and `c` constants, e.g.
+/// `xsGetTime` or `cAttributeFood`. The prelude only models the commonly used builtins, so a name
+/// like this which is not in it is reported as an `UnknownBuiltin` warning instead of an undefined
+/// name error
+pub fn is_builtin_name(name: &str) -> bool {
+    [("xs", 2), ("c", 1)].iter().any(|(prefix, len)| {
+        name.starts_with(prefix) && name[*len..].starts_with(|c: char| c.is_ascii_uppercase())
+    })
+}
+
+/// Versions of the bundled builtin prelude. A new version is added whenever a game update changes
+/// the set of XS builtins, so that older scripts can still be checked against the builtins of the
+/// game version they were written for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PreludeVersion {
+    /// AoE2:DE, update 111772. Covers the rule, array, vector and file builtins, but only the
+    /// commonly used player, object and effect builtins and `c` constants
+    V1,
+}
+
+impl PreludeVersion {
+    pub const LATEST: PreludeVersion = PreludeVersion::V1;
+}
+
+/// (name, param types, return type)
+type FnSign = (&'static str, &'static [Type], Type);
+/// (name, value)
+type Const = (&'static str, Literal);
+/// (name, [x, y, z])
+type VecConst = (&'static str, [f64; 3]);
+
+/// Functions that take a variable number of trailing format arguments after their fixed
+/// parameters
+const VARIADIC_FNS: &[&str] = &[
+    "xsChatData",
+];
+
+const FNS_V1: &[FnSign] = &[
+    // misc
+    ("xsChatData",                  &[Str],                        Void),
+    ("xsGetContextPlayer",          &[],                           Int),
+    ("xsSetContextPlayer",          &[Int],                        Void),
+    ("xsGetTime",                   &[],                           Int),
+    ("xsGetGameTime",               &[],                           Int),
+    ("xsAddRuntimeEvent",           &[Str, Str, Int],              Bool),
+    ("xsSetTriggerVariable",        &[Int, Float],                 Void),
+    ("xsTriggerVariable",           &[Int],                        Float),
+    ("xsGetMapWidth",               &[],                           Int),
+    ("xsGetMapHeight",              &[],                           Int),
+    ("xsGetMapID",                  &[],                           Int),
+    ("xsGetMapName",                &[Bool],                       Str),
+    ("xsGetRandomNumber",           &[],                           Int),
+    ("xsGetRandomNumberLH",         &[Int, Int],                   Int),
+    ("xsGetRandomNumberMax",        &[Int],                        Int),
+    ("xsGetVictoryType",            &[],                           Int),
+    ("xsGetVictoryTime",            &[],                           Int),
+    ("xsGetVictoryTimeForSecondaryGameMode", &[], Int),
+    ("xsGetVictoryPlayer",          &[],                           Int),
+    ("xsGetVictoryPlayerForSecondaryGameMode", &[], Int),
+
+    // rules
+    ("xsEnableRule",                &[Str],                        Void),
+    ("xsDisableRule",               &[Str],                        Void),
+    ("xsEnableRuleGroup",           &[Str],                        Void),
+    ("xsDisableRuleGroup",          &[Str],                        Void),
+    ("xsDisableSelf",               &[],                           Void),
+    ("xsIsRuleEnabled",             &[Str],                        Bool),
+    ("xsIsRuleGroupEnabled",        &[Str],                        Bool),
+    ("xsSetRulePriority",           &[Str, Int],                   Void),
+    ("xsSetRulePrioritySelf",       &[Int],                        Void),
+    ("xsSetRuleMinInterval",        &[Str, Int],                   Void),
+    ("xsSetRuleMinIntervalSelf",    &[Int],                        Void),
+    ("xsSetRuleMaxInterval",        &[Str, Int],                   Void),
+    ("xsSetRuleMaxIntervalSelf",    &[Int],                        Void),
+
+    // arrays
+    ("xsArrayCreateInt",            &[Int, Int, Str],              Int),
+    ("xsArrayCreateFloat",          &[Int, Float, Str],            Int),
+    ("xsArrayCreateBool",           &[Int, Bool, Str],             Int),
+    ("xsArrayCreateString",         &[Int, Str, Str],              Int),
+    ("xsArrayCreateVector",         &[Int, Vector, Str],           Int),
+    ("xsArraySetInt",               &[Int, Int, Int],              Int),
+    ("xsArraySetFloat",             &[Int, Int, Float],            Int),
+    ("xsArraySetBool",              &[Int, Int, Bool],             Int),
+    ("xsArraySetString",            &[Int, Int, Str],              Int),
+    ("xsArraySetVector",            &[Int, Int, Vector],           Int),
+    ("xsArrayGetInt",               &[Int, Int],                   Int),
+    ("xsArrayGetFloat",             &[Int, Int],                   Float),
+    ("xsArrayGetBool",              &[Int, Int],                   Bool),
+    ("xsArrayGetString",            &[Int, Int],                   Str),
+    ("xsArrayGetVector",            &[Int, Int],                   Vector),
+    ("xsArrayResizeInt",            &[Int, Int],                   Int),
+    ("xsArrayResizeFloat",          &[Int, Int],                   Int),
+    ("xsArrayResizeBool",           &[Int, Int],                   Int),
+    ("xsArrayResizeString",         &[Int, Int],                   Int),
+    ("xsArrayResizeVector",         &[Int, Int],                   Int),
+    ("xsArrayGetSize",              &[Int],                        Int),
+
+    // vectors
+    ("xsVectorGetX",                &[Vector],                     Float),
+    ("xsVectorGetY",                &[Vector],                     Float),
+    ("xsVectorGetZ",                &[Vector],                     Float),
+    ("xsVectorSetX",                &[Vector, Float],              Vector),
+    ("xsVectorSetY",                &[Vector, Float],              Vector),
+    ("xsVectorSetZ",                &[Vector, Float],              Vector),
+    ("xsVectorSet",                 &[Float, Float, Float],        Vector),
+    ("xsVectorLength",              &[Vector],                     Float),
+    ("xsVectorNormalize",           &[Vector],                     Vector),
+
+    // files
+    ("xsCreateFile",                &[Bool],                       Bool),
+    ("xsOpenFile",                  &[Str],                        Bool),
+    ("xsCloseFile",                 &[],                           Bool),
+    ("xsGetFilePosition",           &[],                           Int),
+    ("xsSetFilePosition",           &[Int],                        Bool),
+    ("xsOffsetFilePosition",        &[Int, Bool],                  Bool),
+    ("xsGetFileSize",               &[],                           Int),
+    ("xsReadInt",                   &[],                           Int),
+    ("xsReadFloat",                 &[],                           Float),
+    ("xsReadString",                &[],                           Str),
+    ("xsReadVector",                &[],                           Vector),
+    ("xsWriteInt",                  &[Int],                        Bool),
+    ("xsWriteFloat",                &[Float],                      Bool),
+    ("xsWriteString",               &[Str],                        Bool),
+    ("xsWriteVector",               &[Vector],                     Bool),
+
+    // players and objects
+    ("xsEffectAmount",              &[Int, Int, Int, Float, Int],  Void),
+    ("xsPlayerAttribute",           &[Int, Int],                   Float),
+    ("xsSetPlayerAttribute",        &[Int, Int, Float],            Void),
+    ("xsGetObjectAttribute",        &[Int, Int, Int, Int],         Float),
+    ("xsGetPlayerCivilization",     &[Int],                        Int),
+    ("xsGetPlayerInGame",           &[Int],                        Bool),
+    ("xsGetNumPlayers",             &[],                           Int),
+    ("xsGetPlayerNumberOfTechs",    &[Int],                        Int),
+    ("xsGetTechState",              &[Int, Int],                   Int),
+    ("xsResearchTechnology",        &[Int, Bool, Bool, Int],       Bool),
+    ("xsGetObjectCount",            &[Int, Int],                   Int),
+    ("xsGetObjectCountTotal",       &[Int, Int],                   Int),
+    ("xsTask",                      &[Int, Int, Int, Int],         Void),
+    ("xsTaskAmount",                &[Int, Float],                 Void),
+    ("xsRemoveTask",                &[Int, Int, Int, Int],         Void),
+];
+
+const CONSTS_V1: &[Const] = &[
+    // resources
+    ("cAttributeFood",              Literal::Int(0)),
+    ("cAttributeWood",              Literal::Int(1)),
+    ("cAttributeStone",             Literal::Int(2)),
+    ("cAttributeGold",              Literal::Int(3)),
+    ("cAttributePopulationHeadroom", Literal::Int(4)),
+    ("cAttributeCurrentAge",        Literal::Int(6)),
+    ("cAttributeRelics",            Literal::Int(7)),
+
+    // effect types used with xsEffectAmount
+    ("cSetAttribute",               Literal::Int(0)),
+    ("cModResource",                Literal::Int(1)),
+    ("cEnableObject",               Literal::Int(2)),
+    ("cUpgradeUnit",                Literal::Int(3)),
+    ("cAddAttribute",               Literal::Int(4)),
+    ("cMulAttribute",               Literal::Int(5)),
+    ("cMulResource",                Literal::Int(6)),
+    ("cSpawnUnit",                  Literal::Int(7)),
+    ("cModifyTech",                 Literal::Int(101)),
+    ("cSetPlayerData",              Literal::Int(102)),
+
+    // effect operations used with xsEffectAmount
+    ("cAttributeSet",               Literal::Int(0)),
+    ("cAttributeAdd",               Literal::Int(1)),
+
+    // object attributes
+    ("cHitpoints",                  Literal::Int(0)),
+    ("cLineOfSight",                Literal::Int(1)),
+    ("cGarrisonCapacity",           Literal::Int(2)),
+    ("cMovementSpeed",              Literal::Int(5)),
+    ("cArmor",                      Literal::Int(8)),
+    ("cAttack",                     Literal::Int(9)),
+    ("cAttackReloadTime",           Literal::Int(10)),
+    ("cMaxRange",                   Literal::Int(12)),
+    ("cWorkRate",                   Literal::Int(13)),
+    ("cCarryCapacity",              Literal::Int(14)),
+];
+
+const VEC_CONSTS_V1: &[VecConst] = &[
+    ("cInvalidVector",              [-1.0, -1.0, -1.0]),
+    ("cOriginVector",               [0.0, 0.0, 0.0]),
+];
+
+fn fns(version: PreludeVersion) -> &'static [FnSign] {
+    match version {
+        PreludeVersion::V1 => FNS_V1,
+    }
+}
+
+/// The values of the builtin constants of the given prelude version. Vector constants are
+/// returned separately as XS has no vector literals
+pub fn consts(version: PreludeVersion) -> (&'static [Const], &'static [VecConst]) {
+    match version {
+        PreludeVersion::V1 => (CONSTS_V1, VEC_CONSTS_V1),
+    }
+}
+
+/// Builds the initial type environment for a script, containing every builtin function and
+/// constant of the given prelude version
+pub fn prelude(version: PreludeVersion) -> TypeEnv {
+    let (consts, vec_consts) = consts(version);
+    let mut type_env = TypeEnv::with_capacity(fns(version).len() + consts.len() + vec_consts.len());
+
+    for (name, params, return_type) in fns(version) {
+        let mut type_sign = params.to_vec();
+        type_sign.push(return_type.clone());
+        type_env.insert(Identifier::new(name), Type::Func {
+            is_mutable: false,
+            is_variadic: VARIADIC_FNS.contains(name),
+            type_sign,
+        });
+    }
+
+    for (name, value) in consts {
+        type_env.insert(Identifier::new(name), match value {
+            Literal::Int(_)   => Int,
+            Literal::Float(_) => Float,
+            Literal::Bool(_)  => Bool,
+            Literal::Str(_)   => Str,
+        });
+    }
+
+    for (name, _value) in vec_consts {
+        type_env.insert(Identifier::new(name), Vector);
+    }
+
+    type_env
+}
@@ -0,0 +1,466 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use crate::parsing::ast::astree::{ASTreeNode, Body};
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::literal::Literal;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::module_loader::{FileId, Module, ModuleLoader};
+use crate::r#static::type_check::statements::{fn_env, rule_env, xs_tc_body};
+use crate::r#static::type_check::{Groups, TypeEnv};
+
+/// Builtins that take the name of a rule as their first argument
+const RULE_FNS: &[&str] = &[
+    "xsEnableRule",
+    "xsDisableRule",
+    "xsIsRuleEnabled",
+    "xsSetRulePriority",
+    "xsSetRuleMinInterval",
+    "xsSetRuleMaxInterval",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Builtin,
+    Var,
+    Const,
+    Param,
+    Fn,
+    Rule,
+    Label,
+    Class,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: Identifier,
+    pub kind: SymbolKind,
+    pub type_: Type,
+    /// the declaration of the symbol as written, e.g. `int getInt(string name = "")`
+    pub detail: String,
+    /// where the name is defined, `None` for builtins
+    pub def: Option<(FileId, Span)>,
+    /// the doc comment of a top level definition
+    pub doc: Option<String>,
+}
+
+type Locals = HashMap<Identifier, usize>;
+
+/// Every name defined in a module and the modules it includes, and every place each name is used
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    pub symbols: Vec<Symbol>,
+    globals: HashMap<Identifier, usize>,
+    /// every occurrence of a name, definitions included, with the symbol it refers to
+    refs: Vec<(FileId, Span, usize)>,
+    /// the body of each function and rule with the locals defined in it
+    scopes: Vec<(FileId, Span, Vec<usize>)>,
+}
+
+impl SymbolTable {
+    /// The symbol referred to by the name at `offset`
+    pub fn symbol_at(&self, file_id: FileId, offset: usize) -> Option<&Symbol> {
+        self.refs.iter()
+            .filter(|(id, span, _sym)| *id == file_id && span.start <= offset && offset <= span.end)
+            .min_by_key(|(_id, span, _sym)| span.end - span.start)
+            .map(|(_id, _span, sym)| &self.symbols[*sym])
+    }
+
+    /// Every symbol that may be named at `offset`. Locals are only visible after their definition,
+    /// except for labels which may be jumped to from anywhere in their function or rule
+    pub fn visible_at(&self, file_id: FileId, offset: usize) -> Vec<&Symbol> {
+        let locals = self.scopes.iter()
+            .filter(|(id, span, _syms)| *id == file_id && span.start <= offset && offset <= span.end)
+            .flat_map(|(_id, _span, syms)| syms.iter())
+            .map(|sym| &self.symbols[*sym])
+            .filter(|sym| sym.kind == SymbolKind::Label || sym.def.is_some_and(|(_id, span)| span.end < offset));
+
+        self.globals.values()
+            .map(|sym| &self.symbols[*sym])
+            .chain(locals)
+            .collect()
+    }
+}
+
+struct Indexer<'env> {
+    table: SymbolTable,
+    module: Rc<Module>,
+    /// the environment the type checker built for the indexed file
+    type_env: &'env TypeEnv,
+    /// string literals naming a rule, resolved once all rules are known
+    rule_refs: Vec<(FileId, Span, Identifier)>,
+}
+
+/// Indexes the symbols of the file at `path` and every file it (transitively) includes. The types
+/// of the symbols are the ones the type checker found: globals and builtins are looked up in
+/// `type_env`, the environment [xs_tc_module](crate::r#static::module_loader::xs_tc_module) built
+/// for the file, and locals in the environment each body is checked in
+pub fn index_module(path: &Path, loader: &mut ModuleLoader, type_env: &TypeEnv) -> io::Result<SymbolTable> {
+    let module = loader.load(path)?;
+
+    let mut modules = vec![];
+    included_modules(module.clone(), loader, &mut HashSet::from([module.path.clone()]), &mut modules);
+
+    let mut indexer = Indexer { table: SymbolTable::default(), module, type_env, rule_refs: vec![] };
+    // globals may be used before they are defined, e.g. a mutable function
+    for module in modules.iter() {
+        indexer.module = module.clone();
+        for stmt in module.ast.iter() {
+            indexer.global(stmt);
+        }
+    }
+    for (name, type_) in type_env.iter().filter(|(name, _type)| name.0 != "return") {
+        if !indexer.table.globals.contains_key(name) {
+            indexer.table.globals.insert(name.clone(), indexer.table.symbols.len());
+            indexer.table.symbols.push(Symbol {
+                name: name.clone(),
+                kind: SymbolKind::Builtin,
+                type_: type_.clone(),
+                detail: builtin_detail(name, type_),
+                def: None,
+                doc: None,
+            });
+        }
+    }
+    for module in modules {
+        indexer.module = module.clone();
+        for stmt in module.ast.iter() {
+            indexer.top_level_stmt(stmt);
+        }
+    }
+
+    let Indexer { mut table, rule_refs, .. } = indexer;
+    for (file_id, span, name) in rule_refs {
+        if let Some(&sym) = table.globals.get(&name) {
+            if table.symbols[sym].kind == SymbolKind::Rule {
+                table.refs.push((file_id, span, sym));
+            }
+        }
+    }
+
+    Ok(table)
+}
+
+/// Adds the files `module` includes to `modules` before it, in the order they are type checked in
+fn included_modules(
+    module: Rc<Module>,
+    loader: &mut ModuleLoader,
+    included: &mut HashSet<PathBuf>,
+    modules: &mut Vec<Rc<Module>>,
+) {
+    for (stmt, _span) in module.ast.iter() {
+        let ASTreeNode::Include((inc_path, _inc_span)) = stmt else {
+            continue;
+        };
+        let Some(inc_module) = loader.resolve(inc_path, &module.path)
+            .and_then(|path| loader.load(&path).ok()) else {
+            continue;
+        };
+        if included.insert(inc_module.path.clone()) {
+            included_modules(inc_module, loader, included, modules);
+        }
+    }
+    modules.push(module);
+}
+
+fn builtin_detail(name: &Identifier, type_: &Type) -> String {
+    let Type::Func { is_variadic, type_sign, .. } = type_ else {
+        return format!("const {} {}", type_, name.0);
+    };
+
+    let (return_type, params) = type_sign.split_last().expect("function types include a return type");
+    let mut params = params.iter()
+        .map(Type::to_string)
+        .collect::<Vec<String>>();
+    if *is_variadic {
+        params.push("...".to_string());
+    }
+
+    format!("{} {}({})", return_type, name.0, params.join(", "))
+}
+
+impl Indexer<'_> {
+    /// The source text from `start` to `end` with all whitespace runs collapsed into one space
+    fn detail(&self, start: usize, end: usize) -> String {
+        self.module.src[start..end]
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+
+    fn resolve(&self, name: &Identifier, locals: Option<&Locals>) -> Option<usize> {
+        locals.and_then(|locals| locals.get(name))
+            .or_else(|| self.table.globals.get(name))
+            .copied()
+    }
+
+    fn reference(&mut self, (name, span): &Spanned<Identifier>, locals: Option<&Locals>) {
+        if let Some(sym) = self.resolve(name, locals) {
+            self.table.refs.push((self.module.id, *span, sym));
+        }
+    }
+
+    /// Adds a symbol with the type `env` has for it, unless the name is already defined in the same
+    /// scope, in which case the occurrence is recorded as a reference to the existing symbol. Names
+    /// the type checker did not define, e.g. because they were already in use, are not indexed
+    fn define(
+        &mut self,
+        (name, span): &Spanned<Identifier>,
+        kind: SymbolKind,
+        env: &TypeEnv,
+        detail: String,
+        locals: Option<&mut Locals>,
+    ) {
+        let scope = match locals {
+            Some(locals) => locals,
+            None => &mut self.table.globals,
+        };
+        let sym = match scope.get(name) {
+            Some(sym) => *sym,
+            None => {
+                let Some(type_) = env.get(name) else {
+                    return;
+                };
+                self.table.symbols.push(Symbol {
+                    name: name.clone(),
+                    kind,
+                    type_: type_.clone(),
+                    detail,
+                    def: Some((self.module.id, *span)),
+                    doc: None,
+                });
+                scope.insert(name.clone(), self.table.symbols.len() - 1);
+                self.table.symbols.len() - 1
+            }
+        };
+        self.table.refs.push((self.module.id, *span, sym));
+    }
+
+    /// Only the definition which introduced a global name documents it
+    fn document(&mut self, (name, span): &Spanned<Identifier>, doc: &Option<Spanned<String>>) {
+        let Some((doc, _doc_span)) = doc else {
+            return;
+        };
+        let Some(&sym) = self.table.globals.get(name) else {
+            return;
+        };
+        let symbol = &mut self.table.symbols[sym];
+        if symbol.def == Some((self.module.id, *span)) {
+            symbol.doc = Some(doc.clone());
+        }
+    }
+
+    /// Defines the global name introduced by a top level statement
+    fn global(&mut self, (stmt, span): &Spanned<ASTreeNode>) {
+        let type_env = self.type_env;
+        match stmt {
+            ASTreeNode::VarDef { is_const, name, value, doc, .. } => {
+                let (kind, end) = match value {
+                    Some((_expr, value_span)) if *is_const => (SymbolKind::Const, value_span.end),
+                    _ => (SymbolKind::Var, name.1.end),
+                };
+                let detail = self.detail(span.start, end);
+                self.define(name, kind, type_env, detail, None);
+                self.document(name, doc);
+            }
+            ASTreeNode::RuleDef { name, doc, .. } => {
+                let detail = format!("rule {}", name.0.0);
+                self.define(name, SymbolKind::Rule, type_env, detail, None);
+                self.document(name, doc);
+            }
+            ASTreeNode::FnDef { name, body: (_body, body_span), doc, .. } => {
+                let detail = self.detail(span.start, body_span.start);
+                self.define(name, SymbolKind::Fn, type_env, detail, None);
+                self.document(name, doc);
+            }
+            ASTreeNode::Class { name, .. } => {
+                let detail = format!("class {}", name.0.0);
+                self.define(name, SymbolKind::Class, type_env, detail, None);
+            }
+            _ => {}
+        }
+    }
+
+    /// Indexes the names used by a top level statement
+    fn top_level_stmt(&mut self, (stmt, _span): &Spanned<ASTreeNode>) {
+        match stmt {
+            ASTreeNode::VarDef { value: Some(value), .. } => {
+                self.expr(value, None);
+            }
+            ASTreeNode::RuleDef { body: (body, body_span), .. } => {
+                let mut env = rule_env(self.type_env);
+                xs_tc_body(body, &mut env, &mut Groups::new(), &mut vec![]);
+
+                self.body(body, body_span, &env, Locals::new());
+            }
+            ASTreeNode::FnDef { return_type, params, body: (body, body_span), .. } => {
+                let mut env = fn_env(params, return_type, self.type_env);
+                xs_tc_body(body, &mut env, &mut Groups::new(), &mut vec![]);
+
+                let mut locals = Locals::new();
+                for param in params {
+                    self.expr(&param.default, None);
+                    let detail = format!("{} {}", param.type_, param.name.0.0);
+                    self.define(&param.name, SymbolKind::Param, &env, detail, Some(&mut locals));
+                }
+                self.body(body, body_span, &env, locals);
+            }
+            _ => {}
+        }
+    }
+
+    /// Indexes the body of a function or rule, which the type checker checked in `env`
+    fn body(&mut self, body: &Body, body_span: &Span, env: &TypeEnv, mut locals: Locals) {
+        let mut gotos = vec![];
+        self.stmts(body, env, &mut locals, &mut gotos);
+
+        // labels may be defined after the gotos that jump to them
+        for goto in gotos.iter() {
+            self.reference(goto, Some(&locals));
+        }
+
+        self.table.scopes.push((self.module.id, *body_span, locals.into_values().collect()));
+    }
+
+    fn stmts(
+        &mut self,
+        body: &Body,
+        env: &TypeEnv,
+        locals: &mut Locals,
+        gotos: &mut Vec<Spanned<Identifier>>,
+    ) {
+        for stmt in body.0.iter() {
+            self.stmt(stmt, env, locals, gotos);
+        }
+    }
+
+    /// Indexes a statement of a body checked in `env`, with the locals defined before it
+    fn stmt(
+        &mut self,
+        (stmt, span): &Spanned<ASTreeNode>,
+        env: &TypeEnv,
+        locals: &mut Locals,
+        gotos: &mut Vec<Spanned<Identifier>>,
+    ) {
+        match stmt {
+            ASTreeNode::VarDef { is_const, name, value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value, Some(locals));
+                }
+                let (kind, end) = match value {
+                    Some((_expr, value_span)) if *is_const => (SymbolKind::Const, value_span.end),
+                    _ => (SymbolKind::Var, name.1.end),
+                };
+                let detail = self.detail(span.start, end);
+                self.define(name, kind, env, detail, Some(locals));
+            }
+            ASTreeNode::VarAssign { name, value } => {
+                self.reference(name, Some(locals));
+                self.expr(value, Some(locals));
+            }
+            ASTreeNode::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value, Some(locals));
+                }
+            }
+            ASTreeNode::IfElse { condition, consequent, alternate } => {
+                self.expr(condition, Some(locals));
+                self.stmts(&consequent.0, env, locals, gotos);
+                if let Some(alternate) = alternate {
+                    self.stmts(&alternate.0, env, locals, gotos);
+                }
+            }
+            ASTreeNode::While { condition, body } => {
+                self.expr(condition, Some(locals));
+                self.stmts(&body.0, env, locals, gotos);
+            }
+            ASTreeNode::For { var, condition, body } => {
+                if let (ASTreeNode::VarAssign { name, value }, _span) = var.as_ref() {
+                    self.expr(value, Some(locals));
+                    let detail = format!("int {}", name.0.0);
+                    self.define(name, SymbolKind::Var, env, detail, Some(locals));
+                }
+                self.expr(condition, Some(locals));
+                self.stmts(&body.0, env, locals, gotos);
+            }
+            ASTreeNode::Switch { clause, cases } => {
+                self.expr(clause, Some(locals));
+                for (case, (body, _body_span)) in cases {
+                    if let Some(case) = case {
+                        self.expr(case, Some(locals));
+                    }
+                    self.stmts(body, env, locals, gotos);
+                }
+            }
+            ASTreeNode::PostDPlus(name) | ASTreeNode::PostDMinus(name) | ASTreeNode::Debug(name) => {
+                self.reference(name, Some(locals));
+            }
+            ASTreeNode::LabelDef(name) => {
+                let detail = format!("label {}", name.0.0);
+                self.define(name, SymbolKind::Label, env, detail, Some(locals));
+            }
+            ASTreeNode::Goto(name) => {
+                gotos.push(name.clone());
+            }
+            ASTreeNode::Discarded(expr) => {
+                self.expr(expr, Some(locals));
+            }
+            ASTreeNode::Include(_)
+            | ASTreeNode::InfiniteLoopLimit(_)
+            | ASTreeNode::InfiniteRecursionLimit(_)
+            | ASTreeNode::RuleDef { .. }
+            | ASTreeNode::FnDef { .. }
+            | ASTreeNode::Class { .. }
+            | ASTreeNode::Break
+            | ASTreeNode::Continue
+            | ASTreeNode::Breakpoint => {}
+        }
+    }
+
+    fn expr(&mut self, (expr, span): &Spanned<Expr>, locals: Option<&Locals>) {
+        match expr {
+            Expr::Literal(_) => {}
+            Expr::Identifier(name) => {
+                self.reference(&(name.clone(), *span), locals);
+            }
+            Expr::FnCall { name, args } => {
+                self.reference(name, locals);
+                if let (true, Some((Expr::Literal(Literal::Str(rule)), rule_span))) = (
+                    RULE_FNS.contains(&name.0.0.as_str()), args.first()
+                ) {
+                    self.rule_refs.push((self.module.id, *rule_span, Identifier::new(rule)));
+                }
+                for arg in args {
+                    self.expr(arg, locals);
+                }
+            }
+            Expr::Vec { x, y, z } => {
+                self.expr(x, locals);
+                self.expr(y, locals);
+                self.expr(z, locals);
+            }
+            Expr::Paren(expr) | Expr::Neg(expr) | Expr::Not(expr) | Expr::Member { expr, .. } => {
+                self.expr(expr, locals);
+            }
+            Expr::Star(lhs, rhs)
+            | Expr::FSlash(lhs, rhs)
+            | Expr::PCent(lhs, rhs)
+            | Expr::Plus(lhs, rhs)
+            | Expr::Minus(lhs, rhs)
+            | Expr::Lt(lhs, rhs)
+            | Expr::Gt(lhs, rhs)
+            | Expr::Le(lhs, rhs)
+            | Expr::Ge(lhs, rhs)
+            | Expr::Eq(lhs, rhs)
+            | Expr::Ne(lhs, rhs)
+            | Expr::And(lhs, rhs)
+            | Expr::Or(lhs, rhs) => {
+                self.expr(lhs, locals);
+                self.expr(rhs, locals);
+            }
+        }
+    }
+}
@@ -6,6 +6,12 @@ pub mod statement;
 pub mod expression;
 pub mod util;
 pub mod statements;
+pub mod returns;
+pub mod reachability;
+pub mod unused;
+pub mod uninit;
+pub mod sign;
+pub mod consts;
 
 pub type TypeEnv = HashMap<Identifier, Type>;
 pub type Groups = HashSet<String>;
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use crate::dynamic::value::Value;
+use crate::parsing::ast::astree::{ASTreeNode, Body};
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::span::Spanned;
+use crate::r#static::const_eval::{fold, Consts};
+use crate::r#static::xs_error::{Code, XSError};
+
+/// Folds the constant subexpressions of a statement, reporting `int` overflows, divisions by
+/// zero, conditions which are always `true` or `false` and `case` values which are only found to
+/// be equal after folding. The values of `const` variables are recorded in `consts` as they are
+/// defined, those defined in a function or rule are only visible inside it
+pub fn chk_consts((stmt, _span): &Spanned<ASTreeNode>, consts: &mut Consts, errs: &mut Vec<XSError>) {
+    match stmt {
+        ASTreeNode::VarDef { is_const, type_, name: (name, _name_span), value: Some(value), .. } => {
+            let val = fold(value, consts, errs);
+            if *is_const && !consts.contains_key(name) {
+                if let Some(val) = val.and_then(|val| val.convert(type_)) {
+                    consts.insert(name.clone(), val);
+                }
+            }
+        }
+        ASTreeNode::VarAssign { value, .. }
+        | ASTreeNode::Discarded(value)
+        | ASTreeNode::Return(Some(value)) => {
+            fold(value, consts, errs);
+        }
+        ASTreeNode::RuleDef { body, .. } => {
+            chk_body(body, &mut consts.clone(), errs);
+        }
+        ASTreeNode::FnDef { params, body, .. } => {
+            let mut local_consts = consts.clone();
+            for param in params {
+                fold(&param.default, consts, errs);
+                local_consts.remove(&param.name.0);
+            }
+            chk_body(body, &mut local_consts, errs);
+        }
+        ASTreeNode::IfElse { condition, consequent, alternate } => {
+            chk_cond(condition, consts, errs);
+            chk_body(consequent, consts, errs);
+            if let Some(alternate) = alternate {
+                chk_body(alternate, consts, errs);
+            }
+        }
+        ASTreeNode::While { condition, body } => {
+            chk_cond(condition, consts, errs);
+            chk_body(body, consts, errs);
+        }
+        ASTreeNode::For { var, condition, body } => {
+            chk_consts(var, consts, errs);
+            fold(condition, consts, errs);
+            chk_body(body, consts, errs);
+        }
+        ASTreeNode::Switch { clause, cases } => {
+            fold(clause, consts, errs);
+
+            // case values are converted to `int`s like the clause
+            let mut case_vals: HashMap<i32, &Spanned<Expr>> = HashMap::with_capacity(cases.len());
+            for (case_clause, body) in cases {
+                chk_body(body, consts, errs);
+                let Some(spanned_case_expr) = case_clause else {
+                    continue;
+                };
+                let val = fold(spanned_case_expr, consts, errs);
+                let Some(val) = val.and_then(|val| val.as_int()) else {
+                    continue;
+                };
+                let Some((og_expr, og_span)) = case_vals.get(&val) else {
+                    case_vals.insert(val, spanned_case_expr);
+                    continue;
+                };
+                // syntactically equal cases are already reported by the type checker
+                if *og_expr != spanned_case_expr.0 {
+                    errs.push(XSError::new(
+                        Code::DuplicateCase,
+                        "Only the first case will run on a match",
+                        &spanned_case_expr.1,
+                    ).with_related("first case with this value", og_span)
+                        .with_note(&format!("Both cases evaluate to `{}`", val)));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn chk_body((body, _span): &Spanned<Body>, consts: &mut Consts, errs: &mut Vec<XSError>) {
+    for stmt in body.0.iter() {
+        chk_consts(stmt, consts, errs);
+    }
+}
+
+/// Literal conditions like `while (true)` are intentional, as are conditions on a single `const`
+/// which is used as a switch in the script
+fn chk_cond(condition: &Spanned<Expr>, consts: &Consts, errs: &mut Vec<XSError>) {
+    let val = fold(condition, consts, errs);
+
+    let mut expr = condition;
+    while let (Expr::Paren(inner), _) = expr {
+        expr = inner;
+    }
+    if matches!(expr.0, Expr::Literal(_) | Expr::Identifier(_)) {
+        return;
+    }
+
+    if let Some(val @ Value::Bool(_)) = val {
+        errs.push(XSError::new(
+            Code::ConstCondition, &format!("This condition is always `{}`", val), &condition.1
+        ));
+    }
+}
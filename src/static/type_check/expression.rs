@@ -1,10 +1,21 @@
 use crate::parsing::ast::expr::Expr;
 use crate::parsing::ast::literal::Literal;
 use crate::parsing::ast::type_::Type;
-use crate::parsing::span::Spanned;
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::prelude::is_builtin_name;
 use crate::r#static::type_check::TypeEnv;
-use crate::r#static::type_check::util::{arith_op, chk_int_lit, chk_num_lit, logical_op, reln_op, type_cmp};
-use crate::r#static::xs_error::{name_err, syntax_err, type_err, XSError};
+use crate::r#static::type_check::util::{arith_op, chk_fmt_args, chk_int_lit, chk_num_lit, logical_op, reln_op, type_cmp};
+use crate::r#static::xs_error::{Code, XSError};
+
+/// An [Code::UndefinedName] error, or an [Code::UnknownBuiltin] warning if `name` looks like a
+/// builtin which the prelude does not model
+fn undefined_name(name: &str, span: &Span) -> XSError {
+    if !is_builtin_name(name) {
+        return XSError::new(Code::UndefinedName, &format!("Undefined name `{:}`", name), span);
+    }
+    XSError::new(Code::UnknownBuiltin, &format!("`{:}` is not a builtin known to this checker", name), span)
+        .with_note("Its type cannot be checked. If it is not a builtin of the game, it is an undefined name")
+}
 
 pub fn xs_tc_expr<'src>(
     (expr, span): &'src Spanned<Expr>,
@@ -13,7 +24,7 @@ pub fn xs_tc_expr<'src>(
 ) -> Option<&'src Type> { match expr {
     Expr::Literal(lit) => match lit {
         Literal::Int(val) => {
-            errs.extend(chk_int_lit(&val, &span));
+            errs.extend(chk_int_lit(val, span));
             Some(&Type::Int)
         }
         Literal::Float(_) => { Some(&Type::Float) }
@@ -21,8 +32,8 @@ pub fn xs_tc_expr<'src>(
         Literal::Str(_) => { Some(&Type::Str) }
     }
     Expr::Identifier(id) => {
-        let Some(type_) = type_env.get(&id) else {
-            errs.push(name_err(&format!("Undefined name `{:}`", id.0), span));
+        let Some(type_) = type_env.get(id) else {
+            errs.push(undefined_name(&id.0, span));
             return None;
         };
         Some(type_)
@@ -35,46 +46,74 @@ pub fn xs_tc_expr<'src>(
         Some(&Type::Vec)
     }
     Expr::FnCall { name: (name, name_span), args } => {
-        let Some(type_) = type_env.get(&name) else {
-            errs.push(name_err(&format!("Undefined name `{:}`", name.0), name_span));
+        let Some(type_) = type_env.get(name) else {
+            errs.push(undefined_name(&name.0, name_span));
+            // the arguments of a builtin missing from the prelude are still checked
+            for arg in args.iter() {
+                xs_tc_expr(arg, type_env, errs);
+            }
             return None;
         };
-        let Type::Func { type_sign, .. } = type_ else {
-            errs.push(type_err(
+        let Type::Func { type_sign, is_variadic, .. } = type_ else {
+            errs.push(XSError::new(
+                Code::NotCallable,
                 &format!(
                     "Variable '{:}' is of type `{:}` and is not callable", name.0, type_
                 ), name_span
             ));
             return None;
         };
-        for (param_type, arg_expr) in type_sign[..type_sign.len()-1].iter().zip(args) {
+        let num_params = type_sign.len()-1;
+        for (param_type, arg_expr) in type_sign[..num_params].iter().zip(args) {
             let Some(arg_type) = xs_tc_expr(arg_expr, type_env, errs) else {
                 // expr will generate its own error if the type cannot be inferred
                 continue;
             };
             type_cmp(param_type, arg_type, &arg_expr.1, errs, true, false);
         }
-        
-        for (_expr, span) in args[type_sign.len()-1..].iter() {
-            errs.push(syntax_err(
-                &format!(
-                    "Function '{:}' takes {:} arguments, but {:} were given",
-                    name.0,
-                    type_sign.len()-1,
-                    args.len(),
-                ), span
-            ));
+
+        if *is_variadic {
+            chk_fmt_args(args, num_params, type_env, errs);
+        } else {
+            for (_expr, span) in args.iter().skip(num_params) {
+                errs.push(XSError::new(
+                    Code::TooManyArgs,
+                    &format!(
+                        "Function '{:}' takes {:} arguments, but {:} were given",
+                        name.0,
+                        num_params,
+                        args.len(),
+                    ), span
+                ));
+            }
         }
 
         type_sign.last()
     }
 
+    Expr::Member { expr, member: (member, member_span) } => {
+        let type_ = xs_tc_expr(expr, type_env, errs)?;
+        if *type_ != Type::Vec {
+            errs.push(XSError::new(
+                Code::InvalidMember, &format!("Type `{:}` has no members", type_), member_span
+            ));
+            return None;
+        }
+        if !matches!(member.0.as_str(), "x" | "y" | "z") {
+            errs.push(XSError::new(
+                Code::InvalidMember, "A `vector` only has the members `x`, `y` and `z`", member_span
+            ));
+            return None;
+        }
+        Some(&Type::Float)
+    }
+
     Expr::Neg(expr) => {
         errs.extend(chk_num_lit(expr, true));
         xs_tc_expr(expr, type_env, errs)
     }
     Expr::Not(_) => {
-        errs.push(type_err("Unary not is not allowed in XS", span));
+        errs.push(XSError::new(Code::UnaryNot, "Unary not is not allowed in XS", span));
         Some(&Type::Bool)
     }
     
@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::span::Span;
+use crate::r#static::cfg::{BlockId, Cfg, Instr};
+use crate::r#static::xs_error::{Code, XSError};
+
+/// Reports the first statement of each run of statements that can never run, and labels which no
+/// `goto` jumps to
+pub fn chk_reachability(cfg: &Cfg, reachable: &[bool], errs: &mut Vec<XSError>) {
+    // the step of a `for` loop is not written in the source
+    let mut instrs = cfg.blocks.iter().enumerate()
+        .flat_map(|(id, block)| {
+            block.instrs.iter().zip(&block.stmts).map(move |(instr, stmt)| (id, instr, stmt))
+        })
+        .filter(|(_id, instr, _stmt)| !matches!(instr, Instr::Step { .. }))
+        .collect::<Vec<(BlockId, &Instr, &Span)>>();
+    instrs.sort_by_key(|(_id, instr, _stmt)| instr.span().start);
+
+    // the statement an instruction belongs to is reported, so a dead `for` loop is reported as a
+    // whole rather than at its `i = 0`
+    let mut prev_reachable = true;
+    for (id, _instr, stmt) in instrs.iter() {
+        if !reachable[*id] && prev_reachable {
+            let err = match dead_cond(cfg, reachable, *id) {
+                Some((val, cond_span)) => XSError::new(
+                    Code::UnreachableCode,
+                    &format!("This code is never run as the condition is always `{}`", val),
+                    stmt,
+                ).with_related(&format!("this condition is always `{}`", val), &cond_span),
+                None => XSError::new(Code::UnreachableCode, "This code is never run", stmt),
+            };
+            errs.push(err);
+        }
+        prev_reachable = reachable[*id];
+    }
+
+    let targets = instrs.iter()
+        .filter_map(|(_id, instr, _stmt)| match instr {
+            Instr::Stmt((ASTreeNode::Goto((label, _)), _)) => Some(label),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    for (_id, instr, _stmt) in instrs.iter() {
+        if let Instr::Stmt((ASTreeNode::LabelDef((label, label_span)), _)) = instr {
+            if !targets.contains(label) {
+                errs.push(XSError::new(
+                    Code::UnusedLabel, &format!("No `goto` jumps to label `{}`", label.0), label_span
+                ));
+            }
+        }
+    }
+}
+
+/// The literal condition which stops control from reaching a block, looking back through the
+/// empty blocks before it
+fn dead_cond(cfg: &Cfg, reachable: &[bool], id: BlockId) -> Option<(bool, Span)> {
+    let mut seen = HashSet::new();
+    let mut stack = vec![id];
+    while let Some(id) = stack.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+        for &pred in cfg.blocks[id].preds.iter() {
+            if reachable[pred] {
+                if let (Some(val), Some(cond)) = (cfg.const_cond(pred), cfg.blocks[pred].instrs.last()) {
+                    return Some((val, cond.span()));
+                }
+            } else if cfg.blocks[pred].instrs.is_empty() {
+                stack.push(pred);
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,36 @@
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::Span;
+use crate::r#static::cfg::{Cfg, Instr};
+use crate::r#static::xs_error::{Code, XSError};
+
+/// Reports the end of a non void function when a path through its body can reach it without
+/// returning a value
+pub fn chk_returns(
+    return_type: &Type,
+    cfg: &Cfg,
+    reachable: &[bool],
+    body_span: &Span,
+    errs: &mut Vec<XSError>,
+) {
+    if *return_type == Type::Void {
+        return;
+    }
+
+    // every return jumps to the exit, so does the end of the body
+    let falls_off = cfg.blocks.iter().enumerate().any(|(id, block)| {
+        reachable[id]
+            && block.succs.iter().any(|(succ, edge)| *succ == Cfg::EXIT && cfg.is_taken(id, *edge))
+            && !matches!(block.instrs.last(), Some(Instr::Stmt((ASTreeNode::Return(_), _))))
+    });
+
+    if falls_off {
+        let (name, name_span) = &cfg.name;
+        let end = Span::new(body_span.end.saturating_sub(1), body_span.end);
+        errs.push(XSError::new(
+            Code::MissingReturn,
+            &format!("Not all paths of `{}` return a value", name.0),
+            &end,
+        ).with_related(&format!("`{}` returns `{}`", name.0, return_type), name_span));
+    }
+}
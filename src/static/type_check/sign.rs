@@ -0,0 +1,593 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::ast::expr::Expr;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::literal::Literal;
+use crate::parsing::ast::param::Param;
+use crate::parsing::ast::type_::Type;
+use crate::parsing::span::{Span, Spanned};
+use crate::r#static::cfg::{BlockId, Cfg, Edge, Instr};
+use crate::r#static::type_check::TypeEnv;
+use crate::r#static::xs_error::{Code, XSError};
+
+/// The number of times a block is visited before the ranges which are still changing are widened
+/// to infinity, so that loops are analysed in a bounded number of steps
+const WIDEN_AFTER: usize = 3;
+
+/// The closed interval of values a number may take
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Range {
+    lo: f64,
+    hi: f64,
+}
+
+impl Range {
+    const TOP: Range = Range { lo: f64::NEG_INFINITY, hi: f64::INFINITY };
+
+    fn point(val: f64) -> Range {
+        Range { lo: val, hi: val }
+    }
+
+    fn is_finite(&self) -> bool {
+        self.lo.is_finite() && self.hi.is_finite()
+    }
+
+    fn is_zero(&self) -> bool {
+        self.lo == 0.0 && self.hi == 0.0
+    }
+
+    fn has_zero(&self) -> bool {
+        self.lo <= 0.0 && 0.0 <= self.hi
+    }
+
+    fn join(self, other: Range) -> Range {
+        Range { lo: self.lo.min(other.lo), hi: self.hi.max(other.hi) }
+    }
+
+    fn widen(self, new: Range) -> Range {
+        Range {
+            lo: if new.lo < self.lo { f64::NEG_INFINITY } else { self.lo },
+            hi: if new.hi > self.hi { f64::INFINITY } else { self.hi },
+        }
+    }
+
+    fn intersect(self, other: Range) -> Option<Range> {
+        let range = Range { lo: self.lo.max(other.lo), hi: self.hi.min(other.hi) };
+        (range.lo <= range.hi).then_some(range)
+    }
+
+    /// The conversion of a `float` to an `int` truncates
+    fn trunc(self) -> Range {
+        Range { lo: self.lo.trunc(), hi: self.hi.trunc() }
+    }
+
+    fn neg(self) -> Range {
+        Range { lo: -self.hi, hi: -self.lo }
+    }
+
+    fn add(self, other: Range) -> Range {
+        Range { lo: self.lo + other.lo, hi: self.hi + other.hi }.or_top()
+    }
+
+    fn sub(self, other: Range) -> Range {
+        self.add(other.neg())
+    }
+
+    fn mul(self, other: Range) -> Range {
+        Range::hull(&[self.lo * other.lo, self.lo * other.hi, self.hi * other.lo, self.hi * other.hi])
+    }
+
+    fn div(self, other: Range) -> Range {
+        if other.has_zero() {
+            return Range::TOP;
+        }
+        Range::hull(&[self.lo / other.lo, self.lo / other.hi, self.hi / other.lo, self.hi / other.hi])
+    }
+
+    /// The remainder has the sign of the dividend and is smaller than the divisor
+    fn rem(self, other: Range) -> Range {
+        let max = other.lo.abs().max(other.hi.abs());
+        Range {
+            lo: if self.lo < 0.0 { -max } else { 0.0 },
+            hi: if self.hi > 0.0 { max } else { 0.0 },
+        }.or_top()
+    }
+
+    /// The smallest range containing all the values. `0 * inf` is taken to be `0`
+    fn hull(vals: &[f64]) -> Range {
+        let vals = vals.iter().map(|val| if val.is_nan() { 0.0 } else { *val });
+        Range {
+            lo: vals.clone().fold(f64::INFINITY, f64::min),
+            hi: vals.fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+
+    fn or_top(self) -> Range {
+        if self.lo.is_nan() || self.hi.is_nan() { Range::TOP } else { self }
+    }
+
+    /// `int` arithmetic wraps around, so a result outside the range of an `int` may be anything
+    fn wrap(self) -> Range {
+        if self.lo < i32::MIN as f64 || self.hi > i32::MAX as f64 { Range::TOP } else { self }
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.lo == self.hi {
+            write!(f, "`{}`", self.lo)
+        } else {
+            write!(f, "between `{}` and `{}`", self.lo, self.hi)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AbsVal {
+    range: Range,
+    is_int: bool,
+}
+
+impl AbsVal {
+    const TOP: AbsVal = AbsVal { range: Range::TOP, is_int: false };
+}
+
+/// The ranges of the local variables at a point in the body. A local which is not in `vars` may
+/// have any value. `arrays` holds the sizes of the arrays whose IDs are stored in locals
+#[derive(Debug, Clone, Default, PartialEq)]
+struct State<'src> {
+    vars: HashMap<&'src Identifier, Range>,
+    arrays: HashMap<&'src Identifier, Range>,
+}
+
+impl<'src> State<'src> {
+    fn join(&self, other: &State<'src>) -> State<'src> {
+        let join = |map1: &HashMap<&'src Identifier, Range>, map2: &HashMap<&'src Identifier, Range>| {
+            map1.iter()
+                .filter_map(|(name, range1)| map2.get(name).map(|range2| (*name, range1.join(*range2))))
+                .collect()
+        };
+        State { vars: join(&self.vars, &other.vars), arrays: join(&self.arrays, &other.arrays) }
+    }
+
+    fn widen(&self, new: &State<'src>) -> State<'src> {
+        let widen = |old: &HashMap<&'src Identifier, Range>, new: &HashMap<&'src Identifier, Range>| {
+            new.iter()
+                .filter_map(|(name, range)| old.get(name).map(|old| (*name, old.widen(*range))))
+                .collect()
+        };
+        State { vars: widen(&self.vars, &new.vars), arrays: widen(&self.arrays, &new.arrays) }
+    }
+}
+
+struct Signs<'a, 'src> {
+    type_env: &'a TypeEnv,
+    /// the `int` and `float` locals and parameters of the body
+    locals: HashSet<&'src Identifier>,
+    /// warnings are only collected once the ranges are final
+    report: bool,
+    errs: Vec<XSError>,
+}
+
+/// Reports divisions by zero, negative array sizes and out of range array indices, by tracking the
+/// range of values each `int` and `float` local may hold. Divisors and sizes which may be zero or
+/// negative are only reported when both ends of their range are known
+pub fn chk_signs(
+    cfg: &Cfg,
+    reachable: &[bool],
+    params: &[Param],
+    type_env: &TypeEnv,
+    errs: &mut Vec<XSError>,
+) {
+    let mut locals = params.iter().map(|param| &param.name.0).collect::<HashSet<&Identifier>>();
+    for instr in cfg.blocks.iter().flat_map(|block| block.instrs.iter()) {
+        match instr {
+            Instr::Stmt((ASTreeNode::VarDef { name: (name, _), .. }, _))
+            | Instr::Step { name: (name, _), .. } => {
+                locals.insert(name);
+            }
+            _ => {}
+        }
+    }
+    locals.retain(|name| matches!(type_env.get(name), Some(Type::Int | Type::Float)));
+
+    let mut signs = Signs { type_env, locals, report: false, errs: vec![] };
+
+    let mut out: Vec<Option<State>> = vec![None; cfg.blocks.len()];
+    let mut visits = vec![0; cfg.blocks.len()];
+    let mut work = vec![Cfg::ENTRY];
+    while let Some(id) = work.pop() {
+        let Some(in_) = signs.in_state(cfg, reachable, &out, id) else {
+            continue;
+        };
+        let mut new = signs.block(cfg, id, in_);
+
+        visits[id] += 1;
+        if let Some(old) = &out[id] {
+            if visits[id] > WIDEN_AFTER {
+                new = old.widen(&new);
+            }
+            if *old == new {
+                continue;
+            }
+        }
+        out[id] = Some(new);
+        work.extend(cfg.blocks[id].succs.iter()
+            .filter(|(_succ, edge)| cfg.is_taken(id, *edge))
+            .map(|(succ, _edge)| *succ));
+    }
+
+    signs.report = true;
+    for id in (0..cfg.blocks.len()).filter(|id| reachable[*id]) {
+        if let Some(in_) = signs.in_state(cfg, reachable, &out, id) {
+            signs.block(cfg, id, in_);
+        }
+    }
+    errs.extend(signs.errs);
+}
+
+impl<'src> Signs<'_, 'src> {
+    /// The join of the states flowing into a block along the edges which can be taken, `None` if
+    /// control has not reached the block yet
+    fn in_state(
+        &self,
+        cfg: &Cfg<'src>,
+        reachable: &[bool],
+        out: &[Option<State<'src>>],
+        id: BlockId,
+    ) -> Option<State<'src>> {
+        if id == Cfg::ENTRY {
+            return Some(State::default());
+        }
+
+        let mut in_: Option<State> = None;
+        for &pred in cfg.blocks[id].preds.iter().filter(|pred| reachable[**pred]) {
+            let Some(pred_out) = &out[pred] else {
+                continue;
+            };
+            for (_succ, edge) in cfg.blocks[pred].succs.iter().filter(|(succ, _edge)| *succ == id) {
+                if !cfg.is_taken(pred, *edge) {
+                    continue;
+                }
+                let mut state = pred_out.clone();
+                let cond = cfg.blocks[pred].instrs.last();
+                if let (Edge::True | Edge::False, Some(Instr::Cond(cond))) = (edge, cond) {
+                    self.refine(&mut state, cond, *edge == Edge::True);
+                }
+                in_ = Some(match in_ {
+                    None => state,
+                    Some(in_) => in_.join(&state),
+                });
+            }
+        }
+
+        in_
+    }
+
+    fn block(&mut self, cfg: &Cfg<'src>, id: BlockId, mut state: State<'src>) -> State<'src> {
+        for instr in cfg.blocks[id].instrs.iter() {
+            self.instr(instr, &mut state);
+        }
+        state
+    }
+
+    fn instr(&mut self, instr: &Instr<'src>, state: &mut State<'src>) {
+        match instr {
+            Instr::Stmt((stmt, _span)) => match stmt {
+                ASTreeNode::VarDef { is_static, name: (name, _), value, .. } => {
+                    let range = value.as_ref().map(|value| self.expr(value, state).range);
+                    // a static is only initialised the first time the body runs
+                    let range = if *is_static { None } else { Some(range.unwrap_or(Range::point(0.0))) };
+                    self.store(name, range, value.as_ref(), state);
+                }
+                ASTreeNode::VarAssign { name: (name, _), value } => {
+                    let range = self.expr(value, state).range;
+                    self.store(name, Some(range), Some(value), state);
+                }
+                ASTreeNode::PostDPlus((name, _)) => self.step(name, 1.0, state),
+                ASTreeNode::PostDMinus((name, _)) => self.step(name, -1.0, state),
+                ASTreeNode::Return(Some(expr)) => { self.expr(expr, state); }
+                ASTreeNode::Discarded(expr) => {
+                    self.expr(expr, state);
+                    let (Expr::FnCall { name: (fn_name, _), args }, _) = expr else {
+                        return;
+                    };
+                    if let ([(Expr::Identifier(array), _), size, ..], true) = (
+                        args.as_slice(), fn_name.0.starts_with("xsArrayResize")
+                    ) {
+                        let size = self.range(size, state).trunc();
+                        state.arrays.insert(array, size);
+                    }
+                }
+                _ => {}
+            },
+            Instr::Cond(expr) | Instr::Clause(expr) => { self.expr(expr, state); }
+            Instr::Step { name: (name, _), step } => self.step(name, *step as f64, state),
+        }
+    }
+
+    /// Assigns a value to a variable, converting it to the variable's type
+    fn store(
+        &mut self,
+        name: &'src Identifier,
+        range: Option<Range>,
+        value: Option<&Spanned<Expr>>,
+        state: &mut State<'src>,
+    ) {
+        if !self.locals.contains(name) {
+            return;
+        }
+        match range {
+            Some(range) if self.is_int(name) => state.vars.insert(name, range.trunc()),
+            Some(range) => state.vars.insert(name, range),
+            None => state.vars.remove(name),
+        };
+
+        match value {
+            Some((Expr::FnCall { name: (fn_name, _), args: spanned_args }, _)) => {
+                match (spanned_args.first(), fn_name.0.starts_with("xsArrayCreate")) {
+                    (Some(size), true) => state.arrays.insert(name, self.range(size, state).trunc()),
+                    _ => state.arrays.remove(name),
+                };
+            }
+            _ => { state.arrays.remove(name); }
+        }
+    }
+
+    fn step(&mut self, name: &'src Identifier, step: f64, state: &mut State<'src>) {
+        if let Some(range) = state.vars.get(name) {
+            let range = range.add(Range::point(step));
+            state.vars.insert(name, if self.is_int(name) { range.wrap() } else { range });
+        }
+    }
+
+    /// The range of an expression, without reporting anything in it
+    fn range(&self, expr: &Spanned<Expr>, state: &State<'src>) -> Range {
+        Signs { type_env: self.type_env, locals: HashSet::new(), report: false, errs: vec![] }
+            .expr(expr, state).range
+    }
+
+    fn is_int(&self, name: &Identifier) -> bool {
+        matches!(self.type_env.get(name), Some(Type::Int))
+    }
+
+    fn expr(&mut self, (expr, span): &Spanned<Expr>, state: &State<'src>) -> AbsVal {
+        match expr {
+            Expr::Literal(Literal::Int(val)) => AbsVal { range: Range::point(*val as f64), is_int: true },
+            Expr::Literal(Literal::Float(val)) => AbsVal { range: Range::point(*val), is_int: false },
+            Expr::Literal(_) => AbsVal::TOP,
+            Expr::Identifier(name) => AbsVal {
+                range: state.vars.get(name).copied().unwrap_or(Range::TOP),
+                is_int: self.is_int(name),
+            },
+            Expr::Paren(expr) => self.expr(expr, state),
+            Expr::Neg(expr) => {
+                let val = self.expr(expr, state);
+                AbsVal { range: val.range.neg(), ..val }
+            }
+            Expr::FnCall { name: (name, _), args } => {
+                for arg in args {
+                    self.expr(arg, state);
+                }
+                self.chk_array_call(name, args, state);
+                let is_int = matches!(
+                    self.type_env.get(name),
+                    Some(Type::Func { type_sign, .. }) if type_sign.last() == Some(&Type::Int)
+                );
+                AbsVal { range: Range::TOP, is_int }
+            }
+
+            Expr::Star(expr1, expr2) => self.arith(expr1, expr2, state, Range::mul),
+            Expr::Plus(expr1, expr2) => self.arith(expr1, expr2, state, Range::add),
+            Expr::Minus(expr1, expr2) => self.arith(expr1, expr2, state, Range::sub),
+            Expr::FSlash(expr1, expr2) | Expr::PCent(expr1, expr2) => {
+                let (val1, val2) = (self.expr(expr1, state), self.expr(expr2, state));
+                // the divisor is converted to the type of the dividend first
+                let divisor = if val1.is_int { val2.range.trunc() } else { val2.range };
+                self.chk_divisor(divisor, val1.is_int && !val2.is_int, expr2, span);
+
+                let range = match expr {
+                    Expr::FSlash(..) if val1.is_int => val1.range.div(divisor).trunc().wrap(),
+                    Expr::FSlash(..) => val1.range.div(divisor),
+                    _ => val1.range.rem(divisor),
+                };
+                AbsVal { range, is_int: val1.is_int }
+            }
+
+            Expr::Vec { x: expr1, y: expr2, z: expr3 } => {
+                for expr in [expr1, expr2, expr3] {
+                    self.expr(expr, state);
+                }
+                AbsVal::TOP
+            }
+            Expr::Not(expr) | Expr::Member { expr, .. } => {
+                self.expr(expr, state);
+                AbsVal::TOP
+            }
+            Expr::Lt(expr1, expr2) | Expr::Gt(expr1, expr2) | Expr::Le(expr1, expr2) | Expr::Ge(expr1, expr2)
+            | Expr::Eq(expr1, expr2) | Expr::Ne(expr1, expr2)
+            | Expr::And(expr1, expr2) | Expr::Or(expr1, expr2) => {
+                self.expr(expr1, state);
+                self.expr(expr2, state);
+                AbsVal::TOP
+            }
+        }
+    }
+
+    /// The type of the result is the type of the first operand (int first arithmetic)
+    fn arith(
+        &mut self,
+        expr1: &Spanned<Expr>,
+        expr2: &Spanned<Expr>,
+        state: &State<'src>,
+        op: fn(Range, Range) -> Range,
+    ) -> AbsVal {
+        let (val1, val2) = (self.expr(expr1, state), self.expr(expr2, state));
+        if val1.is_int {
+            AbsVal { range: op(val1.range, val2.range.trunc()).wrap(), is_int: true }
+        } else {
+            AbsVal { range: op(val1.range, val2.range), is_int: false }
+        }
+    }
+
+    fn chk_divisor(
+        &mut self,
+        divisor: Range,
+        is_truncated: bool,
+        (_, divisor_span): &Spanned<Expr>,
+        span: &Span,
+    ) {
+        if !self.report {
+            return;
+        }
+        let err = if divisor.is_zero() {
+            XSError::new(Code::DivByZero, "This divides by zero", span)
+        } else if divisor.has_zero() && divisor.is_finite() {
+            XSError::new(Code::DivByZero, "This may divide by zero", span)
+                .with_related(&format!("this is {}", divisor), divisor_span)
+        } else {
+            return;
+        };
+        self.errs.push(if is_truncated {
+            err.with_note("The divisor is converted to an `int` first, as the dividend is an `int`")
+        } else {
+            err
+        });
+    }
+
+    fn chk_array_call(&mut self, name: &Identifier, args: &[Spanned<Expr>], state: &State<'src>) {
+        if !self.report {
+            return;
+        }
+
+        if name.0.starts_with("xsArrayCreate") {
+            let Some(size_expr @ (_, size_span)) = args.first() else {
+                return;
+            };
+            let size = self.range(size_expr, state).trunc();
+            if size.hi < 0.0 {
+                self.errs.push(XSError::new(
+                    Code::NegativeArraySize,
+                    &format!("This array is created with a negative size {}", size),
+                    size_span,
+                ));
+            } else if size.lo < 0.0 && size.is_finite() {
+                self.errs.push(XSError::new(
+                    Code::NegativeArraySize,
+                    &format!("This array may be created with a negative size, its size is {}", size),
+                    size_span,
+                ));
+            }
+            return;
+        }
+
+        let is_access = name.0.starts_with("xsArraySet")
+            || (name.0.starts_with("xsArrayGet") && name.0 != "xsArrayGetSize");
+        let [array, idx_expr @ (_, idx_span), ..] = args else {
+            return;
+        };
+        if !is_access {
+            return;
+        }
+
+        let idx = self.range(idx_expr, state).trunc();
+        let size = match array {
+            (Expr::Identifier(array), _) => state.arrays.get(array).copied(),
+            _ => None,
+        };
+        if idx.hi < 0.0 {
+            self.errs.push(XSError::new(
+                Code::ArrayIndexOutOfRange, &format!("This index is negative, it is {}", idx), idx_span
+            ));
+        } else if let Some(size) = size.filter(|size| idx.lo >= size.hi) {
+            let err = XSError::new(
+                Code::ArrayIndexOutOfRange, &format!("This index is out of range, it is {}", idx), idx_span
+            );
+            self.errs.push(err.with_related(&format!("the size of this array is {}", size), &array.1));
+        }
+    }
+
+    /// Narrows the ranges of the locals compared in a condition, knowing whether it is `true`
+    fn refine(&self, state: &mut State<'src>, (expr, _span): &'src Spanned<Expr>, is_true: bool) {
+        match expr {
+            Expr::Paren(expr) => self.refine(state, expr, is_true),
+            Expr::Not(expr) => self.refine(state, expr, !is_true),
+            Expr::And(expr1, expr2) if is_true => {
+                self.refine(state, expr1, true);
+                self.refine(state, expr2, true);
+            }
+            Expr::Or(expr1, expr2) if !is_true => {
+                self.refine(state, expr1, false);
+                self.refine(state, expr2, false);
+            }
+            Expr::Lt(expr1, expr2) | Expr::Gt(expr1, expr2) | Expr::Le(expr1, expr2) | Expr::Ge(expr1, expr2)
+            | Expr::Eq(expr1, expr2) | Expr::Ne(expr1, expr2) => {
+                let op = match (expr, is_true) {
+                    (Expr::Lt(..), true) | (Expr::Ge(..), false) => Cmp::Lt,
+                    (Expr::Gt(..), true) | (Expr::Le(..), false) => Cmp::Gt,
+                    (Expr::Le(..), true) | (Expr::Gt(..), false) => Cmp::Le,
+                    (Expr::Ge(..), true) | (Expr::Lt(..), false) => Cmp::Ge,
+                    (Expr::Eq(..), true) | (Expr::Ne(..), false) => Cmp::Eq,
+                    _ => Cmp::Ne,
+                };
+                self.refine_cmp(state, expr1, op, expr2);
+                self.refine_cmp(state, expr2, op.flip(), expr1);
+            }
+            _ => {}
+        }
+    }
+
+    /// Narrows `lhs` when it is a local, knowing that `lhs op rhs`
+    fn refine_cmp(&self, state: &mut State<'src>, lhs: &'src Spanned<Expr>, op: Cmp, rhs: &Spanned<Expr>) {
+        let (Expr::Identifier(name), _) = lhs else {
+            return;
+        };
+        if !self.locals.contains(name) {
+            return;
+        }
+
+        let rhs = self.range(rhs, state);
+        let range = state.vars.get(name).copied().unwrap_or(Range::TOP);
+        // one less or one more than a bound, for strict comparisons of `int`s
+        let step = if self.is_int(name) { 1.0 } else { 0.0 };
+
+        let refined = match op {
+            Cmp::Lt => range.intersect(Range { lo: f64::NEG_INFINITY, hi: rhs.hi - step }),
+            Cmp::Le => range.intersect(Range { lo: f64::NEG_INFINITY, hi: rhs.hi }),
+            Cmp::Gt => range.intersect(Range { lo: rhs.lo + step, hi: f64::INFINITY }),
+            Cmp::Ge => range.intersect(Range { lo: rhs.lo, hi: f64::INFINITY }),
+            Cmp::Eq => range.intersect(rhs),
+            Cmp::Ne if rhs.lo == rhs.hi && range.lo == rhs.lo => Some(Range { lo: range.lo + step, ..range }),
+            Cmp::Ne if rhs.lo == rhs.hi && range.hi == rhs.hi => Some(Range { hi: range.hi - step, ..range }),
+            Cmp::Ne => Some(range),
+        };
+        // a condition which can never hold leaves the ranges as they are
+        if let Some(refined) = refined.filter(|refined| refined.lo <= refined.hi) {
+            state.vars.insert(name, refined);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Cmp {
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Cmp {
+    /// `a op b` is `b op.flip() a`
+    fn flip(self) -> Cmp {
+        match self {
+            Cmp::Lt => Cmp::Gt,
+            Cmp::Gt => Cmp::Lt,
+            Cmp::Le => Cmp::Ge,
+            Cmp::Ge => Cmp::Le,
+            op => op,
+        }
+    }
+}
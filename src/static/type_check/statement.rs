@@ -6,10 +6,17 @@ use crate::parsing::ast::identifier::Identifier;
 use crate::parsing::ast::literal::Literal;
 use crate::parsing::ast::type_::Type;
 use crate::parsing::span::{Span, Spanned};
+use crate::r#static::cfg::build::build_cfg;
 use crate::r#static::type_check::expression::xs_tc_expr;
+use crate::r#static::type_check::reachability::chk_reachability;
+use crate::r#static::type_check::returns::chk_returns;
 use crate::r#static::type_check::{Groups, TypeEnv};
-use crate::r#static::type_check::util::{chk_rule_opt, type_cmp};
-use crate::r#static::xs_error::{name_err, syntax_err, type_err, warn, XSError};
+use crate::r#static::type_check::sign::chk_signs;
+use crate::r#static::type_check::statements::{fn_env, rule_env, xs_tc_body};
+use crate::r#static::type_check::uninit::chk_uninit;
+use crate::r#static::type_check::unused::chk_unused;
+use crate::r#static::type_check::util::{chk_int_lit, chk_rule_opt, type_cmp};
+use crate::r#static::xs_error::{Code, XSError};
 
 pub fn xs_tc_stmt<'src>(
     (stmt, span): &'src Spanned<ASTreeNode>,
@@ -23,24 +30,46 @@ pub fn xs_tc_stmt<'src>(
     // an include statement is always parsed with a string literal
     ASTreeNode::Include(_) => {
         if !is_top_level {
-            errs.push(syntax_err(
+            errs.push(XSError::new(
+                Code::TopLevelOnly,
                 "An include statement may only appear at the top of the module", span
             ))
         }
     }
+    ASTreeNode::InfiniteLoopLimit((limit, limit_span))
+    | ASTreeNode::InfiniteRecursionLimit((limit, limit_span)) => {
+        let directive = match stmt {
+            ASTreeNode::InfiniteLoopLimit(_) => "infiniteLoopLimit",
+            _ => "infiniteRecursionLimit",
+        };
+        if !is_top_level {
+            errs.push(XSError::new(
+                Code::TopLevelOnly,
+                &format!("`{}` may only appear at the top level", directive), span
+            ))
+        }
+
+        errs.extend(chk_int_lit(limit, limit_span));
+        if *limit <= 0 {
+            errs.push(XSError::new(
+                Code::NonPositiveLimit, &format!("`{}` must be at least 1", directive), limit_span
+            ))
+        }
+    }
     ASTreeNode::VarDef {
         is_extern,
         is_static: _is_static,
         is_const,
         type_,
         name: spanned_name,
-        value
+        value,
+        doc: _doc,
     } => {
         let (name, name_span) = spanned_name;
         match type_env.get(name) {
             Some(_) => {
-                errs.push(name_err(
-                    "Variable name is already in use", name_span
+                errs.push(XSError::new(
+                    Code::NameInUse, "Variable name is already in use", name_span
                 ))
             }
             None => {
@@ -49,13 +78,14 @@ pub fn xs_tc_stmt<'src>(
         };
 
         if !is_top_level && *is_extern {
-            errs.push(syntax_err(
-                "Local variables cannot be declared as `extern`", name_span
+            errs.push(XSError::new(
+                Code::ExternLocal, "Local variables cannot be declared as `extern`", name_span
             ))
         }
         let Some(spanned_expr) = value else {
             if *is_const {
-                errs.push(syntax_err(
+                errs.push(XSError::new(
+                    Code::ConstWithoutValue,
                     "Variable declared as `const` must be initialised with a value", name_span
                 ))
             }
@@ -68,14 +98,16 @@ pub fn xs_tc_stmt<'src>(
         if is_top_level || *is_const {
             match expr {
                 Expr::Literal(Literal::Str(_)) if is_top_level => {
-                    errs.push(warn(
+                    errs.push(XSError::new(
+                        Code::TopLevelStrInit,
                         "Top level string initializers do not work correctly. yES",
                         expr_span
                     ));
                 }
                 Expr::Literal(_) => {}
                 _ => {
-                    errs.push(syntax_err(
+                    errs.push(XSError::new(
+                        Code::NonLiteralInit,
                         "Top level or `const` variable initializers must be literals",
                         expr_span
                     ));
@@ -95,16 +127,16 @@ pub fn xs_tc_stmt<'src>(
         value: spanned_expr
     } => {
         if is_top_level {
-            errs.push(syntax_err(
-                "Assignments are not allowed at the top level", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "Assignments are not allowed at the top level", span
             ));
         }
 
         let (name, name_span) = spanned_name;
 
         let Some(type_) = type_env.get(name) else {
-            errs.push(name_err(
-                "Undefined variable", name_span
+            errs.push(XSError::new(
+                Code::UndefinedName, "Undefined variable", name_span
             ));
             return;
         };
@@ -117,13 +149,15 @@ pub fn xs_tc_stmt<'src>(
         type_cmp(type_, init_type, &spanned_expr.1, errs, false, false);
     },
     ASTreeNode::RuleDef {
-        name: (name, name_span),
+        name: spanned_name,
         rule_opts, // todo check for dups, add grp names
-        body: (body, _body_span)
+        body: spanned_body,
+        doc: _doc,
     } => {
+        let ((name, name_span), (body, _body_span)) = (spanned_name, spanned_body);
         if !is_top_level {
-            errs.push(syntax_err(
-                "Rule definitions are only allowed at the top level", name_span
+            errs.push(XSError::new(
+                Code::TopLevelOnly, "Rule definitions are only allowed at the top level", name_span
             ))
         }
 
@@ -160,8 +194,8 @@ pub fn xs_tc_stmt<'src>(
         
         match type_env.get(name) {
             Some(_) => {
-                errs.push(name_err(
-                    "Variable name is already in use", name_span
+                errs.push(XSError::new(
+                    Code::NameInUse, "Variable name is already in use", name_span
                 ))
             }
             None => {
@@ -169,41 +203,48 @@ pub fn xs_tc_stmt<'src>(
             }
         };
 
-        let mut local_type_env = type_env.clone();
-        local_type_env.push((Identifier::new("return"), Type::Void));
-        for spanned_stmt in body.0.iter() {
-            xs_tc_stmt(spanned_stmt, &mut local_type_env, groups, errs, false, is_breakable, is_continuable);
-        }
+        let mut local_type_env = rule_env(type_env);
+        xs_tc_body(body, &mut local_type_env, groups, errs);
+
+        let cfg = build_cfg(spanned_name, spanned_body);
+        let reachable = cfg.reachable();
+        chk_reachability(&cfg, &reachable, errs);
+        chk_unused(&cfg, &reachable, &[], false, errs);
+        chk_uninit(&cfg, &reachable, errs);
+        chk_signs(&cfg, &reachable, &[], &local_type_env, errs);
     }
     ASTreeNode::FnDef {
+        is_extern,
+        is_export,
         is_mutable,
         return_type,
-        name: (name, name_span),
+        name: spanned_name,
         params,
-        body: (body, _body_span)
+        body: spanned_body,
+        doc: _doc,
     } => {
+        let ((name, name_span), (body, _body_span)) = (spanned_name, spanned_body);
         if !is_top_level {
-            errs.push(syntax_err(
+            errs.push(XSError::new(
+                Code::TopLevelOnly,
                 "Function definitions are only allowed at the top level", name_span
             ))
         }
         
-        let mut local_type_env = HashMap::with_capacity(params.len());
+        let mut param_names = HashSet::with_capacity(params.len());
         for param in params {
             let (param_name, param_name_span) = &param.name;
-            if let (Some(_), _) | (_, Some(_)) = (
-                type_env.get(param_name), local_type_env.get(param_name)
-            ) {
-                errs.push(name_err(
-                    "Variable name is already in use", param_name_span
+            let is_duplicate = !param_names.insert(param_name);
+            if type_env.get(param_name).is_some() || is_duplicate {
+                errs.push(XSError::new(
+                    Code::NameInUse, "Variable name is already in use", param_name_span
                 ))
             }
-            local_type_env.push((param_name.clone(), param.type_.clone()));
 
             let (expr, expr_span) = &param.default;
             if let Expr::Literal(_) = expr {} else {
-                errs.push(syntax_err(
-                    "Parameter defaults must be literals",
+                errs.push(XSError::new(
+                    Code::NonLiteralParamDefault, "Parameter defaults must be literals",
                     expr_span,
                 ));
             };
@@ -230,44 +271,52 @@ pub fn xs_tc_stmt<'src>(
         match type_env.get(name) {
             Some(Type::Func {
                 is_mutable: was_mutable,
-                type_sign 
+                type_sign,
+                ..
             }) => if !was_mutable {
-                errs.push(name_err(
+                errs.push(XSError::new(
+                    Code::FnRedefinition,
                     "This function is not mutable and cannot be redefined", name_span,
                 ))
             } else if new_type_sign != *type_sign {
-                errs.push(syntax_err(
+                errs.push(XSError::new(
+                    Code::MutableFnSignMismatch,
                     "Type signature of mutable functions must be consistent", name_span,
                 ))
             } else {
                 type_env.push((
                     name.clone(),
-                    Type::Func { is_mutable: is_mutable.clone(), type_sign: new_type_sign }
+                    Type::Func { is_mutable: *is_mutable, is_variadic: false, type_sign: new_type_sign }
                 ));
             },
-            Some(_) => errs.push(name_err(
-                "Variable name is already in use", name_span
+            Some(_) => errs.push(XSError::new(
+                Code::NameInUse, "Variable name is already in use", name_span
             )),
             _ => {
                 type_env.push((
                     name.clone(),
-                    Type::Func { is_mutable: is_mutable.clone(), type_sign: new_type_sign }
+                    Type::Func { is_mutable: *is_mutable, is_variadic: false, type_sign: new_type_sign }
                 ));
             }
         }
 
-        local_type_env.extend(type_env.clone());
-        local_type_env.push((Identifier::new("return"), return_type.clone()));
+        let mut local_type_env = fn_env(params, return_type, type_env);
+        xs_tc_body(body, &mut local_type_env, groups, errs);
 
-        // todo: figure out how to check returns on all fn paths
-        for spanned_stmt in body.0.iter() {
-            xs_tc_stmt(spanned_stmt, &mut local_type_env, groups, errs, false, is_breakable, is_continuable);
-        }
+        let cfg = build_cfg(spanned_name, spanned_body);
+        let reachable = cfg.reachable();
+        chk_reachability(&cfg, &reachable, errs);
+        // extern and export functions are called from outside the script, which decides what
+        // they are passed
+        chk_unused(&cfg, &reachable, params, *is_mutable || *is_extern || *is_export, errs);
+        chk_uninit(&cfg, &reachable, errs);
+        chk_signs(&cfg, &reachable, params, &local_type_env, errs);
+        chk_returns(return_type, &cfg, &reachable, &spanned_body.1, errs);
     },
     ASTreeNode::Return(spanned_expr) => {
         let Some(return_type) = type_env.get(&Identifier::new("return")) else {
-            errs.push(syntax_err(
-                "`return` statement is not allowed here",
+            errs.push(XSError::new(
+                Code::ReturnNotAllowed, "`return` statement is not allowed here",
                 span
             ));
             return;
@@ -275,7 +324,8 @@ pub fn xs_tc_stmt<'src>(
 
         let Some(spanned_expr) = spanned_expr else {
             if *return_type != Type::Void {
-                errs.push(syntax_err(
+                errs.push(XSError::new(
+                    Code::MissingReturnValue,
                     &format!("This function must return a value of type `{:}`", return_type),
                     span,
                 ));
@@ -283,13 +333,16 @@ pub fn xs_tc_stmt<'src>(
             return;
         };
         if *return_type == Type::Void {
-            errs.push(syntax_err("This function cannot return a value", span));
+            errs.push(XSError::new(
+                Code::VoidReturnValue, "This function cannot return a value", span
+            ));
             return;
         }
 
         let (expr, expr_span) = spanned_expr;
         if let Expr::Paren(_) = expr {} else {
-            errs.push(syntax_err(
+            errs.push(XSError::new(
+                Code::ReturnNotParenthesised,
                 "`return` statement expressions must be enclosed in parenthesis. yES",
                 expr_span,
             ));
@@ -308,15 +361,15 @@ pub fn xs_tc_stmt<'src>(
         alternate
     } => {
         if is_top_level {
-            errs.push(syntax_err(
-                "`if` statements are only allowed inside a local scope", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "`if` statements are only allowed inside a local scope", span
             ))
         }
         
         if let Some(type_) = xs_tc_expr(condition, type_env, errs) {
             if *type_ != Type::Bool {
-                errs.push(type_err(
-                    "`Conditional expression must be a boolean value",
+                errs.push(XSError::new(
+                    Code::NonBoolCondition, "Conditional expression must be a boolean value",
                     &condition.1,
                 ));
             }
@@ -334,15 +387,15 @@ pub fn xs_tc_stmt<'src>(
     },
     ASTreeNode::While { condition, body } => {
         if is_top_level {
-            errs.push(syntax_err(
-                "`while` statements are only allowed inside a local scope", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "`while` statements are only allowed inside a local scope", span
             ))
         }
         
         if let Some(type_) = xs_tc_expr(condition, type_env, errs) {
             if *type_ != Type::Bool {
-                errs.push(type_err(
-                    "Conditional expression must be a boolean value",
+                errs.push(XSError::new(
+                    Code::NonBoolCondition, "Conditional expression must be a boolean value",
                     &condition.1,
                 ));
             }
@@ -354,8 +407,8 @@ pub fn xs_tc_stmt<'src>(
     },
     ASTreeNode::For { var, condition, body } => {
         if is_top_level {
-            errs.push(syntax_err(
-                "`for` statements are only allowed inside a local scope", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "`for` statements are only allowed inside a local scope", span
             ))
         }
         
@@ -363,8 +416,8 @@ pub fn xs_tc_stmt<'src>(
             else { return; }; // unreachable
         
         let None = type_env.get(name) else {
-            errs.push(name_err(
-                "Variable name already in use",
+            errs.push(XSError::new(
+                Code::NameInUse, "Variable name already in use",
                 name_span,
             ));
             return;
@@ -377,8 +430,8 @@ pub fn xs_tc_stmt<'src>(
         type_env.push((name.clone(), Type::Int));
         if let Some(type_) = xs_tc_expr(condition, type_env, errs) {
             if *type_ != Type::Bool {
-                errs.push(type_err(
-                    "Conditional expression must be a boolean value",
+                errs.push(XSError::new(
+                    Code::NonBoolCondition, "Conditional expression must be a boolean value",
                     &condition.1,
                 ));
             }
@@ -390,8 +443,8 @@ pub fn xs_tc_stmt<'src>(
     },
     ASTreeNode::Switch { clause, cases } => {
         if is_top_level {
-            errs.push(syntax_err(
-                "`switch` statements are only allowed inside a local scope", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "`switch` statements are only allowed inside a local scope", span
             ))
         }
         
@@ -413,14 +466,11 @@ pub fn xs_tc_stmt<'src>(
                     default_span = Some(body_span);
                     continue;
                 };
-                errs.push(warn(
-                    "Only the first default block will run when case matching fails",
-                    og_span,
-                ));
-                errs.push(warn(
+                errs.push(XSError::new(
+                    Code::DuplicateDefault,
                     "Only the first default block will run when case matching fails",
                     body_span,
-                ));
+                ).with_related("first default block", og_span));
                 continue;
             };
             let (case_expr, case_expr_span) = spanned_case_expr;
@@ -428,14 +478,11 @@ pub fn xs_tc_stmt<'src>(
                 type_cmp(&Type::Int, clause_type, case_expr_span, errs, false, true);
             }
             if let Some(&og_span) = case_spans.get(case_expr) {
-                errs.push(warn(
-                    "Only the first case will run on a match",
-                    og_span,
-                ));
-                errs.push(warn(
+                errs.push(XSError::new(
+                    Code::DuplicateCase,
                     "Only the first case will run on a match",
                     &spanned_case_expr.1,
-                ));
+                ).with_related("first case with this value", og_span));
             } else {
                 case_spans.push((case_expr, case_expr_span));
             }
@@ -443,78 +490,88 @@ pub fn xs_tc_stmt<'src>(
     },
     ASTreeNode::PostDPlus((id, id_span)) => {
         if is_top_level {
-            errs.push(syntax_err(
-                "`postfix` statements are only allowed inside a local scope", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "`postfix` statements are only allowed inside a local scope", span
             ))
         }
         
         let Some(id_type) = type_env.get(id) else {
-            errs.push(name_err(&format!("Undefined name `{:}`", id.0), id_span));
+            errs.push(XSError::new(
+                Code::UndefinedName, &format!("Undefined name `{:}`", id.0), id_span
+            ));
             return;
         };
 
         if let Type::Int | Type::Float = id_type {
             return;
         }
-        errs.push(syntax_err(
+        errs.push(XSError::new(
+            Code::InvalidPostfix,
             &format!("Postfix increment is only allowed on `int | float` values {:}", id.0),
             span
         ));
     },
     ASTreeNode::PostDMinus((id, id_span)) => {
         if is_top_level {
-            errs.push(syntax_err(
-                "`postfix` statements are only allowed inside a local scope", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "`postfix` statements are only allowed inside a local scope", span
             ))
         }
         
         let Some(id_type) = type_env.get(id) else {
-            errs.push(name_err(&format!("Undefined name `{:}`", id.0), id_span));
+            errs.push(XSError::new(
+                Code::UndefinedName, &format!("Undefined name `{:}`", id.0), id_span
+            ));
             return;
         };
 
         if let Type::Int | Type::Float = id_type {
             return;
         }
-        errs.push(syntax_err(
+        errs.push(XSError::new(
+            Code::InvalidPostfix,
             &format!("Postfix decrement is only allowed on `int | float` values {:}", id.0),
             span
         ));
     },
     ASTreeNode::Break => {
         if !is_breakable {
-            errs.push(syntax_err(
+            errs.push(XSError::new(
+                Code::BreakNotAllowed,
                 "`break` statements are only allowed inside a loop or a case block", span
             ))
         }
     },
     ASTreeNode::Continue => {
         if !is_continuable {
-            errs.push(syntax_err(
+            errs.push(XSError::new(
+                Code::ContinueNotAllowed,
                 "`continue` statements are only allowed inside a loop", span
             ))
         }
     },
     ASTreeNode::LabelDef((id, id_span)) => {
         if is_top_level {
-            errs.push(syntax_err(
-                "`label` definitions are only allowed inside a local scope", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "`label` definitions are only allowed inside a local scope", span
             ))
         }
         let None = type_env.get(id) else {
-            errs.push(name_err("Variable name already in use", id_span));
+            errs.push(XSError::new(Code::NameInUse, "Variable name already in use", id_span));
             return;
         };
         type_env.push((id.clone(), Type::Label));
     },
     ASTreeNode::Goto((id, id_span)) => {
         if is_top_level {
-            errs.push(syntax_err(
-                "`goto` statements are only allowed inside a local scope", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "`goto` statements are only allowed inside a local scope", span
             ))
         }
         let Some(id_type) = type_env.get(id) else {
-            errs.push(name_err(&format!("Undefined name `{:}`", id.0), id_span));
+            errs.push(XSError::new(
+                Code::UndefinedName, &format!("Undefined name `{:}`", id.0), id_span
+            ));
             return;
         };
 
@@ -522,14 +579,16 @@ pub fn xs_tc_stmt<'src>(
     },
     ASTreeNode::Discarded(spanned_expr) => {
         if is_top_level {
-            errs.push(syntax_err(
-                "Discarded expressions are only allowed inside a local scope", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "Discarded expressions are only allowed inside a local scope", span
             ))
         }
         
         let (expr, expr_span) = spanned_expr;
         let Expr::FnCall { .. } = expr else {
-            errs.push(syntax_err("Only function calls may be discarded", expr_span));
+            errs.push(XSError::new(
+                Code::DiscardedNonCall, "Only function calls may be discarded", expr_span
+            ));
             return;
         };
 
@@ -540,16 +599,21 @@ pub fn xs_tc_stmt<'src>(
             return;
         }
 
-        errs.push(warn("The return value of this function call is being ignored", expr_span));
+        errs.push(XSError::new(
+            Code::IgnoredReturnValue,
+            "The return value of this function call is being ignored", expr_span
+        ));
     },
     ASTreeNode::Debug((id, id_span)) => {
         if is_top_level {
-            errs.push(syntax_err(
-                "`dbg` statements are only allowed inside a local scope", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "`dbg` statements are only allowed inside a local scope", span
             ))
         }
         let Some(id_type) = type_env.get(id) else {
-            errs.push(name_err(&format!("Undefined name `{:}`", id.0), id_span));
+            errs.push(XSError::new(
+                Code::UndefinedName, &format!("Undefined name `{:}`", id.0), id_span
+            ));
             return;
         };
         
@@ -557,29 +621,29 @@ pub fn xs_tc_stmt<'src>(
             return;
         };
 
-        errs.push(syntax_err(
-            "Invalid variable name for `dbg`", id_span
+        errs.push(XSError::new(
+            Code::InvalidDbg, "Invalid variable name for `dbg`", id_span
         ))
     },
     ASTreeNode::Breakpoint => {
         if is_top_level {
-            errs.push(syntax_err(
-                "Breakpoints are only allowed inside a local scope", span
+            errs.push(XSError::new(
+                Code::LocalOnly, "Breakpoints are only allowed inside a local scope", span
             ))
         }
         
-        errs.push(warn(
-            "Breakpoints cause XS execution to pause irrecoverably", span
+        errs.push(XSError::new(
+            Code::Breakpoint, "Breakpoints cause XS execution to pause irrecoverably", span
         ));
     },
     ASTreeNode::Class { name: (id, id_span), member_vars } => {
         if !is_top_level {
-            errs.push(syntax_err(
-                "`class` definitions are only allowed at the top level", span
+            errs.push(XSError::new(
+                Code::TopLevelOnly, "`class` definitions are only allowed at the top level", span
             ))
         }
-        if let Some(_) = type_env.get(id) {
-            errs.push(name_err("Variable name already in use", id_span));
+        if type_env.get(id).is_some() {
+            errs.push(XSError::new(Code::NameInUse, "Variable name already in use", id_span));
         } else {
             type_env.push((id.clone(), Type::Class));
         }
@@ -592,22 +656,32 @@ pub fn xs_tc_stmt<'src>(
                 value,
                 is_extern,
                 is_const,
-                is_static
+                is_static,
+                doc: _doc,
             } = member_var
                 else { continue; }; // unreachable
             
             if *is_extern {
-                errs.push(syntax_err("Member variables cannot be declared as `extern`", id_span));
+                errs.push(XSError::new(
+                    Code::InvalidMemberModifier,
+                    "Member variables cannot be declared as `extern`", id_span
+                ));
             }
             if *is_const {
-                errs.push(syntax_err("Member variables cannot be declared as `const`", id_span));
+                errs.push(XSError::new(
+                    Code::InvalidMemberModifier,
+                    "Member variables cannot be declared as `const`", id_span
+                ));
             }
             if *is_static {
-                errs.push(syntax_err("Member variables cannot be declared as `static`", id_span));
+                errs.push(XSError::new(
+                    Code::InvalidMemberModifier,
+                    "Member variables cannot be declared as `static`", id_span
+                ));
             }
             
             if mem_name.contains(id) {
-                errs.push(name_err("Variable name already in use", id_span));
+                errs.push(XSError::new(Code::NameInUse, "Variable name already in use", id_span));
             } else {
                 mem_name.push(id);
             }
@@ -619,8 +693,8 @@ pub fn xs_tc_stmt<'src>(
             type_cmp(type_, init_value_type, init_value_span, errs, false, false);
         }
         
-        errs.push(warn(
-            "Classes are unusable in XS", span
+        errs.push(XSError::new(
+            Code::UnusableClass, "Classes are unusable in XS", span
         ))
     },
 }}
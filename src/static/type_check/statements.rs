@@ -1,5 +1,10 @@
-use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::ast::astree::{ASTreeNode, Body};
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::param::Param;
+use crate::parsing::ast::type_::Type;
 use crate::parsing::span::Spanned;
+use crate::r#static::const_eval::Consts;
+use crate::r#static::type_check::consts::chk_consts;
 use crate::r#static::type_check::{Groups, TypeEnv};
 use crate::r#static::type_check::statement::xs_tc_stmt;
 use crate::r#static::xs_error::XSError;
@@ -8,9 +13,37 @@ pub fn xs_tc<'src>(
     stmts: &'src Vec<Spanned<ASTreeNode>>,
     type_env: &'src mut TypeEnv,
     groups: &'src mut Groups,
+    consts: &mut Consts,
     errs: &mut Vec<XSError>,
 ) {
     for stmt in stmts {
         xs_tc_stmt(stmt, type_env, groups, errs, true, false, false);
+        chk_consts(stmt, consts, errs);
+    }
+}
+
+/// The environment the body of a function is checked in, the globals defined before it and its
+/// parameters. `return` holds the type the function returns
+pub fn fn_env(params: &[Param], return_type: &Type, type_env: &TypeEnv) -> TypeEnv {
+    let mut local_type_env = params.iter()
+        .map(|param| (param.name.0.clone(), param.type_.clone()))
+        .collect::<TypeEnv>();
+    local_type_env.extend(type_env.clone());
+    local_type_env.insert(Identifier::new("return"), return_type.clone());
+    local_type_env
+}
+
+/// The environment the body of a rule is checked in
+pub fn rule_env(type_env: &TypeEnv) -> TypeEnv {
+    let mut local_type_env = type_env.clone();
+    local_type_env.insert(Identifier::new("return"), Type::Void);
+    local_type_env
+}
+
+/// Type checks the body of a function or rule in `local_type_env`. Locals are added to it as they
+/// are defined, so afterwards it holds every name defined in the body
+pub fn xs_tc_body(body: &Body, local_type_env: &mut TypeEnv, groups: &mut Groups, errs: &mut Vec<XSError>) {
+    for spanned_stmt in body.0.iter() {
+        xs_tc_stmt(spanned_stmt, local_type_env, groups, errs, false, false, false);
     }
 }
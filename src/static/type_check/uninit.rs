@@ -0,0 +1,48 @@
+use std::collections::{HashMap, HashSet};
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::span::Span;
+use crate::r#static::cfg::assignment::assign;
+use crate::r#static::cfg::{Cfg, Instr};
+use crate::r#static::xs_error::{Code, XSError};
+
+/// Reports reads of local variables declared without a value which may not have been assigned
+/// one on every path to the read. `static` variables keep their value between calls and are not
+/// reported
+pub fn chk_uninit(cfg: &Cfg, reachable: &[bool], errs: &mut Vec<XSError>) {
+    let declared = cfg.blocks.iter()
+        .flat_map(|block| block.instrs.iter())
+        .filter_map(|instr| match instr {
+            Instr::Stmt((
+                ASTreeNode::VarDef { is_static: false, name: (name, name_span), value: None, .. }, _
+            )) => Some((name, *name_span)),
+            _ => None,
+        })
+        .collect::<HashMap<&Identifier, Span>>();
+    if declared.is_empty() {
+        return;
+    }
+
+    let assigned_in = cfg.assigned_in(reachable);
+    let mut reported = HashSet::new();
+    for (id, block) in cfg.blocks.iter().enumerate().filter(|(id, _block)| reachable[*id]) {
+        let mut assigned = assigned_in[id].clone();
+        for instr in block.instrs.iter() {
+            for (name, span) in instr.uses() {
+                let Some(decl_span) = declared.get(name) else {
+                    continue;
+                };
+                if assigned.contains(name) || !reported.insert(span) {
+                    continue;
+                }
+                errs.push(XSError::new(
+                    Code::UninitRead,
+                    &format!("`{}` may be read before it is assigned a value", name.0),
+                    &span,
+                ).with_related(&format!("`{}` is declared here without a value", name.0), decl_span)
+                    .with_note("Until it is assigned, a variable holds the default value of its type"));
+            }
+            assign(instr, &mut assigned);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::{HashMap, HashSet};
+use crate::parsing::ast::astree::ASTreeNode;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::param::Param;
+use crate::parsing::span::Span;
+use crate::r#static::cfg::{Cfg, Instr};
+use crate::r#static::xs_error::{Code, XSError};
+
+/// Names starting with `_` are placeholders, they are never reported
+fn is_placeholder(name: &Identifier) -> bool {
+    name.0.starts_with('_')
+}
+
+/// Reports local variables and parameters which are never read, and values assigned to local
+/// variables which are overwritten or go out of scope before they are read. Parameters are not
+/// reported when `keep_params` is set, like for a mutable function which may be redefined to use
+/// them
+pub fn chk_unused(
+    cfg: &Cfg,
+    reachable: &[bool],
+    params: &[Param],
+    keep_params: bool,
+    errs: &mut Vec<XSError>,
+) {
+    let instrs = || cfg.blocks.iter().flat_map(|block| block.instrs.iter());
+
+    let read = instrs()
+        .flat_map(|instr| instr.uses())
+        .map(|(name, _span)| name)
+        .collect::<HashSet<&Identifier>>();
+
+    // statics keep their value between calls, so none of their stores are dead
+    let mut locals: HashMap<&Identifier, bool> = HashMap::new();
+    for instr in instrs() {
+        let Instr::Stmt((ASTreeNode::VarDef { is_static, name: (name, name_span), .. }, _)) = instr else {
+            continue;
+        };
+        locals.insert(name, !is_static);
+        if !read.contains(name) && !is_placeholder(name) {
+            errs.push(XSError::new(
+                Code::UnusedVariable, &format!("Variable `{}` is never read", name.0), name_span
+            ));
+        }
+    }
+
+    if !keep_params {
+        for Param { name: (name, name_span), .. } in params {
+            if !read.contains(name) && !is_placeholder(name) {
+                errs.push(XSError::new(
+                    Code::UnusedParam, &format!("Parameter `{}` is never read", name.0), name_span
+                ));
+            }
+        }
+    }
+
+    let live_out = cfg.live_out();
+    for (id, block) in cfg.blocks.iter().enumerate().filter(|(id, _block)| reachable[*id]) {
+        let mut live = live_out[id].clone();
+        for instr in block.instrs.iter().rev() {
+            if let Some(span) = dead_store(instr, &live, &read, &locals) {
+                let (name, _) = instr.def().expect("a store defines a variable");
+                errs.push(XSError::new(
+                    Code::DeadStore, &format!("The value assigned to `{}` is never read", name.0), &span
+                ));
+            }
+            if let Some((name, _span)) = instr.def() {
+                live.remove(name);
+            }
+            live.extend(instr.uses().into_iter().map(|(name, _span)| name));
+        }
+    }
+}
+
+/// The span of an assignment whose value is never read. Variables which are never read at all are
+/// already reported as unused
+fn dead_store(
+    instr: &Instr,
+    live: &HashSet<&Identifier>,
+    read: &HashSet<&Identifier>,
+    locals: &HashMap<&Identifier, bool>,
+) -> Option<Span> {
+    let Instr::Stmt((
+        ASTreeNode::VarDef { name: (name, _), value: Some(_), .. }
+        | ASTreeNode::VarAssign { name: (name, _), .. },
+        span,
+    )) = instr else {
+        return None;
+    };
+
+    let is_dead = locals.get(name) == Some(&true)
+        && read.contains(name)
+        && !live.contains(name)
+        && !is_placeholder(name);
+
+    is_dead.then_some(*span)
+}
@@ -4,13 +4,14 @@ use crate::parsing::ast::expr::Expr;
 use crate::parsing::ast::literal::Literal;
 use crate::parsing::ast::type_::Type;
 use crate::parsing::span::{Span, Spanned};
+use crate::r#static::const_eval::is_int_lit;
 use crate::r#static::type_check::expression::xs_tc_expr;
 use crate::r#static::type_check::TypeEnv;
-use crate::r#static::xs_error::{syntax_err, type_err, warn, XSError};
+use crate::r#static::xs_error::{Code, XSError};
 
 pub fn chk_int_lit(val: &i64, span: &Span) -> Vec<XSError> {
-    if *val < -999_999_999 || 999_999_999 < *val {
-        vec!(type_err("`int` literals cannot have more than 9 digits", span))
+    if !is_int_lit(*val) {
+        vec!(XSError::new(Code::IntLitTooLarge, "`int` literals cannot have more than 9 digits", span))
     } else {
         vec![]
     }
@@ -19,21 +20,81 @@ pub fn chk_int_lit(val: &i64, span: &Span) -> Vec<XSError> {
 pub fn chk_num_lit((expr, span): &Spanned<Expr>, is_neg: bool) -> Vec<XSError> {
     match expr {
         Expr::Neg(expr) => if is_neg {
-            vec![type_err("Unary negative may only be used with `int | float` literals", span)]
+            vec![XSError::new(
+                Code::NonLiteralNeg, "Unary negative may only be used with `int | float` literals", span
+            )]
         } else {
             chk_num_lit(expr, true)
         }
         Expr::Literal(lit) => match lit {
             Literal::Int(val) => { chk_int_lit(val, span) }
             Literal::Float(_) => { vec![] }
-            _ => { vec![type_err("Expected a value of type `int | float`", span)] }
+            _ => { vec![XSError::new(Code::NonNumVecInit, "Expected a value of type `int | float`", span)] }
         }
         _ => {
-            vec![type_err("Only `int | float` literals are allowed in vector initialisations", span)]
+            vec![XSError::new(
+                Code::NonNumVecInit, "Only `int | float` literals are allowed in vector initialisations", span
+            )]
         }
     }
 }
 
+/// Checks the trailing format arguments of a call to a variadic function. The last fixed parameter
+/// of a variadic function is its format string, if it is a literal then the number of format
+/// arguments must match the number of format specifiers in it
+pub fn chk_fmt_args<'src>(
+    args: &'src [Spanned<Expr>],
+    num_params: usize,
+    type_env: &'src TypeEnv,
+    errs: &mut Vec<XSError>,
+) {
+    let fmt_args = args.get(num_params..).unwrap_or_default();
+    for arg_expr in fmt_args {
+        let Some(arg_type) = xs_tc_expr(arg_expr, type_env, errs) else {
+            // expr will generate its own error if the type cannot be inferred
+            continue;
+        };
+        if let Type::Int | Type::Float | Type::Bool | Type::Str = arg_type {
+            continue;
+        }
+        errs.push(XSError::new(
+            Code::InvalidFmtArg,
+            &format!("Cannot use a value of type `{:}` as a format argument", arg_type),
+            &arg_expr.1,
+        ));
+    }
+
+    let Some((Expr::Literal(Literal::Str(fmt)), fmt_span)) = num_params.checked_sub(1)
+        .and_then(|idx| args.get(idx)) else {
+        return;
+    };
+
+    let mut chars = fmt.chars().peekable();
+    let mut num_specs = 0;
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        match chars.peek() {
+            Some('%') => { chars.next(); }
+            Some(c) if c.is_ascii_alphabetic() => { num_specs += 1; }
+            _ => {}
+        }
+    }
+
+    if num_specs != fmt_args.len() {
+        errs.push(XSError::new(
+            Code::FmtArgCount,
+            &format!(
+                "This format string has {:} format specifiers, but {:} format arguments were given",
+                num_specs,
+                fmt_args.len(),
+            ),
+            fmt_span,
+        ));
+    }
+}
+
 pub fn arith_op<'src>(
     span: &'src Span,
     expr1: &'src Spanned<Expr>,
@@ -53,10 +114,9 @@ pub fn arith_op<'src>(
     match (type1, type2) {
         (Type::Int, Type::Int) => { Some(&Type::Int) }
         (Type::Int, Type::Float) => {
-            errs.push(warn(
-                "This expression yields an `int`, not a `float`.\n\nThe resulting type of an arithmetic operation depends on its first operand. yES.",
-                span
-            ));
+            errs.push(XSError::new(
+                Code::IntFirstArith, "This expression yields an `int`, not a `float`", span
+            ).with_note("The resulting type of an arithmetic operation depends on its first operand. yES."));
             Some(&Type::Int)
         }
 
@@ -64,9 +124,28 @@ pub fn arith_op<'src>(
 
         (Type::Str, _) | (_, Type::Str) if op_name == "add" => { Some(&Type::Str) }
 
+        (Type::Vec, Type::Vec) if op_name == "add" || op_name == "subtract" => { Some(&Type::Vec) }
+        (Type::Vec, Type::Int | Type::Float) if op_name == "multiply" || op_name == "divide" => {
+            Some(&Type::Vec)
+        }
+
+        (Type::Int | Type::Float, Type::Vec) if op_name == "multiply" => {
+            errs.push(XSError::new(
+                Code::InvalidArithOperands,
+                &format!("Cannot {:} types `{:}` and `{:}`", op_name, type1, type2),
+                span,
+            ).with_note(
+                "The type of an arithmetic operation is the type of its first operand, write the \
+                `vector` first to scale it"
+            ));
+            None
+        }
+
         _ => {
-            errs.push(type_err(
-                &format!("Cannot {:} types `{:}` and `{:}`", op_name, type1, type2), span
+            errs.push(XSError::new(
+                Code::InvalidArithOperands,
+                &format!("Cannot {:} types `{:}` and `{:}`", op_name, type1, type2),
+                span,
             ));
             None
         }
@@ -93,8 +172,9 @@ pub fn reln_op<'src>(
         (Type::Int | Type::Float, Type::Int | Type::Float) => { Some(&Type::Bool) }
         (Type::Str, Type::Str) => { Some(&Type::Bool) }
         (Type::Vec, Type::Vec) | (Type::Bool, Type::Bool) => {
-            if op_name != "eq" || op_name != "ne" {
-                errs.push(warn(
+            if op_name != "eq" && op_name != "ne" {
+                errs.push(XSError::new(
+                    Code::CrashingComparison,
                     "This comparison will cause a silent XS crash!",
                     span,
                 ));
@@ -103,8 +183,10 @@ pub fn reln_op<'src>(
         }
 
         _ => {
-            errs.push(type_err(
-                &format!("Cannot compare types `{:}` and `{:}`", type1, type2), span
+            errs.push(XSError::new(
+                Code::InvalidComparisonOperands,
+                &format!("Cannot compare types `{:}` and `{:}`", type1, type2),
+                span,
             ));
             None
         }
@@ -130,8 +212,10 @@ pub fn logical_op<'src>(
     match (type1, type2) {
         (Type::Bool, Type::Bool) => { Some(&Type::Bool) }
         _ => {
-            errs.push(type_err(
-                &format!("Cannot {:} types `{:}` and `{:}`", op_name, type1, type2), span
+            errs.push(XSError::new(
+                Code::InvalidLogicalOperands,
+                &format!("Cannot {:} types `{:}` and `{:}`", op_name, type1, type2),
+                span,
             ));
             None
         }
@@ -149,30 +233,36 @@ pub fn type_cmp(
     match (expected, actual) {
         (_, _) if *expected == *actual => {},
         (Type::Int, Type::Bool) if is_case_expr => {
-            errs.push(syntax_err(
+            errs.push(XSError::new(
+                Code::BoolCaseExpr,
                 "Using booleans in a case's expression will cause a silent XS crash",
                 actual_span
             ))
         }
         (Type::Int, Type::Bool) => {} // yES
         (Type::Int, Type::Float) => {
-            errs.push(warn(
+            errs.push(XSError::new(
+                Code::FloatDowncast,
                 "Possible loss of precision due to downcast from `float` to an `int`",
                 actual_span
             ))
         }
         (Type::Float, Type::Int | Type::Bool) => if is_fn_call {
-            errs.push(warn(
+            errs.push(XSError::new(
+                Code::NoFloatPromotion,
                 "Intermediate `int` or `bool` values do not get promoted to `float` in a \
-                function call, floating point operations on this parameter will not work correctly. \
-                Consider explicitly assigning this expression to a temporary `float` variable \
-                before passing that as a parameter. yES",
+                function call, floating point operations on this parameter will not work correctly",
                 actual_span
+            ).with_note(
+                "Consider explicitly assigning this expression to a temporary `float` variable \
+                before passing that as a parameter. yES"
             ))
         }
         _ => {
-            errs.push(type_err(
-                &format!("Expected `{:}` found `{:}`", expected, actual), actual_span
+            errs.push(XSError::new(
+                Code::TypeMismatch,
+                &format!("Expected `{:}` found `{:}`", expected, actual),
+                actual_span,
             ))
         }
     }
@@ -184,13 +274,10 @@ pub fn chk_rule_opt<'src>(
     opt_spans: &mut HashMap<&'static str, &'src Span>,
     errs: &mut Vec<XSError>,
 ) -> bool {
-    return if let Some(&og_span) = opt_spans.get(opt_type) {
-        errs.push(syntax_err(
-            &format!("Cannot set {:} twice", opt_type), og_span
-        ));
-        errs.push(syntax_err(
-            &format!("Cannot set {:} twice", opt_type), opt_span
-        ));
+    if let Some(&og_span) = opt_spans.get(opt_type) {
+        errs.push(XSError::new(
+            Code::DuplicateRuleOpt, &format!("Cannot set {:} twice", opt_type), opt_span
+        ).with_related("first set here", og_span));
         true
     } else {
         opt_spans.push((opt_type, opt_span));
@@ -1,35 +1,439 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use serde::{Serialize, Serializer};
 use crate::parsing::span::{Span, Spanned};
+use crate::r#static::module_loader::FileId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error   => write!(f, "error"),
+        }
+    }
+}
+
+/// Every check has its own code. Codes are stable: a code is never reused for a different check
+/// and new checks are only ever appended. Errors are numbered from `XS0001` and warnings from
+/// `XS1001`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Code {
+    InvalidToken,
+    UnexpectedToken,
+    UndefinedName,
+    NameInUse,
+    TypeMismatch,
+    NotCallable,
+    TooManyArgs,
+    TopLevelOnly,
+    LocalOnly,
+    ReturnNotAllowed,
+    MissingReturnValue,
+    ReturnNotParenthesised,
+    VoidReturnValue,
+    NonBoolCondition,
+    BreakNotAllowed,
+    ContinueNotAllowed,
+    DuplicateRuleOpt,
+    IntLitTooLarge,
+    NonLiteralInit,
+    NonLiteralParamDefault,
+    ConstWithoutValue,
+    ExternLocal,
+    FnRedefinition,
+    MutableFnSignMismatch,
+    InvalidArithOperands,
+    InvalidComparisonOperands,
+    InvalidLogicalOperands,
+    UnaryNot,
+    NonLiteralNeg,
+    NonNumVecInit,
+    InvalidPostfix,
+    DiscardedNonCall,
+    InvalidDbg,
+    InvalidMemberModifier,
+    BoolCaseExpr,
+    IncludeNotFound,
+    IncludeCycle,
+    InvalidFmtArg,
+    NonPositiveLimit,
+    InvalidMember,
+    IncludeUnreadable,
+
+    IntFirstArith,
+    FloatDowncast,
+    NoFloatPromotion,
+    CrashingComparison,
+    TopLevelStrInit,
+    DuplicateCase,
+    DuplicateDefault,
+    IgnoredReturnValue,
+    Breakpoint,
+    UnusableClass,
+    FmtArgCount,
+    MissingReturn,
+    UnreachableCode,
+    UnusedLabel,
+    UnusedVariable,
+    DeadStore,
+    UnusedParam,
+    UninitRead,
+    DivByZero,
+    NegativeArraySize,
+    ArrayIndexOutOfRange,
+    IntOverflow,
+    ConstCondition,
+    UnknownBuiltin,
+}
+
+impl Code {
+    pub const ALL: &'static [Code] = &[
+        Code::InvalidToken,
+        Code::UnexpectedToken,
+        Code::UndefinedName,
+        Code::NameInUse,
+        Code::TypeMismatch,
+        Code::NotCallable,
+        Code::TooManyArgs,
+        Code::TopLevelOnly,
+        Code::LocalOnly,
+        Code::ReturnNotAllowed,
+        Code::MissingReturnValue,
+        Code::ReturnNotParenthesised,
+        Code::VoidReturnValue,
+        Code::NonBoolCondition,
+        Code::BreakNotAllowed,
+        Code::ContinueNotAllowed,
+        Code::DuplicateRuleOpt,
+        Code::IntLitTooLarge,
+        Code::NonLiteralInit,
+        Code::NonLiteralParamDefault,
+        Code::ConstWithoutValue,
+        Code::ExternLocal,
+        Code::FnRedefinition,
+        Code::MutableFnSignMismatch,
+        Code::InvalidArithOperands,
+        Code::InvalidComparisonOperands,
+        Code::InvalidLogicalOperands,
+        Code::UnaryNot,
+        Code::NonLiteralNeg,
+        Code::NonNumVecInit,
+        Code::InvalidPostfix,
+        Code::DiscardedNonCall,
+        Code::InvalidDbg,
+        Code::InvalidMemberModifier,
+        Code::BoolCaseExpr,
+        Code::IncludeNotFound,
+        Code::IncludeCycle,
+        Code::InvalidFmtArg,
+        Code::NonPositiveLimit,
+        Code::InvalidMember,
+        Code::IncludeUnreadable,
+
+        Code::IntFirstArith,
+        Code::FloatDowncast,
+        Code::NoFloatPromotion,
+        Code::CrashingComparison,
+        Code::TopLevelStrInit,
+        Code::DuplicateCase,
+        Code::DuplicateDefault,
+        Code::IgnoredReturnValue,
+        Code::Breakpoint,
+        Code::UnusableClass,
+        Code::FmtArgCount,
+        Code::MissingReturn,
+        Code::UnreachableCode,
+        Code::UnusedLabel,
+        Code::UnusedVariable,
+        Code::DeadStore,
+        Code::UnusedParam,
+        Code::UninitRead,
+        Code::DivByZero,
+        Code::NegativeArraySize,
+        Code::ArrayIndexOutOfRange,
+        Code::IntOverflow,
+        Code::ConstCondition,
+        Code::UnknownBuiltin,
+    ];
+
+    /// The stable identifier of this check, e.g. `XS0012`
+    pub fn id(&self) -> &'static str {
+        match self {
+            Code::InvalidToken              => "XS0001",
+            Code::UnexpectedToken           => "XS0002",
+            Code::UndefinedName             => "XS0003",
+            Code::NameInUse                 => "XS0004",
+            Code::TypeMismatch              => "XS0005",
+            Code::NotCallable               => "XS0006",
+            Code::TooManyArgs               => "XS0007",
+            Code::TopLevelOnly              => "XS0008",
+            Code::LocalOnly                 => "XS0009",
+            Code::ReturnNotAllowed          => "XS0010",
+            Code::MissingReturnValue        => "XS0011",
+            Code::ReturnNotParenthesised    => "XS0012",
+            Code::VoidReturnValue           => "XS0013",
+            Code::NonBoolCondition          => "XS0014",
+            Code::BreakNotAllowed           => "XS0015",
+            Code::ContinueNotAllowed        => "XS0016",
+            Code::DuplicateRuleOpt          => "XS0017",
+            Code::IntLitTooLarge            => "XS0018",
+            Code::NonLiteralInit            => "XS0019",
+            Code::NonLiteralParamDefault    => "XS0020",
+            Code::ConstWithoutValue         => "XS0021",
+            Code::ExternLocal               => "XS0022",
+            Code::FnRedefinition            => "XS0023",
+            Code::MutableFnSignMismatch     => "XS0024",
+            Code::InvalidArithOperands      => "XS0025",
+            Code::InvalidComparisonOperands => "XS0026",
+            Code::InvalidLogicalOperands    => "XS0027",
+            Code::UnaryNot                  => "XS0028",
+            Code::NonLiteralNeg             => "XS0029",
+            Code::NonNumVecInit             => "XS0030",
+            Code::InvalidPostfix            => "XS0031",
+            Code::DiscardedNonCall          => "XS0032",
+            Code::InvalidDbg                => "XS0033",
+            Code::InvalidMemberModifier     => "XS0034",
+            Code::BoolCaseExpr              => "XS0035",
+            Code::IncludeNotFound           => "XS0036",
+            Code::IncludeCycle              => "XS0037",
+            Code::InvalidFmtArg             => "XS0038",
+            Code::NonPositiveLimit          => "XS0039",
+            Code::InvalidMember             => "XS0040",
+            Code::IncludeUnreadable         => "XS0041",
+
+            Code::IntFirstArith             => "XS1001",
+            Code::FloatDowncast             => "XS1002",
+            Code::NoFloatPromotion          => "XS1003",
+            Code::CrashingComparison        => "XS1004",
+            Code::TopLevelStrInit           => "XS1005",
+            Code::DuplicateCase             => "XS1006",
+            Code::DuplicateDefault          => "XS1007",
+            Code::IgnoredReturnValue        => "XS1008",
+            Code::Breakpoint                => "XS1009",
+            Code::UnusableClass             => "XS1010",
+            Code::FmtArgCount               => "XS1011",
+            Code::MissingReturn             => "XS1012",
+            Code::UnreachableCode           => "XS1013",
+            Code::UnusedLabel               => "XS1014",
+            Code::UnusedVariable            => "XS1015",
+            Code::DeadStore                 => "XS1016",
+            Code::UnusedParam               => "XS1017",
+            Code::UninitRead                => "XS1018",
+            Code::DivByZero                 => "XS1019",
+            Code::NegativeArraySize         => "XS1020",
+            Code::ArrayIndexOutOfRange      => "XS1021",
+            Code::IntOverflow               => "XS1022",
+            Code::ConstCondition            => "XS1023",
+            Code::UnknownBuiltin            => "XS1024",
+        }
+    }
+
+    /// Errors stop a script from compiling in game, warnings point out code which compiles but is
+    /// likely to misbehave
+    pub fn severity(&self) -> Severity {
+        match self {
+            Code::InvalidToken
+            | Code::UnexpectedToken
+            | Code::UndefinedName
+            | Code::NameInUse
+            | Code::TypeMismatch
+            | Code::NotCallable
+            | Code::TooManyArgs
+            | Code::TopLevelOnly
+            | Code::LocalOnly
+            | Code::ReturnNotAllowed
+            | Code::MissingReturnValue
+            | Code::ReturnNotParenthesised
+            | Code::VoidReturnValue
+            | Code::NonBoolCondition
+            | Code::BreakNotAllowed
+            | Code::ContinueNotAllowed
+            | Code::DuplicateRuleOpt
+            | Code::IntLitTooLarge
+            | Code::NonLiteralInit
+            | Code::NonLiteralParamDefault
+            | Code::ConstWithoutValue
+            | Code::ExternLocal
+            | Code::FnRedefinition
+            | Code::MutableFnSignMismatch
+            | Code::InvalidArithOperands
+            | Code::InvalidComparisonOperands
+            | Code::InvalidLogicalOperands
+            | Code::UnaryNot
+            | Code::NonLiteralNeg
+            | Code::NonNumVecInit
+            | Code::InvalidPostfix
+            | Code::DiscardedNonCall
+            | Code::InvalidDbg
+            | Code::InvalidMemberModifier
+            | Code::BoolCaseExpr
+            | Code::IncludeNotFound
+            | Code::IncludeCycle
+            | Code::InvalidFmtArg
+            | Code::NonPositiveLimit
+            | Code::InvalidMember
+            | Code::IncludeUnreadable => Severity::Error,
+
+            Code::IntFirstArith
+            | Code::FloatDowncast
+            | Code::NoFloatPromotion
+            | Code::CrashingComparison
+            | Code::TopLevelStrInit
+            | Code::DuplicateCase
+            | Code::DuplicateDefault
+            | Code::IgnoredReturnValue
+            | Code::Breakpoint
+            | Code::UnusableClass
+            | Code::FmtArgCount
+            | Code::MissingReturn
+            | Code::UnreachableCode
+            | Code::UnusedLabel
+            | Code::UnusedVariable
+            | Code::DeadStore
+            | Code::UnusedParam
+            | Code::UninitRead
+            | Code::DivByZero
+            | Code::NegativeArraySize
+            | Code::ArrayIndexOutOfRange
+            | Code::IntOverflow
+            | Code::ConstCondition
+            | Code::UnknownBuiltin => Severity::Warning,
+        }
+    }
+
+    /// A short human-readable name for this check
+    pub fn name(&self) -> &'static str {
+        match self {
+            Code::InvalidToken              => "invalid token",
+            Code::UnexpectedToken           => "unexpected token",
+            Code::UndefinedName             => "undefined name",
+            Code::NameInUse                 => "name already in use",
+            Code::TypeMismatch              => "type mismatch",
+            Code::NotCallable               => "value is not callable",
+            Code::TooManyArgs               => "too many arguments",
+            Code::TopLevelOnly              => "statement only allowed at the top level",
+            Code::LocalOnly                 => "statement only allowed in a local scope",
+            Code::ReturnNotAllowed          => "return outside of a function or rule",
+            Code::MissingReturnValue        => "missing return value",
+            Code::ReturnNotParenthesised    => "return expression not parenthesised",
+            Code::VoidReturnValue           => "value returned from void function",
+            Code::NonBoolCondition          => "condition is not a boolean",
+            Code::BreakNotAllowed           => "break outside of a loop or case",
+            Code::ContinueNotAllowed        => "continue outside of a loop",
+            Code::DuplicateRuleOpt          => "duplicate rule option",
+            Code::IntLitTooLarge            => "int literal too large",
+            Code::NonLiteralInit            => "non literal initializer",
+            Code::NonLiteralParamDefault    => "non literal parameter default",
+            Code::ConstWithoutValue         => "const without a value",
+            Code::ExternLocal               => "extern local variable",
+            Code::FnRedefinition            => "redefinition of non mutable function",
+            Code::MutableFnSignMismatch     => "inconsistent mutable function signature",
+            Code::InvalidArithOperands      => "invalid arithmetic operands",
+            Code::InvalidComparisonOperands => "invalid comparison operands",
+            Code::InvalidLogicalOperands    => "invalid logical operands",
+            Code::UnaryNot                  => "unary not",
+            Code::NonLiteralNeg             => "unary negative on a non literal",
+            Code::NonNumVecInit             => "non numeric literal in vector",
+            Code::InvalidPostfix            => "invalid postfix operand",
+            Code::DiscardedNonCall          => "discarded expression is not a call",
+            Code::InvalidDbg                => "invalid dbg operand",
+            Code::InvalidMemberModifier     => "invalid member variable modifier",
+            Code::BoolCaseExpr              => "boolean case expression",
+            Code::IncludeNotFound           => "included file not found",
+            Code::IncludeCycle              => "include cycle",
+            Code::InvalidFmtArg             => "invalid format argument",
+            Code::NonPositiveLimit          => "non positive limit",
+            Code::InvalidMember             => "invalid member access",
+            Code::IncludeUnreadable         => "included file cannot be read",
+
+            Code::IntFirstArith             => "int first arithmetic",
+            Code::FloatDowncast             => "float downcast",
+            Code::NoFloatPromotion          => "no float promotion",
+            Code::CrashingComparison        => "crashing comparison",
+            Code::TopLevelStrInit           => "top level string initializer",
+            Code::DuplicateCase             => "duplicate case",
+            Code::DuplicateDefault          => "duplicate default",
+            Code::IgnoredReturnValue        => "ignored return value",
+            Code::Breakpoint                => "breakpoint",
+            Code::UnusableClass             => "unusable class",
+            Code::FmtArgCount               => "format argument count mismatch",
+            Code::MissingReturn             => "missing return on some paths",
+            Code::UnreachableCode           => "unreachable code",
+            Code::UnusedLabel               => "unused label",
+            Code::UnusedVariable            => "unused variable",
+            Code::DeadStore                 => "dead store",
+            Code::UnusedParam               => "unused parameter",
+            Code::UninitRead                => "possibly unassigned read",
+            Code::DivByZero                 => "division by zero",
+            Code::NegativeArraySize         => "negative array size",
+            Code::ArrayIndexOutOfRange      => "array index out of range",
+            Code::IntOverflow               => "int overflow",
+            Code::ConstCondition            => "constant condition",
+            Code::UnknownBuiltin            => "unknown builtin",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Code> {
+        Code::ALL.iter().find(|code| code.id() == id).copied()
+    }
+}
+
+impl Display for Code {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+impl Serialize for Code {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
 
 #[derive(Clone, Debug)]
-pub enum XSError {
-    TypeError(Spanned<String>),
-    NameError(Spanned<String>),
-    SyntaxError(Spanned<String>),
-    
-    Warning(Spanned<String>),
-}
-
-pub fn type_err(msg: &str, span: &Span) -> XSError {
-    XSError::TypeError((
-        format!("TypeError: {:}", msg),
-        span.clone()
-    ))
-}
-pub fn name_err(msg: &str, span: &Span) -> XSError {
-    XSError::NameError((
-        format!("NameError: {:}", msg),
-        span.clone()
-    ))
-}
-pub fn syntax_err(msg: &str, span: &Span) -> XSError {
-    XSError::SyntaxError((
-        format!("SyntaxError: {:}", msg),
-        span.clone()
-    ))
-}
-pub fn warn(msg: &str, span: &Span) -> XSError {
-    XSError::Warning((
-        format!("Warning: {:}", msg),
-        span.clone()
-    ))
-}
\ No newline at end of file
+pub struct XSError {
+    pub code: Code,
+    pub severity: Severity,
+    pub msg: String,
+    pub span: Span,
+    /// secondary spans with a label explaining how they relate to the primary span
+    pub related: Vec<Spanned<String>>,
+    pub notes: Vec<String>,
+    /// set by the module loader once the error has been attributed to a file
+    pub file_id: FileId,
+}
+
+impl XSError {
+    pub fn new(code: Code, msg: &str, span: &Span) -> Self {
+        XSError {
+            code,
+            severity: code.severity(),
+            msg: msg.to_string(),
+            span: *span,
+            related: vec![],
+            notes: vec![],
+            file_id: FileId::default(),
+        }
+    }
+
+    pub fn with_related(mut self, label: &str, span: &Span) -> Self {
+        self.related.push((label.to_string(), *span));
+        self
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.severity == Severity::Warning
+    }
+}
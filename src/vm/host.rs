@@ -0,0 +1,28 @@
+use crate::dynamic::interpreter::Interpreter;
+use crate::dynamic::value::Value;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::span::Span;
+
+/// Provides the builtin functions to the VM. The rule builtins are run by the VM itself, since
+/// they change how the VM schedules its rules
+pub trait Host {
+    /// Runs the builtin `name` at `time` milliseconds into the game. An error stops the script
+    fn call(&mut self, name: &Identifier, args: Vec<Value>, time: i32) -> Result<Option<Value>, String>;
+
+    /// Writes a line which traces the script as it runs, like the rules run by
+    /// [Vm::simulate](crate::vm::machine::Vm::simulate)
+    fn trace(&mut self, line: &str) -> Result<(), String>;
+}
+
+/// The interpreter runs the builtins which do not depend on the state of a game, so scripts behave
+/// the same on the VM as they do when interpreted
+impl Host for Interpreter<'_> {
+    fn call(&mut self, name: &Identifier, args: Vec<Value>, time: i32) -> Result<Option<Value>, String> {
+        self.scheduler.time = time;
+        self.builtin(name, args, &Span::new(0, 0)).map_err(|err| err.msg)
+    }
+
+    fn trace(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.out, "{}", line).map_err(|err| format!("Cannot write trace: {}", err))
+    }
+}
@@ -0,0 +1,381 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::codegen::bc::{Addr, Bytecode, Instr, Operand};
+use crate::codegen::pa::{BinOp, Program, Reg, UnOp};
+use crate::dynamic::expression::no_float_promotion;
+use crate::dynamic::scheduler::Scheduler;
+use crate::dynamic::value::{arith, compare, equals, ArithOp, Value};
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::type_::Type;
+use crate::r#static::prelude::{consts, PreludeVersion};
+use crate::vm::host::Host;
+use crate::vm::scheduler::rules;
+
+/// The value of `r_lp` when a procedure is called by the host rather than by a `jmp`, so its
+/// `ret` hands control back to the host
+const HOST_LP: i32 = -1;
+
+/// An error which stops a script running on the VM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VmError {
+    pub msg: String,
+    /// the instruction which was running
+    pub addr: Addr,
+    /// the procedures which were running, innermost first
+    pub trace: Vec<Identifier>,
+}
+
+impl Display for VmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.msg, self.addr)?;
+        for name in self.trace.iter() {
+            write!(f, "\n    in `{}`", name.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs bytecode on the register machine described in `maths/docs/xs_vm/xs_rt.md`
+pub struct Vm<'a> {
+    pub(crate) bc: &'a Bytecode,
+    pub(crate) host: &'a mut dyn Host,
+    regs: [Value; 32],
+    /// each slot holds one value of any type
+    stack: Vec<Value>,
+    /// the values of the globals, by the index of their name
+    globals: Vec<Option<Value>>,
+    procs: HashMap<u32, Addr>,
+    /// the procedures being run, innermost last
+    calls: Vec<u32>,
+    pc: Addr,
+
+    /// when the rules run, and the game clock they run on
+    pub scheduler: Scheduler,
+    /// how deep procedure calls may be nested before the script is stopped
+    pub recursion_limit: usize,
+    /// the number of instructions a call made by the host may run before the script is stopped,
+    /// as a script which never finishes would otherwise hang
+    pub max_steps: Option<u64>,
+    /// the number of instructions run since the last call made by the host
+    steps: u64,
+}
+
+impl<'a> Vm<'a> {
+    /// A VM with the builtin constants of `version` defined, ready to [Vm::load] `bc`
+    pub fn new(bc: &'a Bytecode, host: &'a mut dyn Host, version: PreludeVersion) -> Self {
+        let (consts, vec_consts) = consts(version);
+        let builtin_consts = consts.iter()
+            .map(|(name, lit)| (*name, Value::from_lit(lit)))
+            .chain(vec_consts.iter().map(|(name, [x, y, z])| {
+                (*name, Value::Vec([*x as f32, *y as f32, *z as f32]))
+            }))
+            .collect::<HashMap<&str, Value>>();
+        let globals = bc.names.iter().map(|name| builtin_consts.get(name.0.as_str()).cloned()).collect();
+
+        let mut regs = std::array::from_fn(|_| Value::Int(0));
+        regs[Reg::LP.0 as usize] = Value::Int(HOST_LP);
+
+        Vm {
+            bc,
+            host,
+            regs,
+            stack: vec![],
+            globals,
+            procs: bc.procs.iter().copied().collect(),
+            calls: vec![],
+            pc: 0,
+            scheduler: Scheduler { rules: rules(bc), ..Scheduler::default() },
+            recursion_limit: bc.recursion_limit.map_or(256, |limit| limit as usize),
+            max_steps: None,
+            steps: 0,
+        }
+    }
+
+    /// Initialises the global variables and schedules the rules
+    pub fn load(&mut self) -> Result<(), VmError> {
+        let now = self.scheduler.time;
+        for rule in self.scheduler.rules.iter_mut() {
+            rule.schedule(now);
+        }
+        self.call(Program::GLOBALS, vec![]).map(|_| ())
+    }
+
+    /// Whether a function or rule named `name` has been defined
+    pub fn is_callable(&self, name: &str) -> bool {
+        name != Program::GLOBALS && self.bc.entry(name).is_some()
+    }
+
+    /// Calls a function or runs a rule by name. Unlike a call made by the script, parameters which
+    /// are not given do not take their default values. Returns the value left in `r_ret`, which
+    /// is meaningless for `void` functions and rules
+    pub fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value, VmError> {
+        let found = self.bc.procs.iter().find(|(idx, _entry)| self.bc.names[*idx as usize].0 == name);
+        let Some((name_idx, entry)) = found.copied() else {
+            return Err(self.error(&format!("Undefined function or rule `{}`", name)));
+        };
+
+        self.scheduler.enter(name);
+        let (sp, lp) = (self.read(&Operand::Reg(Reg::SP))?, self.read(&Operand::Reg(Reg::LP))?);
+        self.steps = 0;
+        let result = self.call_proc(name_idx, entry, args);
+
+        // the stack is unwound even when the script is stopped, so the host may call it again
+        self.scheduler.current = None;
+        self.calls.clear();
+        self.write(&Operand::Reg(Reg::SP), sp)?;
+        self.write(&Operand::Reg(Reg::LP), lp)?;
+        result.map(|_| self.regs[Reg::RET.0 as usize].clone())
+    }
+
+    fn call_proc(&mut self, name: u32, entry: Addr, args: Vec<Value>) -> Result<(), VmError> {
+        for arg in args.into_iter().rev() {
+            self.push(arg)?;
+        }
+        self.write(&Operand::Reg(Reg::LP), Value::Int(HOST_LP))?;
+        self.enter(name, entry)?;
+        self.run()
+    }
+
+    pub(crate) fn error(&self, msg: &str) -> VmError {
+        VmError {
+            msg: msg.to_string(),
+            addr: self.pc,
+            trace: self.calls.iter().rev().map(|name| self.bc.names[*name as usize].clone()).collect(),
+        }
+    }
+
+    /// Starts running the procedure `name`. The variables are initialised by [Vm::load], which is
+    /// not counted as a call
+    fn enter(&mut self, name: u32, entry: Addr) -> Result<(), VmError> {
+        let is_globals = |name: u32| self.bc.names[name as usize].0 == Program::GLOBALS;
+        let depth = self.calls.iter().filter(|call| !is_globals(**call)).count();
+        if !is_globals(name) && depth >= self.recursion_limit {
+            return Err(self.error(&format!("Exceeded the maximum call depth of {}", self.recursion_limit)));
+        }
+        self.calls.push(name);
+        self.pc = entry;
+        Ok(())
+    }
+
+    /// Runs instructions until the procedure called by the host returns
+    fn run(&mut self) -> Result<(), VmError> {
+        let bc = self.bc;
+        loop {
+            let Some(instr) = bc.code.get(self.pc) else {
+                return Err(self.error("Ran past the end of the code"));
+            };
+            self.steps += 1;
+            if let Some(max) = self.max_steps.filter(|max| self.steps > *max) {
+                return Err(self.error(&format!("Stopped after {} steps, the script may never finish", max)));
+            }
+            match instr {
+                Instr::Copy { dst, src } => {
+                    let value = self.read(src)?;
+                    self.write(dst, value)?;
+                }
+                Instr::Unary { dst, op, src } => {
+                    let value = self.read(src)?;
+                    let value = self.unary(*op, value)?;
+                    self.write(dst, value)?;
+                }
+                Instr::Binary { dst, op, src1, src2 } => {
+                    let (val1, val2) = (self.read(src1)?, self.read(src2)?);
+                    let value = self.binary(*op, val1, val2)?;
+                    self.write(dst, value)?;
+                }
+                Instr::Vec { dst, x, y, z } => {
+                    let mut vec = [0.0; 3];
+                    for (component, src) in vec.iter_mut().zip([x, y, z]) {
+                        match self.read(src)?.convert(&Type::Float) {
+                            Some(Value::Float(val)) => *component = val,
+                            _ => return Err(self.error("Expected a value of type `float`")),
+                        }
+                    }
+                    self.write(dst, Value::Vec(vec))?;
+                }
+                Instr::Ret => {
+                    self.calls.pop();
+                    match self.read(&Operand::Reg(Reg::LP))? {
+                        Value::Int(HOST_LP) => return Ok(()),
+                        Value::Int(lp) => self.pc = lp as Addr,
+                        _ => return Err(self.error("`r_lp` does not hold an address")),
+                    }
+                }
+                Instr::Jmp { name, argc } => match self.procs.get(name).copied() {
+                    Some(entry) => {
+                        self.write(&Operand::Reg(Reg::LP), Value::Int(self.pc as i32))?;
+                        self.enter(*name, entry)?;
+                        continue;
+                    }
+                    None => self.builtin(*name, *argc as usize)?,
+                },
+                Instr::Ifn { cond, addr } => match self.read(cond)? {
+                    Value::Bool(true) => {}
+                    Value::Bool(false) => {
+                        self.pc = *addr;
+                        continue;
+                    }
+                    _ => return Err(self.error("Conditional expression must be a boolean value")),
+                },
+                Instr::Goto(addr) => {
+                    self.pc = *addr;
+                    continue;
+                }
+                Instr::Push(src) => {
+                    let value = self.read(src)?;
+                    self.push(value)?;
+                }
+                Instr::Pop(dst) => {
+                    let sp = self.addr_of(Reg::SP, -1)?;
+                    let value = self.load_slot(sp)?;
+                    self.write(&Operand::Reg(Reg::SP), Value::Int(sp as i32))?;
+                    self.write(dst, value)?;
+                }
+                Instr::Trap(msg) => {
+                    let Value::Str(msg) = &self.bc.consts[*msg as usize] else {
+                        return Err(self.error("The message of a `trap` must be a string"));
+                    };
+                    return Err(self.error(msg));
+                }
+            }
+            self.pc += 1;
+        }
+    }
+
+    /// Calls a builtin with the `argc` arguments at the top of the stack, the first argument on top
+    fn builtin(&mut self, name: u32, argc: usize) -> Result<(), VmError> {
+        let sp = self.addr_of(Reg::SP, 0)?;
+        let mut args = Vec::with_capacity(argc);
+        for idx in 1..=argc as i64 {
+            args.push(self.load_slot(sp - idx)?);
+        }
+
+        let bc = self.bc;
+        let name_id = &bc.names[name as usize];
+        let value = match self.rule_builtin(name_id, &args) {
+            Some(result) => result?,
+            None => {
+                let time = self.scheduler.time;
+                self.host.call(name_id, args, time).map_err(|msg| self.error(&msg))?
+            }
+        };
+        if let Some(value) = value {
+            self.write(&Operand::Reg(Reg::RET), value)?;
+        }
+        Ok(())
+    }
+
+    fn unary(&self, op: UnOp, value: Value) -> Result<Value, VmError> {
+        match (op, value) {
+            (UnOp::Neg, Value::Int(val)) => Ok(Value::Int(val.wrapping_neg())),
+            (UnOp::Neg, Value::Float(val)) => Ok(Value::Float(-val)),
+            (UnOp::Not, Value::Bool(val)) => Ok(Value::Bool(!val)),
+            (UnOp::Neg | UnOp::Not, value) => {
+                Err(self.error(&format!("Cannot negate a value of type `{}`", value.type_())))
+            }
+            (UnOp::Conv(type_), value) => value.convert(&type_.type_())
+                .ok_or_else(|| self.error(&format!("Expected a value of type `{}`", type_.type_()))),
+            (UnOp::Bits, value) => Ok(no_float_promotion(value)),
+            (UnOp::Member(idx), Value::Vec(vec)) => Ok(Value::Float(vec[idx as usize])),
+            (UnOp::Member(idx), value) => Err(self.error(&format!(
+                "Type `{}` has no member `{}`", value.type_(), ["x", "y", "z"][idx as usize]
+            ))),
+        }
+    }
+
+    fn binary(&self, op: BinOp, val1: Value, val2: Value) -> Result<Value, VmError> {
+        let arith_op = match op {
+            BinOp::Add => Some(ArithOp::Add),
+            BinOp::Sub => Some(ArithOp::Sub),
+            BinOp::Mul => Some(ArithOp::Mul),
+            BinOp::Div => Some(ArithOp::Div),
+            BinOp::Rem => Some(ArithOp::Rem),
+            _ => None,
+        };
+        if let Some(arith_op) = arith_op {
+            return arith(arith_op, val1, val2).map_err(|msg| self.error(&msg));
+        }
+
+        let test: fn(Ordering) -> bool = match op {
+            BinOp::Lt => Ordering::is_lt,
+            BinOp::Gt => Ordering::is_gt,
+            BinOp::Le => Ordering::is_le,
+            BinOp::Ge => Ordering::is_ge,
+            BinOp::Eq | BinOp::Ne => {
+                let eq = equals(&val1, &val2).map_err(|msg| self.error(&msg))?;
+                return Ok(Value::Bool(eq == (op == BinOp::Eq)));
+            }
+            _ => return match (val1, val2) {
+                (Value::Bool(val1), Value::Bool(val2)) => {
+                    Ok(Value::Bool(if op == BinOp::And { val1 && val2 } else { val1 || val2 }))
+                }
+                (val1, val2) => Err(self.error(&format!(
+                    "Cannot use a logical operator on types `{}` and `{}`", val1.type_(), val2.type_()
+                ))),
+            },
+        };
+        // comparisons with NaN are always false
+        let ordering = compare(&val1, &val2).map_err(|msg| self.error(&msg))?;
+        Ok(Value::Bool(ordering.is_some_and(test)))
+    }
+
+    fn read(&self, src: &Operand) -> Result<Value, VmError> {
+        match src {
+            Operand::Reg(Reg::ZERO) => Ok(Value::Int(0)),
+            Operand::Reg(reg) => Ok(self.regs[reg.0 as usize].clone()),
+            Operand::Const(idx) => Ok(self.bc.consts[*idx as usize].clone()),
+            Operand::Global(idx) => self.globals[*idx as usize].clone().ok_or_else(|| {
+                self.error(&format!("Undefined name `{}`", self.bc.names[*idx as usize].0))
+            }),
+            Operand::Mem(reg, offset) => self.load_slot(self.addr_of(*reg, *offset as i64)?),
+        }
+    }
+
+    fn write(&mut self, dst: &Operand, value: Value) -> Result<(), VmError> {
+        match dst {
+            Operand::Reg(Reg::ZERO) => {}
+            Operand::Reg(reg) => self.regs[reg.0 as usize] = value,
+            Operand::Const(_) => return Err(self.error("Cannot write to a constant")),
+            Operand::Global(idx) => self.globals[*idx as usize] = Some(value),
+            Operand::Mem(reg, offset) => {
+                let addr = self.addr_of(*reg, *offset as i64)?;
+                self.store_slot(addr, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, value: Value) -> Result<(), VmError> {
+        let sp = self.addr_of(Reg::SP, 0)?;
+        self.store_slot(sp, value)?;
+        self.write(&Operand::Reg(Reg::SP), Value::Int(sp as i32 + 1))
+    }
+
+    /// The address `offset` slots above the address in `reg`
+    fn addr_of(&self, reg: Reg, offset: i64) -> Result<i64, VmError> {
+        match self.read(&Operand::Reg(reg))? {
+            Value::Int(addr) => Ok(addr as i64 + offset),
+            _ => Err(self.error(&format!("`{}` does not hold an address", reg))),
+        }
+    }
+
+    fn load_slot(&self, addr: i64) -> Result<Value, VmError> {
+        usize::try_from(addr).ok()
+            .and_then(|addr| self.stack.get(addr))
+            .cloned()
+            .ok_or_else(|| self.error(&format!("Stack slot {} is empty", addr)))
+    }
+
+    fn store_slot(&mut self, addr: i64, value: Value) -> Result<(), VmError> {
+        let Ok(addr) = usize::try_from(addr) else {
+            return Err(self.error(&format!("Stack slot {} does not exist", addr)));
+        };
+        if addr >= self.stack.len() {
+            self.stack.resize(addr + 1, Value::Int(0));
+        }
+        self.stack[addr] = value;
+        Ok(())
+    }
+}
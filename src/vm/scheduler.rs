@@ -0,0 +1,76 @@
+use crate::codegen::bc::Bytecode;
+use crate::dynamic::scheduler::{simulate, Firing, Rule, RuleRunner, Scheduler, SchedulerConfig};
+use crate::dynamic::value::Value;
+use crate::parsing::ast::identifier::Identifier;
+use crate::parsing::ast::type_::Type;
+use crate::vm::machine::{Vm, VmError};
+
+impl RuleRunner for Vm<'_> {
+    type Error = VmError;
+
+    fn scheduler(&mut self) -> &mut Scheduler {
+        &mut self.scheduler
+    }
+
+    fn run_rule(&mut self, name: &Identifier) -> Result<(), VmError> {
+        self.call(&name.0, vec![]).map(|_| ())
+    }
+
+    fn trace(&mut self, line: &str) -> Result<(), VmError> {
+        self.host.trace(line).map_err(|msg| self.error(&msg))
+    }
+}
+
+/// The rules of `bc`, with the options they are defined with
+pub(crate) fn rules(bc: &Bytecode) -> Vec<Rule> {
+    bc.rules.iter()
+        .map(|entry| Rule {
+            active: entry.active,
+            run_immediately: entry.run_immediately,
+            min_interval: entry.min_interval,
+            max_interval: entry.max_interval,
+            priority: entry.priority,
+            group: entry.group.clone(),
+            ..Rule::new(bc.names[entry.name as usize].clone())
+        })
+        .collect()
+}
+
+impl Vm<'_> {
+    /// Runs the active rules on a simulated game clock, exactly like
+    /// [Interpreter::simulate](crate::dynamic::interpreter::Interpreter::simulate)
+    pub fn simulate(&mut self, duration: i32, cfg: &SchedulerConfig) -> Result<Vec<Firing>, VmError> {
+        simulate(self, duration, cfg)
+    }
+
+    /// Runs `name` if it is one of the rule builtins, which are run by the VM rather than its host
+    pub(crate) fn rule_builtin(
+        &mut self,
+        name: &Identifier,
+        args: &[Value],
+    ) -> Option<Result<Option<Value>, VmError>> {
+        let params = Scheduler::builtin_params(&name.0)?;
+        Some(self.convert_args(name, args, params).and_then(|args| {
+            self.scheduler.builtin(&name.0, &args).map_err(|msg| self.error(&msg))
+        }))
+    }
+
+    /// Checks and converts the arguments of a builtin like the interpreter does
+    fn convert_args(
+        &self,
+        name: &Identifier,
+        args: &[Value],
+        params: &[Type],
+    ) -> Result<Vec<Value>, VmError> {
+        if args.len() != params.len() {
+            return Err(self.error(&format!(
+                "Function `{}` takes {} arguments, but {} were given", name.0, params.len(), args.len()
+            )));
+        }
+        args.iter().zip(params).enumerate()
+            .map(|(idx, (arg, type_))| arg.clone().convert(type_).ok_or_else(|| self.error(
+                &format!("Argument {} of `{}` must be of type `{}`", idx + 1, name.0, type_)
+            )))
+            .collect()
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use xs_check::api::{Code, LintLevel, Position, Severity, SOURCE_PATH};
+use xs_check::{check_file, check_source, Options};
+
+const SCRIPT: &str = "include \"missing.xs\";
+
+void f() {
+    int a = 0;
+    /* é */ y = 1;
+}
+";
+
+/// An empty directory for the files of the test `name`
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xs-check-api-{}-{}", process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn check_source_reports_nothing_for_a_valid_script() {
+    let report = check_source("int f() { return (1); }", &Options::default());
+
+    assert_eq!(report.diagnostics, vec![]);
+    assert_eq!(report.files, vec![PathBuf::from(SOURCE_PATH)]);
+    assert!(!report.has_errors());
+}
+
+#[test]
+fn check_source_reports_an_unresolved_include() {
+    let report = check_source(SCRIPT, &Options::default());
+    let diag = &report.diagnostics[0];
+
+    assert_eq!(diag.code, Code::IncludeNotFound);
+    assert_eq!(diag.severity, Severity::Error);
+    assert_eq!(diag.message, "Cannot find included file `missing.xs`");
+    assert_eq!(diag.location.file, PathBuf::from(SOURCE_PATH));
+    assert_eq!(diag.location.span, 8..20);
+}
+
+#[test]
+fn report_counts_errors_and_warnings() {
+    let report = check_source(SCRIPT, &Options::default());
+    let codes = report.diagnostics.iter().map(|diag| diag.code).collect::<Vec<Code>>();
+
+    assert_eq!(codes, vec![Code::IncludeNotFound, Code::UnusedVariable, Code::UndefinedName]);
+    assert_eq!(report.num_errors(), 2);
+    assert_eq!(report.num_warnings(), 1);
+    assert!(report.has_errors());
+}
+
+#[test]
+fn lints_turn_off_or_escalate_warnings() {
+    let lint = |level| Options {
+        lints: HashMap::from([(Code::UnusedVariable, level)]),
+        ..Options::default()
+    };
+    assert_eq!(check_source(SCRIPT, &lint(LintLevel::Off)).num_warnings(), 0);
+
+    let report = check_source(SCRIPT, &lint(LintLevel::Error));
+    assert_eq!((report.num_errors(), report.num_warnings()), (3, 0));
+}
+
+#[test]
+fn location_counts_lines_and_chars_from_one() {
+    let report = check_source(SCRIPT, &Options::default());
+    let location = &report.diagnostics[2].location;
+
+    // `é` is two bytes but one column
+    assert_eq!(location.span, 62..63);
+    assert_eq!(location.start, Position { line: 5, column: 13 });
+    assert_eq!(location.end, Position { line: 5, column: 14 });
+}
+
+#[test]
+fn diagnostic_display() {
+    let report = check_source(SCRIPT, &Options::default());
+    let lines = report.diagnostics.iter().map(|diag| diag.to_string()).collect::<Vec<String>>();
+
+    assert_eq!(lines, vec![
+        "<source>:1:9: error[XS0036]: Cannot find included file `missing.xs`",
+        "<source>:4:9: warning[XS1015]: Variable `a` is never read",
+        "<source>:5:13: error[XS0003]: Undefined variable",
+    ]);
+}
+
+#[test]
+fn check_file_checks_included_files() {
+    let dir = temp_dir("check_file");
+    fs::write(dir.join("main.xs"), "include \"lib.xs\";\nint g() { return (LIB); }\n").unwrap();
+    fs::write(dir.join("lib.xs"), "const int LIB = 1;\nint h() { return (missing); }\n").unwrap();
+
+    let report = check_file(&dir.join("main.xs"), &Options::default()).unwrap();
+    let names = report.files.iter().map(|file| file.file_name().unwrap()).collect::<Vec<_>>();
+
+    assert_eq!(names, vec!["main.xs", "lib.xs"]);
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].code, Code::UndefinedName);
+    assert_eq!(report.diagnostics[0].location.file.file_name().unwrap(), "lib.xs");
+    assert_eq!(report.diagnostics[0].location.start, Position { line: 2, column: 19 });
+}
+
+#[test]
+fn check_file_fails_if_the_script_cannot_be_read() {
+    let dir = temp_dir("unreadable");
+
+    assert!(check_file(&dir.join("missing.xs"), &Options::default()).is_err());
+    assert!(check_file(Path::new(""), &Options::default()).is_err());
+}
@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+use xs_check::internal::codegen::bc::encoding::{decode, encode};
+use xs_check::internal::codegen::bc::lower::lower_program;
+use xs_check::internal::codegen::bc::{Bytecode, Instr, Operand, RuleEntry};
+use xs_check::internal::codegen::pa::munch::lower_module;
+use xs_check::internal::codegen::pa::{BinOp, ConvType, Reg, UnOp};
+use xs_check::internal::dynamic::value::Value;
+use xs_check::internal::parsing::ast::identifier::Identifier;
+use xs_check::internal::r#static::module_loader::ModuleLoader;
+use xs_check::internal::r#static::prelude::{prelude, PreludeVersion};
+
+fn compile(loader: &mut ModuleLoader, path: &Path) -> Bytecode {
+    let module = loader.load(path).expect("the script can be read");
+    assert!(module.errs.is_empty(), "`{}` does not parse", path.display());
+    let program = lower_module(&module, loader, &prelude(PreludeVersion::LATEST));
+    lower_program(&program)
+}
+
+fn compile_src(src: &str) -> Bytecode {
+    let path = PathBuf::from("test.xs");
+    let mut loader = ModuleLoader::new(vec![]);
+    loader.set_source(&path, src.to_string());
+    compile(&mut loader, &path)
+}
+
+fn assert_round_trips(bc: &Bytecode) {
+    let bytes = encode(bc);
+    let decoded = decode(&bytes).expect("encoded bytecode decodes");
+    assert_eq!(&decoded, bc);
+    assert_eq!(encode(&decoded), bytes);
+    assert_eq!(decoded.to_string(), bc.to_string());
+}
+
+#[test]
+fn test_xs_round_trips() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_xs");
+    let mut loader = ModuleLoader::new(vec![dir.clone()]);
+    for entry in dir.read_dir().unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "xs") {
+            assert_round_trips(&compile(&mut loader, &path));
+        }
+    }
+}
+
+#[test]
+fn rules_and_statics_round_trip() {
+    let bc = compile_src(r#"
+        infiniteRecursionLimit 50;
+        int f(float x = 1.0, bool b = true) {
+            static int count = 0;
+            count++;
+            vector w = vector(1.5, -2.0, 3.0);
+            if (b && (w.x > x) || (count >= 2)) {
+                return (count % 3);
+            }
+            switch (count) {
+                case 1 : { return (1); }
+                default : { break; }
+            }
+            return (count / 2 * 4 - 1);
+        }
+        rule r inactive highFrequency priority 7 group "g" runImmediately {
+            int i = f(2.5, false);
+            while (i != 0) { i--; }
+            string s = "a" + i;
+            breakpoint;
+        }
+    "#);
+    assert_eq!(bc.recursion_limit, Some(50));
+    assert_round_trips(&bc);
+}
+
+#[test]
+fn every_instruction_round_trips() {
+    let (r1, r2) = (Operand::Reg(Reg(1)), Operand::Reg(Reg(2)));
+    let mem = Operand::Mem(Reg::BP, -3);
+    let mut bc = Bytecode {
+        consts: vec![
+            Value::Int(-7),
+            Value::Float(0.5),
+            Value::Bool(true),
+            Value::Str("msg".to_string()),
+            Value::Vec([1.0, 2.0, 3.0]),
+        ],
+        names: vec![Identifier::new("f"), Identifier::new("g")],
+        procs: vec![(0, 0)],
+        rules: vec![RuleEntry {
+            name: 0,
+            active: true,
+            run_immediately: false,
+            min_interval: 3,
+            max_interval: Some(9),
+            priority: -2,
+            group: Some("grp".to_string()),
+        }],
+        recursion_limit: None,
+        code: vec![
+            Instr::Copy { dst: r1, src: Operand::Const(4) },
+            Instr::Vec { dst: r2, x: r1, y: Operand::Const(1), z: mem },
+            Instr::Jmp { name: 1, argc: 3 },
+            Instr::Ifn { cond: Operand::Global(1), addr: 0 },
+            Instr::Goto(2),
+            Instr::Push(mem),
+            Instr::Pop(Operand::Reg(Reg::LP)),
+            Instr::Trap(3),
+            Instr::Ret,
+        ],
+    };
+    let unary = [UnOp::Neg, UnOp::Not, UnOp::Bits, UnOp::Member(2)].into_iter()
+        .chain([ConvType::Int, ConvType::Float, ConvType::Bool, ConvType::Str, ConvType::Vec].map(UnOp::Conv));
+    for op in unary {
+        bc.code.push(Instr::Unary { dst: r1, op, src: Operand::Const(0) });
+    }
+    let binary = [
+        BinOp::Add, BinOp::Sub, BinOp::Mul, BinOp::Div, BinOp::Rem,
+        BinOp::Lt, BinOp::Gt, BinOp::Le, BinOp::Ge, BinOp::Eq, BinOp::Ne,
+        BinOp::And, BinOp::Or,
+    ];
+    for op in binary {
+        bc.code.push(Instr::Binary { dst: mem, op, src1: r1, src2: Operand::Const(2) });
+    }
+    assert_round_trips(&bc);
+}
+
+#[test]
+fn spills_when_registers_run_out() {
+    let defs = (0..40).map(|i| format!("int v{i} = xsGetTime() + {i};")).collect::<String>();
+    let sum = (0..40).map(|i| format!("v{i}")).collect::<Vec<_>>().join(" + ");
+    let bc = compile_src(&format!("void main() {{ {defs} xsChatData(\"\" + ({sum})); }}"));
+
+    let spilled = bc.code.iter().any(|instr| match instr {
+        Instr::Copy { dst, .. } => matches!(dst, Operand::Mem(_, offset) if *offset >= 0),
+        _ => false,
+    });
+    assert!(spilled, "{}", bc);
+    assert!(bc.to_string().contains("alloc"), "{}", bc);
+    assert_round_trips(&bc);
+}
+
+#[test]
+fn registers_live_across_calls_are_saved() {
+    let bc = compile_src("int g(int a = 0) { return (a); } void main() { int x = g(1); int y = g(2); g(x + y); }");
+    let calls = bc.code.iter()
+        .enumerate()
+        .filter(|(_addr, instr)| matches!(instr, Instr::Jmp { name, .. } if bc.names[*name as usize].0 == "g"))
+        .map(|(addr, _instr)| addr)
+        .collect::<Vec<_>>();
+    assert_eq!(calls.len(), 3, "{}", bc);
+
+    // `x` is needed after the second call, so it is pushed before the argument and popped after
+    let call = calls[1];
+    let Instr::Push(Operand::Reg(saved)) = bc.code[call - 2] else {
+        panic!("no register is saved before the call at {}\n{}", call, bc);
+    };
+    assert!(matches!(bc.code[call - 1], Instr::Push(Operand::Const(_))), "{}", bc);
+    assert_eq!(bc.code[call + 2], Instr::Pop(Operand::Reg(saved)), "{}", bc);
+}
+
+#[test]
+fn bad_bytecode_is_rejected() {
+    let bc = compile_src("void main() { xsChatData(\"hi\"); }");
+    let bytes = encode(&bc);
+
+    assert_eq!(decode(b"nope").unwrap_err().offset, 0);
+    for len in 0..bytes.len() {
+        assert!(decode(&bytes[..len]).is_err(), "{} bytes decoded", len);
+    }
+
+    let mut extra = bytes.clone();
+    extra.push(0);
+    assert_eq!(decode(&extra).unwrap_err().offset, bytes.len());
+}
@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+use xs_check::internal::r#static::cfg::build::module_cfgs;
+use xs_check::internal::r#static::cfg::{BlockId, Cfg, Edge, Instr};
+use xs_check::internal::r#static::module_loader::ModuleLoader;
+
+/// A block by the source of its instructions, and its successors
+type Shape = (Vec<String>, Vec<(BlockId, Edge)>);
+
+/// The shape of each block of the first function in `src`
+fn shape(src: &str) -> Vec<Shape> {
+    let path = PathBuf::from("test.xs");
+    let mut loader = ModuleLoader::new(vec![]);
+    loader.set_source(&path, src.to_string());
+    let module = loader.load(&path).unwrap();
+    assert!(module.errs.is_empty(), "the script does not parse: {:?}", module.errs);
+
+    let cfgs = module_cfgs(&module.ast);
+    let cfg = &cfgs[0];
+    cfg.blocks.iter()
+        .map(|block| {
+            let instrs = block.instrs.iter()
+                .map(|instr| match instr {
+                    Instr::Step { name: (name, _), step } => {
+                        format!("{}{}", name.0, if *step > 0 { "++" } else { "--" })
+                    }
+                    instr => src[instr.span().into_range()].to_string(),
+                })
+                .collect();
+            (instrs, block.succs.clone())
+        })
+        .collect()
+}
+
+fn block(instrs: &[&str], succs: &[(BlockId, Edge)]) -> Shape {
+    (instrs.iter().map(|instr| instr.to_string()).collect(), succs.to_vec())
+}
+
+#[test]
+fn continue_in_a_for_loop_skips_the_step() {
+    assert_eq!(shape(r#"
+        void f() {
+            for (i = 0; < 3) {
+                if (i == 1) {
+                    continue;
+                }
+                xsChatData("%d", i);
+            }
+        }
+    "#), vec![
+        block(&["i = 0;"], &[(2, Edge::Jump)]),
+        block(&[], &[]),
+        // the condition is spanned from the start of the loop variable
+        block(&["i = 0; < 3"], &[(3, Edge::True), (4, Edge::False)]),
+        block(&["i == 1"], &[(6, Edge::True), (5, Edge::False)]),
+        block(&[], &[(Cfg::EXIT, Edge::Jump)]),
+        block(&["xsChatData(\"%d\", i);", "i++"], &[(2, Edge::Jump)]),
+        // the `continue` jumps straight back to the condition
+        block(&["continue;"], &[(2, Edge::Jump)]),
+        block(&[], &[(5, Edge::Jump)]),
+    ]);
+}
+
+#[test]
+fn switch_cases_do_not_fall_through() {
+    assert_eq!(shape(r#"
+        void f(int a = 0) {
+            switch (a) {
+                case 0 : { xsChatData("zero"); }
+                case 1 : { xsChatData("one"); break; }
+                default : { xsChatData("many"); }
+            }
+        }
+    "#), vec![
+        block(&["a"], &[(3, Edge::Case(0)), (4, Edge::Case(1)), (6, Edge::Default)]),
+        block(&[], &[]),
+        block(&[], &[(Cfg::EXIT, Edge::Jump)]),
+        block(&["xsChatData(\"zero\");"], &[(2, Edge::Jump)]),
+        block(&["xsChatData(\"one\");", "break;"], &[(2, Edge::Jump)]),
+        // the block after the `break` is never reached
+        block(&[], &[(2, Edge::Jump)]),
+        block(&["xsChatData(\"many\");"], &[(2, Edge::Jump)]),
+    ]);
+}
+
+#[test]
+fn switch_without_default_may_skip_every_case() {
+    assert_eq!(shape(r#"
+        void f(int a = 0) {
+            switch (a) {
+                case 0 : { xsChatData("zero"); }
+            }
+        }
+    "#), vec![
+        block(&["a"], &[(3, Edge::Case(0)), (2, Edge::Default)]),
+        block(&[], &[]),
+        block(&[], &[(Cfg::EXIT, Edge::Jump)]),
+        block(&["xsChatData(\"zero\");"], &[(2, Edge::Jump)]),
+    ]);
+}
+
+#[test]
+fn goto_jumps_forwards_and_backwards() {
+    assert_eq!(shape(r#"
+        void f() {
+            int i = 0;
+            label back;
+            i++;
+            if (i < 3) {
+                goto back;
+            }
+            goto done;
+            xsChatData("skipped");
+            label done;
+        }
+    "#), vec![
+        block(&["int i = 0;"], &[(2, Edge::Jump)]),
+        block(&[], &[]),
+        block(&["label back;", "i++;", "i < 3"], &[(4, Edge::True), (3, Edge::False)]),
+        block(&["goto done;"], &[(7, Edge::Jump)]),
+        block(&["goto back;"], &[(2, Edge::Jump)]),
+        block(&[], &[(3, Edge::Jump)]),
+        // nothing jumps to the statement after the `goto`
+        block(&["xsChatData(\"skipped\");"], &[(7, Edge::Jump)]),
+        block(&["label done;"], &[(Cfg::EXIT, Edge::Jump)]),
+    ]);
+}
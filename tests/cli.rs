@@ -0,0 +1,267 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output};
+
+/// An empty directory for the files of the test `name`
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xs-check-cli-{}-{}", process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn xs_check(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_xs-check"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+/// The files `xs-check -v` says it checks
+fn checked(output: &Output) -> Vec<String> {
+    stderr(output).lines()
+        .filter_map(|line| line.strip_prefix("Checking "))
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn directories_are_checked_recursively_in_order() {
+    let dir = temp_dir("recursive");
+    fs::create_dir_all(dir.join("scripts/ai")).unwrap();
+    fs::write(dir.join("scripts/b.xs"), "").unwrap();
+    fs::write(dir.join("scripts/a.xs"), "").unwrap();
+    fs::write(dir.join("scripts/notes.txt"), "").unwrap();
+    fs::write(dir.join("scripts/ai/c.xs"), "").unwrap();
+
+    let output = xs_check(&dir, &["-v", "scripts"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(checked(&output), vec!["scripts/a.xs", "scripts/ai/c.xs", "scripts/b.xs"]);
+}
+
+#[test]
+fn a_file_given_twice_is_checked_once() {
+    let dir = temp_dir("twice");
+    fs::create_dir_all(dir.join("scripts")).unwrap();
+    fs::write(dir.join("scripts/a.xs"), "").unwrap();
+
+    let output = xs_check(&dir, &["-v", "scripts", "scripts/a.xs", "./scripts/a.xs"]);
+
+    assert_eq!(checked(&output), vec!["scripts/a.xs"]);
+    assert!(stderr(&output).contains("in 1 file"), "{}", stderr(&output));
+}
+
+#[test]
+fn the_summary_counts_included_files() {
+    let dir = temp_dir("summary");
+    fs::write(dir.join("a.xs"), "include \"lib.xs\";").unwrap();
+    fs::write(dir.join("lib.xs"), "").unwrap();
+
+    let output = xs_check(&dir, &["a.xs"]);
+
+    assert!(stderr(&output).contains("in 2 files"), "{}", stderr(&output));
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_directories_are_not_followed() {
+    let dir = temp_dir("symlinks");
+    fs::create_dir_all(dir.join("scripts/d")).unwrap();
+    fs::write(dir.join("scripts/a.xs"), "").unwrap();
+    std::os::unix::fs::symlink("..", dir.join("scripts/d/up")).unwrap();
+    std::os::unix::fs::symlink("../a.xs", dir.join("scripts/d/link.xs")).unwrap();
+
+    let output = xs_check(&dir, &["-v", "scripts"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(checked(&output), vec!["scripts/a.xs"]);
+}
+
+#[test]
+fn fmt_check_exit_codes() {
+    let dir = temp_dir("fmt_check");
+    fs::write(dir.join("formatted.xs"), "int x = 1;\n").unwrap();
+    fs::write(dir.join("unformatted.xs"), "int x=1;").unwrap();
+    fs::write(dir.join("invalid.xs"), "int x = ;").unwrap();
+
+    let formatted = xs_check(&dir, &["fmt", "--check", "formatted.xs"]);
+    assert_eq!(formatted.status.code(), Some(0));
+    assert!(formatted.stdout.is_empty());
+
+    let unformatted = xs_check(&dir, &["fmt", "--check", "formatted.xs", "unformatted.xs"]);
+    assert_eq!(unformatted.status.code(), Some(1));
+    assert_eq!(String::from_utf8(unformatted.stdout).unwrap(), "unformatted.xs\n");
+    assert_eq!(fs::read_to_string(dir.join("unformatted.xs")).unwrap(), "int x=1;");
+
+    assert_eq!(xs_check(&dir, &["fmt", "--check", "invalid.xs"]).status.code(), Some(1));
+    assert_eq!(xs_check(&dir, &["fmt", "--check", "missing.xs"]).status.code(), Some(2));
+
+    assert_eq!(xs_check(&dir, &["fmt", "unformatted.xs"]).status.code(), Some(0));
+    assert_eq!(fs::read_to_string(dir.join("unformatted.xs")).unwrap(), "int x = 1;\n");
+}
+
+#[test]
+fn run_stops_a_script_which_never_finishes() {
+    let dir = temp_dir("max_steps");
+    // `continue` skips the step, so `i` stays at 1
+    let src = "void main() { for (i = 0; < 3) { if (i == 1) { continue; } } }";
+    fs::write(dir.join("hang.xs"), src).unwrap();
+
+    for vm in [None, Some("--vm")] {
+        let args = ["run", "--max-steps", "1000", "hang.xs"].into_iter().chain(vm).collect::<Vec<_>>();
+        let output = xs_check(&dir, &args);
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        assert!(stderr(&output).contains("Stopped after 1000 steps"), "{}", stderr(&output));
+    }
+}
+
+#[test]
+fn simulate_is_limited_to_the_game_clock() {
+    let dir = temp_dir("simulate");
+    fs::write(dir.join("r.xs"), "rule a active minInterval 3000000 { }").unwrap();
+
+    let output = xs_check(&dir, &["run", "--simulate", "1", "r.xs"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // the clock is an `int` of milliseconds
+    let output = xs_check(&dir, &["run", "--simulate", "2147484", "r.xs"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("2147484 is not in 0..=2147483"), "{}", stderr(&output));
+}
+
+#[test]
+fn emit_cfg() {
+    let dir = temp_dir("emit_cfg");
+    fs::write(dir.join("a.xs"), "\
+int f(int n = 0) {
+    for (i = 0; < n) {
+        if (i == 1) {
+            continue;
+        }
+        return (i);
+    }
+    return (-1);
+}
+").unwrap();
+
+    let output = xs_check(&dir, &["--emit", "cfg", "a.xs"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), r#"digraph "f" {
+    node [shape=box, fontname=monospace];
+    b0 [label="i = 0;\l"];
+    b1 [label="exit"];
+    b2 [label="i = 0; < n\l"];
+    b3 [label="i == 1\l"];
+    b4 [label="return (-1);\l"];
+    b5 [label="return (i);\l"];
+    b6 [label="continue;\l"];
+    b8 [label="i++\l"];
+    b0 -> b2;
+    b2 -> b3 [label="true"];
+    b2 -> b4 [label="false"];
+    b3 -> b6 [label="true"];
+    b3 -> b5 [label="false"];
+    b4 -> b1;
+    b5 -> b1;
+    b6 -> b2;
+    b8 -> b2;
+}
+"#);
+}
+
+#[test]
+fn emit_pa() {
+    let dir = temp_dir("emit_pa");
+    fs::write(dir.join("a.xs"), "\
+int add(int a = 1, int b = 2) {
+    return (a + b);
+}
+void main() {
+    int n = add(5);
+    switch (n) {
+        case 0 : { n = 1; }
+        default : { break; }
+    }
+    for (i = 0; < 3) {
+        if (i == 1) {
+            continue;
+        }
+        n = n + i;
+    }
+    label done;
+    if (n > 100) {
+        goto done;
+    }
+}
+").unwrap();
+
+    let output = xs_check(&dir, &["--emit", "pa", "a.xs"]);
+
+    // the arguments are pushed last first, with the defaults filled in, and the callee saves the
+    // link pointer. Neither switch case falls through, and the `continue` skips the step at 40
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), r#" 1: add:
+ 2:     push r_lp
+ 3:     push r_bp
+ 4:     r_bp <- r_sp
+ 5:     a <- (int) [r_bp-3]
+ 6:     b <- (int) [r_bp-4]
+ 7:     %1 <- a + b
+ 8:     r_ret <- (int) %1
+ 9:     goto 11
+10:     r_ret <- 0
+11:     r_sp <- r_bp
+12:     pop r_bp
+13:     pop r_lp
+14:     ret
+15: main:
+16:     push r_lp
+17:     push r_bp
+18:     r_bp <- r_sp
+19:     push 2
+20:     push 5
+21:     jmp add, 2
+22:     dealloc 2
+23:     %1 <- r_ret
+24:     n <- (int) %1
+25:     %2 <- (int) n
+26:     %3 <- %2 != 0
+27:     ifn %3 goto 29
+28:     goto 31
+29:     n <- 1
+30:     goto 32
+31:     goto 32
+32:     i%4 <- 0
+33:     %5 <- i%4 < 3
+34:     ifn %5 goto 42
+35:     %6 <- i%4 == 1
+36:     ifn %6 goto 38
+37:     goto 33
+38:     %7 <- n + i%4
+39:     n <- (int) %7
+40:     i%4 <- i%4 + 1
+41:     goto 33
+42:     %8 <- n > 100
+43:     ifn %8 goto 45
+44:     goto 42
+45:     r_sp <- r_bp
+46:     pop r_bp
+47:     pop r_lp
+48:     ret
+49: <globals>:
+50:     push r_lp
+51:     push r_bp
+52:     r_bp <- r_sp
+53:     r_sp <- r_bp
+54:     pop r_bp
+55:     pop r_lp
+56:     ret
+"#);
+}
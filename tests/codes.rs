@@ -0,0 +1,43 @@
+use std::collections::HashSet;
+use xs_check::internal::r#static::xs_error::{Code, Severity};
+
+#[test]
+fn ids_are_unique() {
+    let mut seen = HashSet::new();
+    for code in Code::ALL {
+        assert!(seen.insert(code.id()), "`{}` is used by more than one code", code.id());
+    }
+}
+
+#[test]
+fn ids_round_trip() {
+    for code in Code::ALL {
+        assert_eq!(Code::from_id(code.id()), Some(*code));
+        assert_eq!(code.to_string(), code.id());
+    }
+    assert_eq!(Code::from_id("XS0000"), None);
+    assert_eq!(Code::from_id("xs0001"), None);
+}
+
+#[test]
+fn errors_and_warnings_are_numbered_separately() {
+    for code in Code::ALL {
+        let prefix = match code.severity() {
+            Severity::Error => "XS0",
+            Severity::Warning => "XS1",
+        };
+        assert!(code.id().starts_with(prefix), "`{}` is numbered as the wrong severity", code.id());
+    }
+}
+
+#[test]
+fn ids_are_consecutive() {
+    for (prefix, severity) in [("XS0", Severity::Error), ("XS1", Severity::Warning)] {
+        let mut nums = Code::ALL.iter()
+            .filter(|code| code.severity() == severity)
+            .map(|code| code.id()[prefix.len()..].parse::<u32>().unwrap())
+            .collect::<Vec<u32>>();
+        nums.sort();
+        assert_eq!(nums, (1..=nums.len() as u32).collect::<Vec<u32>>(), "{} codes have gaps", prefix);
+    }
+}
@@ -0,0 +1,131 @@
+use xs_check::internal::parsing::ast::astree::ASTreeNode;
+use xs_check::internal::parsing::ast::comment::{AttachedComments, Comment};
+use xs_check::internal::parsing::span::Spanned;
+use xs_check::internal::r#static::module_loader::{parse_src, Parsed};
+
+fn parse(src: &str) -> (Vec<Spanned<ASTreeNode>>, AttachedComments) {
+    let Parsed { ast, attached, errs, .. } = parse_src(src);
+    assert!(errs.is_empty(), "the script does not parse: {:?}", errs);
+    (ast, attached)
+}
+
+/// The doc comment of each top level definition
+fn docs(src: &str) -> Vec<Option<String>> {
+    parse(src).0.into_iter()
+        .map(|(stmt, _span)| match stmt {
+            ASTreeNode::FnDef { doc, .. }
+            | ASTreeNode::RuleDef { doc, .. }
+            | ASTreeNode::VarDef { doc, .. } => doc.map(|(text, _span)| text),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The text of comments
+fn texts(comments: &[Spanned<Comment>]) -> Vec<&str> {
+    comments.iter().map(|(cmt, _span)| cmt.0.as_str()).collect()
+}
+
+#[test]
+fn doc_comments() {
+    let is_doc = |cmt: &str| Comment::new(cmt).is_doc();
+    assert!(is_doc("/// doc"));
+    assert!(is_doc("///"));
+    assert!(is_doc("/** doc */"));
+    assert!(!is_doc("// regular"));
+    assert!(!is_doc("//// separator"));
+    assert!(!is_doc("/* regular */"));
+    assert!(!is_doc("/*** separator */"));
+    assert!(!is_doc("/**/"));
+}
+
+#[test]
+fn doc_text_strips_the_markers() {
+    let doc_text = |cmt: &str| Comment::new(cmt).doc_text();
+    assert_eq!(doc_text("/// Halves `x`  "), "Halves `x`");
+    assert_eq!(doc_text("///no space"), "no space");
+    assert_eq!(doc_text("/** one line */"), "one line");
+    assert_eq!(doc_text("/**\n * first\n *   indented\n *\n * last\n */"), "first\n  indented\n\nlast");
+}
+
+#[test]
+fn comments_lead_trail_or_are_inside_a_statement() {
+    let src = "\
+// leading
+int x = 1; // trailing
+void f() {
+    // nested
+    if (x > 0 /* inner */) {
+        x = 2; /* nested trailing */
+    }
+    // after the last statement
+}
+";
+    let (ast, attached) = parse(src);
+
+    let x = &attached[&ast[0].1];
+    assert_eq!(texts(&x.leading), vec!["// leading"]);
+    assert_eq!(x.trailing.as_ref().map(|(cmt, _span)| cmt.0.as_str()), Some("// trailing"));
+
+    let ASTreeNode::FnDef { body: (body, _), .. } = &ast[1].0 else {
+        panic!("`f` is not a function");
+    };
+    let if_ = &attached[&body.0[0].1];
+    assert_eq!(texts(&if_.leading), vec!["// nested"]);
+    assert_eq!(texts(&if_.inner), vec!["/* inner */"]);
+
+    let ASTreeNode::IfElse { consequent: (consequent, _), .. } = &body.0[0].0 else {
+        panic!("the first statement of `f` is not an `if`");
+    };
+    let assign = &attached[&consequent.0[0].1];
+    assert_eq!(assign.trailing.as_ref().map(|(cmt, _span)| cmt.0.as_str()), Some("/* nested trailing */"));
+
+    let all = attached.values()
+        .flat_map(|cmts| cmts.leading.iter().chain(&cmts.trailing).chain(&cmts.inner))
+        .collect::<Vec<_>>();
+    assert_eq!(all.len(), 5, "the comment after the last statement is not attached");
+}
+
+#[test]
+fn docs_are_the_doc_comments_directly_before_a_definition() {
+    assert_eq!(docs("\
+/// Line one
+/// line two
+int a = 1;
+/** Block */
+void f() { }
+"), vec![Some("Line one\nline two".to_string()), Some("Block".to_string())]);
+}
+
+#[test]
+fn regular_comments_are_not_docs() {
+    assert_eq!(docs("\
+//// separator
+int a = 1;
+/**/
+int b = 1;
+// regular
+int c = 1;
+"), vec![None, None, None]);
+}
+
+#[test]
+fn a_regular_comment_breaks_a_docstring() {
+    assert_eq!(docs("\
+/// dropped
+// regular
+/// kept
+int a = 1;
+/// dropped too
+// regular
+int b = 1;
+"), vec![Some("kept".to_string()), None]);
+}
+
+#[test]
+fn trailing_doc_comments_do_not_document_the_next_definition() {
+    assert_eq!(docs("\
+int a = 1; /// about a
+int b = 2;
+"), vec![None, None]);
+}
@@ -0,0 +1,176 @@
+use std::path::Path;
+use xs_check::api::{Code, Severity};
+use xs_check::internal::r#static::module_loader::{xs_tc_module, ModuleLoader};
+use xs_check::internal::r#static::prelude::{prelude, PreludeVersion};
+use xs_check::internal::r#static::type_check::Groups;
+use xs_check::{check_source, Diagnostic, Options};
+
+/// The IntOverflow, ConstCondition, DuplicateCase and DivByZero diagnostics reported for `src`
+fn consts(src: &str) -> Vec<Diagnostic> {
+    check_source(src, &Options::default()).diagnostics.into_iter()
+        .filter(|diag| matches!(
+            diag.code,
+            Code::IntOverflow | Code::ConstCondition | Code::DuplicateCase | Code::DivByZero
+        ))
+        .collect()
+}
+
+/// Asserts the code, message and line of each diagnostic reported for `src`
+fn assert_consts(src: &str, expected: &[(Code, &str, usize)]) {
+    let diags = consts(src);
+    let actual = diags.iter()
+        .map(|diag| (diag.code, diag.message.as_str(), diag.location.start.line))
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected, "{}", src);
+}
+
+#[test]
+fn int_overflow_wraps_around() {
+    let src = "\
+const int MAX = 999999999;
+int f() {
+    int a = 999999999 * 3;
+    int b = MAX * 2 + MAX;
+    int c = (0 - MAX) - MAX - MAX;
+    int d = MAX + 0;
+    return (a + b + c + d);
+}
+";
+    let msg = "The result of this operation does not fit in an `int`";
+    assert_consts(src, &[
+        (Code::IntOverflow, msg, 3),
+        (Code::IntOverflow, "The result of this operation is too large for an `int` literal", 4),
+        (Code::IntOverflow, msg, 4),
+        (Code::IntOverflow, "The result of this operation is too large for an `int` literal", 5),
+        (Code::IntOverflow, msg, 5),
+    ]);
+
+    let diags = consts(src);
+    assert_eq!(&src[diags[0].location.span.clone()], "999999999 * 3");
+    let note = |val| format!("`int`s are 32 bits wide and wrap around, so this evaluates to `{}`", val);
+    assert_eq!(diags[0].notes, vec![note(-1294967299)]);
+    assert_eq!(diags[2].notes, vec![note(-1294967299)]);
+    assert_eq!(diags[4].notes, vec![note(1294967299)]);
+
+    // the literals are all valid
+    let errs = check_source(src, &Options::default()).diagnostics.into_iter()
+        .filter(|diag| diag.severity == Severity::Error)
+        .collect::<Vec<_>>();
+    assert!(errs.is_empty(), "{:?}", errs);
+}
+
+#[test]
+fn results_too_large_for_a_literal_are_reported() {
+    let src = "\
+int f() {
+    int a = 1000000 * 1000;
+    int b = 1000000 * 1000 + 1;
+    int c = 0 - 999999999 - 1;
+    return (a + b + c);
+}
+";
+    let msg = "The result of this operation is too large for an `int` literal";
+    assert_consts(src, &[
+        (Code::IntOverflow, msg, 2),
+        (Code::IntOverflow, msg, 3),
+        (Code::IntOverflow, msg, 4),
+    ]);
+
+    let diags = consts(src);
+    assert_eq!(&src[diags[1].location.span.clone()], "1000000 * 1000");
+    assert_eq!(diags[0].notes, vec![
+        "`int` literals cannot have more than 9 digits, so `1000000000` cannot be written in the script",
+    ]);
+}
+
+#[test]
+fn float_arithmetic_does_not_overflow() {
+    assert_consts("float f() {\n    return (2147483647.0 * 2);\n}\n", &[]);
+}
+
+#[test]
+fn conditions_which_are_always_the_same() {
+    assert_consts("\
+const bool DEBUG = false;
+const int LEVEL = 3;
+void f() {
+    if (1 < 2) {
+        xsChatData(\"always\");
+    }
+    while (LEVEL > 5) {
+        xsChatData(\"never\");
+    }
+    if (DEBUG) {
+        xsChatData(\"a switch in the script\");
+    }
+    while (true) {
+        break;
+    }
+}
+", &[
+        (Code::ConstCondition, "This condition is always `true`", 4),
+        (Code::ConstCondition, "This condition is always `false`", 7),
+    ]);
+}
+
+#[test]
+fn cases_which_are_equal_after_folding() {
+    let src = "\
+const int TWO = 2;
+void f(int a = 0) {
+    switch (a) {
+        case 2 : { xsChatData(\"two\"); }
+        case 1 + 1 : { xsChatData(\"also two\"); }
+        case TWO : { xsChatData(\"two again\"); }
+        case 3 : { xsChatData(\"three\"); }
+    }
+}
+";
+    assert_consts(src, &[
+        (Code::DuplicateCase, "Only the first case will run on a match", 5),
+        (Code::DuplicateCase, "Only the first case will run on a match", 6),
+    ]);
+
+    let diags = consts(src);
+    assert_eq!(diags[0].related[0].0, "first case with this value");
+    assert_eq!(diags[0].related[0].1.start.line, 4);
+    assert_eq!(diags[0].notes, vec!["Both cases evaluate to `2`"]);
+}
+
+#[test]
+fn consts_are_scoped_to_the_function_which_defines_them() {
+    assert_consts("\
+const int ZERO = 0;
+int f(int ZERO = 1) {
+    return (10 / ZERO);
+}
+int g() {
+    const int ONE = 1;
+    return (10 / (ONE - 1));
+}
+int h(int ONE = 0) {
+    return (10 / ONE);
+}
+", &[(Code::DivByZero, "This divides by zero", 7)]);
+}
+
+#[test]
+fn consts_of_included_files_are_folded() {
+    let mut loader = ModuleLoader::new(vec![]);
+    let main = "include \"lib.xs\";\nint f() { return (10 / ZERO); }";
+    loader.set_source(Path::new("main.xs"), main.to_string());
+    loader.set_source(Path::new("lib.xs"), "const int ZERO = 0;".to_string());
+
+    let mut errs = vec![];
+    let mut type_env = prelude(PreludeVersion::LATEST);
+    xs_tc_module(Path::new("main.xs"), &mut loader, &mut type_env, &mut Groups::new(), &mut errs).unwrap();
+
+    let actual = errs.iter().map(|err| (err.code, err.msg.as_str())).collect::<Vec<_>>();
+    assert_eq!(actual, vec![(Code::DivByZero, "This divides by zero")]);
+}
+
+#[test]
+fn division_by_zero_is_reported_once() {
+    // both the sign analysis and the folding find this division
+    assert_consts("int f() {\n    return (10 / 0);\n}\n", &[(Code::DivByZero, "This divides by zero", 2)]);
+}
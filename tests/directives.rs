@@ -0,0 +1,91 @@
+use xs_check::api::Code;
+use xs_check::internal::parsing::ast::astree::ASTreeNode;
+use xs_check::internal::r#static::module_loader::{parse_src, Parsed};
+use xs_check::{check_source, Options};
+
+/// The AST of `src`, which must parse
+fn parse(src: &str) -> Vec<ASTreeNode> {
+    let Parsed { ast, errs, .. } = parse_src(src);
+    assert!(errs.is_empty(), "the script does not parse: {:?}", errs);
+    ast.into_iter().map(|(stmt, _span)| stmt).collect()
+}
+
+/// The code and message of each diagnostic reported for `src`
+fn diags(src: &str) -> Vec<(Code, String)> {
+    check_source(src, &Options::default()).diagnostics.into_iter()
+        .map(|diag| (diag.code, diag.message))
+        .collect()
+}
+
+#[test]
+fn limits_are_parsed() {
+    let ast = parse("infiniteLoopLimit 100;\ninfiniteRecursionLimit 20;");
+    assert!(matches!(ast[0], ASTreeNode::InfiniteLoopLimit((100, _))));
+    assert!(matches!(ast[1], ASTreeNode::InfiniteRecursionLimit((20, _))));
+
+    assert!(!parse_src("infiniteLoopLimit;").errs.is_empty());
+    assert!(!parse_src("infiniteLoopLimit 1.5;").errs.is_empty());
+    assert!(!parse_src("infiniteLoopLimit 100").errs.is_empty());
+}
+
+#[test]
+fn limits_must_be_positive_and_at_the_top_level() {
+    assert_eq!(diags("infiniteLoopLimit 100;\ninfiniteRecursionLimit 20;"), vec![]);
+    assert_eq!(diags("infiniteLoopLimit 0;"), vec![
+        (Code::NonPositiveLimit, "`infiniteLoopLimit` must be at least 1".to_string()),
+    ]);
+    // a limit which is not positive parses, so it is reported as too small
+    for (src, limit) in [
+        ("infiniteLoopLimit -5;", -5),
+        ("infiniteRecursionLimit 0 - 1;", -1),
+        ("infiniteLoopLimit 2 - 2;", 0),
+    ] {
+        let val = match parse(src)[0] {
+            ASTreeNode::InfiniteLoopLimit((val, _)) | ASTreeNode::InfiniteRecursionLimit((val, _)) => val,
+            _ => panic!("`{}` is not a limit", src),
+        };
+        assert_eq!(val, limit, "{}", src);
+        let codes = diags(src).into_iter().map(|(code, _msg)| code).collect::<Vec<_>>();
+        assert_eq!(codes, vec![Code::NonPositiveLimit], "{}", src);
+    }
+    assert_eq!(diags("void f() {\n    infiniteRecursionLimit 5;\n}"), vec![
+        (Code::TopLevelOnly, "`infiniteRecursionLimit` may only appear at the top level".to_string()),
+    ]);
+}
+
+#[test]
+fn function_modifiers_are_parsed_in_any_order() {
+    for src in ["export mutable void f() { }", "mutable export void f() { }"] {
+        let ast = parse(src);
+        assert!(
+            matches!(ast[0], ASTreeNode::FnDef { is_extern: false, is_export: true, is_mutable: true, .. }),
+            "{}", src,
+        );
+    }
+    let ast = parse("extern void f() { }");
+    assert!(matches!(ast[0], ASTreeNode::FnDef { is_extern: true, is_export: false, is_mutable: false, .. }));
+}
+
+#[test]
+fn function_modifiers_may_only_be_given_once() {
+    let Parsed { ast, errs, .. } = parse_src("mutable mutable export int f() { return (1); }");
+    let errs = errs.iter().map(|err| (err.msg.as_str(), err.span.into_range())).collect::<Vec<_>>();
+    assert_eq!(errs, vec![("`mutable` may only be given once", 8..15)]);
+    // the definition is still parsed, so the rest of the file is checked
+    assert!(matches!(ast[0].0, ASTreeNode::FnDef { is_export: true, is_mutable: true, .. }));
+}
+
+#[test]
+fn functions_cannot_be_both_export_and_extern() {
+    let Parsed { errs, .. } = parse_src("extern export void f() { }\nexport void g() { }");
+    let errs = errs.iter().map(|err| (err.msg.as_str(), err.span.into_range())).collect::<Vec<_>>();
+    assert_eq!(errs, vec![("A function cannot be both `export` and `extern`", 7..13)]);
+}
+
+#[test]
+fn parameters_of_extern_and_export_functions_may_go_unread() {
+    let unused = |src| diags(src).into_iter().filter(|(code, _msg)| *code == Code::UnusedParam).count();
+    assert_eq!(unused("void f(int a = 0) { }"), 1);
+    assert_eq!(unused("extern void f(int a = 0) { }"), 0);
+    assert_eq!(unused("export void f(int a = 0) { }"), 0);
+}
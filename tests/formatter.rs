@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+use chumsky::Parser;
+use xs_check::internal::formatter::{format_module, BraceStyle, FmtConfig, Indent};
+use xs_check::internal::parsing::ast::comment::Comment;
+use xs_check::internal::parsing::ast::identifier::Identifier;
+use xs_check::internal::parsing::lexer::lexer;
+use xs_check::internal::parsing::lexer::token::Token;
+use xs_check::internal::r#static::module_loader::ModuleLoader;
+
+fn format_with(src: &str, cfg: &FmtConfig) -> String {
+    let path = PathBuf::from("test.xs");
+    let mut loader = ModuleLoader::new(vec![]);
+    loader.set_source(&path, src.to_string());
+    let module = loader.load(&path).unwrap();
+    assert!(module.errs.is_empty(), "the script does not parse: {:?}", module.errs);
+
+    format_module(&module, cfg)
+}
+
+fn format(src: &str) -> String {
+    format_with(src, &FmtConfig::default())
+}
+
+fn tokens(src: &str) -> Vec<Token> {
+    lexer().parse(src).into_result().unwrap().into_iter().map(|(tok, _span)| tok).collect()
+}
+
+#[test]
+fn formatting_test_xs_is_idempotent() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_xs");
+    let mut num_formatted = 0;
+    for entry in dir.read_dir().unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "xs") {
+            continue;
+        }
+        let src = std::fs::read_to_string(&path).unwrap();
+        let mut loader = ModuleLoader::new(vec![]);
+        if !loader.load(&path).unwrap().errs.is_empty() {
+            continue;
+        }
+
+        let once = format(&src);
+        assert_eq!(format(&once), once, "formatting `{}` twice changes it", path.display());
+        num_formatted += 1;
+    }
+    assert!(num_formatted > 0);
+}
+
+#[test]
+fn layout() {
+    let src = "int f(int a=1,float b=2.0){if(a>0){return(a);}else if(a<0){return(0-a);}else{return(0);}}";
+    assert_eq!(format(src), "\
+int f(int a = 1, float b = 2.0) {
+    if (a > 0) {
+        return (a);
+    } else if (a < 0) {
+        return (0 - a);
+    } else {
+        return (0);
+    }
+}
+");
+
+    let cfg = FmtConfig { indent: Indent::Tabs, brace_style: BraceStyle::NextLine, space_after_keyword: false };
+    assert_eq!(format_with("void main() { while (true) { break; } }", &cfg), "\
+void main()
+{
+\twhile(true)
+\t{
+\t\tbreak;
+\t}
+}
+");
+}
+
+#[test]
+fn comments_are_kept() {
+    let src = "\
+// leading
+/// Doc for x
+int x = 1;   // trailing
+
+/* block */ int y = 1 /* inner */ + 2;
+void main() {
+    /* multi
+       line */
+    int z = x;
+
+
+    // before the closing brace
+}
+// at the end
+";
+    assert_eq!(format(src), "\
+// leading
+/// Doc for x
+int x = 1; // trailing
+
+/* block */
+int y = 1 + 2; /* inner */
+void main() {
+    /* multi
+       line */
+    int z = x;
+
+    // before the closing brace
+}
+// at the end
+");
+}
+
+#[test]
+fn modulo_is_lexed_and_kept() {
+    assert!(tokens("7 % 3").contains(&Token::PCent));
+    assert!(!tokens("7 % 3").contains(&Token::FSlash));
+    assert_eq!(format("int x = 7%3;"), "int x = 7 % 3;\n");
+}
+
+#[test]
+fn block_comments_end_at_their_closing_delimiter() {
+    assert_eq!(tokens("/* a */ b"), vec![
+        Token::Comment(Comment::new("/* a */")),
+        Token::Identifier(Identifier::new("b")),
+    ]);
+    assert_eq!(format("/* a */ int b = 1; /* c */\n"), "/* a */\nint b = 1; /* c */\n");
+}
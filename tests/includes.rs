@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+use xs_check::internal::r#static::module_loader::{xs_tc_module, ModuleLoader};
+use xs_check::internal::r#static::prelude::{prelude, PreludeVersion};
+use xs_check::internal::r#static::type_check::Groups;
+use xs_check::internal::r#static::xs_error::{Code, XSError};
+
+/// A loader which reads `files` from memory instead of the disk
+fn loader(search_roots: Vec<PathBuf>, files: &[(&str, &str)]) -> ModuleLoader {
+    let mut loader = ModuleLoader::new(search_roots);
+    for (path, src) in files {
+        loader.set_source(Path::new(path), src.to_string());
+    }
+    loader
+}
+
+fn check(loader: &mut ModuleLoader, path: &str) -> Vec<XSError> {
+    let mut errs = vec![];
+    let mut type_env = prelude(PreludeVersion::LATEST);
+    xs_tc_module(Path::new(path), loader, &mut type_env, &mut Groups::new(), &mut errs).unwrap();
+    errs
+}
+
+/// Asserts the code, message and file name of each error
+fn assert_errs(loader: &ModuleLoader, errs: &[XSError], expected: &[(Code, &str, &str)]) {
+    let actual = errs.iter()
+        .map(|err| {
+            let path = &loader.module(err.file_id).path;
+            (err.code, err.msg.as_str(), path.file_name().unwrap().to_str().unwrap())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn included_definitions_are_visible_to_the_includer() {
+    let mut loader = loader(vec![], &[
+        ("main.xs", "include \"lib.xs\";\nint f() { return (g() + LIMIT); }"),
+        ("lib.xs", "const int LIMIT = 3;\nint g() { return (1); }"),
+    ]);
+    let errs = check(&mut loader, "main.xs");
+
+    assert_errs(&loader, &errs, &[]);
+    assert_eq!(loader.modules().count(), 2);
+}
+
+#[test]
+fn missing_include_is_reported_at_the_include() {
+    let mut loader = loader(vec![], &[
+        ("main.xs", "include \"missing.xs\";\nint f() { return (x); }"),
+    ]);
+    let errs = check(&mut loader, "main.xs");
+
+    assert_errs(&loader, &errs, &[
+        (Code::IncludeNotFound, "Cannot find included file `missing.xs`", "main.xs"),
+        (Code::UndefinedName, "Undefined name `x`", "main.xs"),
+    ]);
+    assert_eq!(errs[0].span.into_range(), 8..20);
+}
+
+#[test]
+fn missing_include_of_an_included_file_is_reported_in_that_file() {
+    let mut loader = loader(vec![], &[
+        ("main.xs", "include \"lib.xs\";"),
+        ("lib.xs", "include \"gone.xs\";"),
+    ]);
+    let errs = check(&mut loader, "main.xs");
+
+    assert_errs(&loader, &errs, &[
+        (Code::IncludeNotFound, "Cannot find included file `gone.xs`", "lib.xs"),
+    ]);
+}
+
+#[test]
+fn include_cycle_is_reported_at_the_include_closing_it() {
+    let mut loader = loader(vec![], &[
+        ("a.xs", "include \"b.xs\";\nint fa() { return (fb()); }"),
+        ("b.xs", "include \"a.xs\";\nint fb() { return (1); }"),
+    ]);
+    let errs = check(&mut loader, "a.xs");
+
+    assert_errs(&loader, &errs, &[
+        (Code::IncludeCycle, "Include cycle detected: a.xs -> b.xs -> a.xs", "b.xs"),
+    ]);
+}
+
+#[test]
+fn file_including_itself_is_a_cycle() {
+    let mut loader = loader(vec![], &[
+        ("a.xs", "include \"a.xs\";"),
+    ]);
+    let errs = check(&mut loader, "a.xs");
+
+    assert_errs(&loader, &errs, &[
+        (Code::IncludeCycle, "Include cycle detected: a.xs -> a.xs", "a.xs"),
+    ]);
+}
+
+#[test]
+fn file_included_twice_is_checked_once() {
+    let mut loader = loader(vec![], &[
+        ("main.xs", "include \"b.xs\";\ninclude \"c.xs\";"),
+        ("b.xs", "include \"common.xs\";"),
+        ("c.xs", "include \"common.xs\";"),
+        ("common.xs", "int f() { return (x); }"),
+    ]);
+    let errs = check(&mut loader, "main.xs");
+
+    // no cycle is reported, and the definition of `f` does not clash with itself
+    assert_errs(&loader, &errs, &[
+        (Code::UndefinedName, "Undefined name `x`", "common.xs"),
+    ]);
+}
+
+#[test]
+fn includes_are_resolved_against_the_search_roots() {
+    let mut loader = loader(vec![PathBuf::from("lib")], &[
+        ("main.xs", "include \"util.xs\";\nint f() { return (g()); }"),
+        ("lib/util.xs", "int g() { return (1); }"),
+    ]);
+    let errs = check(&mut loader, "main.xs");
+
+    assert_errs(&loader, &errs, &[]);
+}
+
+#[test]
+fn include_paths_are_unescaped() {
+    let mut loader = loader(vec![], &[
+        ("main.xs", "include \"dir\\\\lib \\\"1\\\".xs\";\nint f() { return (g()); }"),
+        ("dir\\lib \"1\".xs", "int g() { return (1); }"),
+    ]);
+    let errs = check(&mut loader, "main.xs");
+
+    assert_errs(&loader, &errs, &[]);
+}
+
+#[test]
+fn an_included_file_which_cannot_be_read_is_reported_with_the_reason() {
+    let dir = std::env::temp_dir().join(format!("xs-check-includes-{}-unreadable", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    // not UTF-8, so the file is found but cannot be read as a script
+    std::fs::write(dir.join("lib.xs"), [0xff, 0xfe]).unwrap();
+    let mut loader = loader(vec![], &[]);
+    loader.set_source(&dir.join("main.xs"), "include \"lib.xs\";".to_string());
+
+    let errs = check(&mut loader, dir.join("main.xs").to_str().unwrap());
+
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].code, Code::IncludeUnreadable);
+    assert!(errs[0].msg.starts_with("Cannot read included file `lib.xs`: "), "{}", errs[0].msg);
+}
@@ -0,0 +1,159 @@
+use std::path::PathBuf;
+use xs_check::internal::dynamic::interpreter::Interpreter;
+use xs_check::internal::r#static::module_loader::ModuleLoader;
+use xs_check::internal::r#static::prelude::PreludeVersion;
+
+/// What `main` printed, and the error the script stopped with, if any
+fn run_with(src: &str, max_steps: Option<u64>) -> (String, Option<String>) {
+    let path = PathBuf::from("test.xs");
+    let mut loader = ModuleLoader::new(vec![]);
+    loader.set_source(&path, src.to_string());
+    let module = loader.load(&path).unwrap();
+    assert!(module.errs.is_empty(), "the script does not parse: {:?}", module.errs);
+
+    let mut out = vec![];
+    let mut interpreter = Interpreter::new(PreludeVersion::LATEST, &mut out);
+    interpreter.max_steps = max_steps;
+    let result = interpreter.load(module, &mut loader)
+        .and_then(|_| interpreter.call("main", vec![]).map(|_| ()));
+    let err = result.err().map(|err| err.msg);
+
+    (String::from_utf8(out).unwrap(), err)
+}
+
+fn run(src: &str) -> String {
+    let (out, err) = run_with(src, None);
+    assert_eq!(err, None, "the script was stopped");
+    out
+}
+
+#[test]
+fn arithmetic_takes_the_type_of_the_first_operand() {
+    assert_eq!(run(r#"
+        void main() {
+            xsChatData("" + (1 + 2.5));
+            xsChatData("" + (7 / 2.0));
+            xsChatData("" + (2.5 + 1));
+            int i = 3.9;
+            xsChatData("" + i);
+        }
+    "#), "3\n3\n3.500000\n3\n");
+}
+
+#[test]
+fn ints_are_not_promoted_to_float_params() {
+    // the bits of the int are reinterpreted as a float instead of being converted
+    assert_eq!(run(r#"
+        float id(float x = 0.0) {
+            return (x);
+        }
+        void main() {
+            xsChatData("" + id(1.0));
+            xsChatData("" + (id(1) == 1.0));
+            xsChatData("" + (id(1) > 0.0));
+            xsChatData("" + (id(1) < 0.000001));
+        }
+    "#), "1.000000\nfalse\ntrue\ntrue\n");
+}
+
+#[test]
+fn goto_jumps_forwards_and_backwards() {
+    assert_eq!(run(r#"
+        void main() {
+            int i = 0;
+            label again;
+            i++;
+            if (i < 3) {
+                goto again;
+            }
+            goto done;
+            xsChatData("skipped");
+            label done;
+            xsChatData("%d", i);
+        }
+    "#), "3\n");
+}
+
+#[test]
+fn switch_runs_one_case_without_falling_through() {
+    assert_eq!(run(r#"
+        void main() {
+            for (i = 0; < 4) {
+                switch (i) {
+                    case 0 : { xsChatData("zero"); }
+                    case 1 : { xsChatData("one"); break; }
+                    case 2 : { xsChatData("two"); }
+                    default : { xsChatData("many"); }
+                }
+            }
+            switch (9) {
+                case 0 : { xsChatData("not run"); }
+            }
+        }
+    "#), "zero\none\ntwo\nmany\n");
+}
+
+#[test]
+fn a_script_which_never_finishes_is_stopped() {
+    // `continue` skips the step, so `i` stays at 1
+    let src = r#"
+        void main() {
+            for (i = 0; < 3) {
+                if (i == 1) {
+                    continue;
+                }
+                xsChatData("%d", i);
+            }
+        }
+    "#;
+    let (out, err) = run_with(src, Some(10_000));
+    assert_eq!(out, "0\n");
+    assert_eq!(err.as_deref(), Some("Stopped after 10000 steps, the script may never finish"));
+
+    // an empty loop body still counts towards the limit
+    let (_out, err) = run_with("void main() { while (true) { } }", Some(100));
+    assert_eq!(err.as_deref(), Some("Stopped after 100 steps, the script may never finish"));
+}
+
+#[test]
+fn limits_below_one_are_ignored() {
+    let (out, err) = run_with(r#"
+        infiniteRecursionLimit 0;
+        int f() {
+            return (1);
+        }
+        void main() {
+            xsChatData("%d", f());
+        }
+    "#, None);
+    assert_eq!((out.as_str(), err), ("1\n", None));
+
+    let (out, err) = run_with(r#"
+        infiniteLoopLimit 0;
+        void main() {
+            int i = 0;
+            while (i < 5) {
+                i++;
+            }
+            xsChatData("%d", i);
+        }
+    "#, None);
+    assert_eq!((out.as_str(), err), ("5\n", None));
+}
+
+#[test]
+fn vectors() {
+    assert_eq!(run(r#"
+        void main() {
+            vector v = vector(1.0, 2.0, 3.0);
+            vector w = v + v - vector(0.0, 1.0, 0.0);
+            xsChatData("%f %f %f", w.x, w.y, w.z);
+            xsChatData("" + (v * 2) + " " + (v / 2.0));
+            xsChatData("" + (v == vector(1.0, 2.0, 3.0)) + " " + (v != v));
+        }
+    "#), "\
+2.000000 3.000000 6.000000
+(2.000000, 4.000000, 6.000000) (0.500000, 1.000000, 1.500000)
+true false
+");
+}
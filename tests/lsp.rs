@@ -0,0 +1,168 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{self, Child, ChildStdin, ChildStdout, Command, Stdio};
+use serde_json::{json, Value};
+
+const MAIN: &str = "include \"lib.xs\";
+void main() {
+    float ratio = half(3.0);
+    int n = missing;
+}
+";
+
+const LIB: &str = "/// Halves `x`
+float half(float x = 0.0) {
+    return (x / 2.0);
+}
+";
+
+/// A language server talking JSON-RPC over the pipes of a child process
+struct Client {
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Client {
+    fn send(&mut self, msg: Value) {
+        let body = msg.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn request(&mut self, id: i32, method: &str, params: Value) -> Value {
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        let response = self.recv();
+        assert_eq!(response["id"], id, "{}", response);
+        response["result"].clone()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn recv(&mut self) -> Value {
+        let mut len = 0;
+        loop {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).unwrap();
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length: ") {
+                len = value.parse().unwrap();
+            }
+        }
+        let mut body = vec![0; len];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+}
+
+/// Starts a language server and initialises it
+fn start() -> (Child, Client) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_xs-check-lsp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut client = Client {
+        stdin: child.stdin.take().unwrap(),
+        stdout: BufReader::new(child.stdout.take().unwrap()),
+    };
+
+    let init = client.request(1, "initialize", json!({ "capabilities": {} }));
+    assert_eq!(init["capabilities"]["hoverProvider"], true);
+    client.notify("initialized", json!({}));
+
+    (child, client)
+}
+
+/// Asks the server to exit and waits for it to do so
+fn stop(mut child: Child, mut client: Client, id: i32) {
+    client.request(id, "shutdown", Value::Null);
+    client.notify("exit", Value::Null);
+    assert!(child.wait().unwrap().success());
+}
+
+#[cfg(unix)]
+#[test]
+fn diagnostics_and_hover_over_stdio() {
+    let (child, mut client) = start();
+
+    // the documents are opened through a symlink, and `lib.xs` is not saved to disk yet, so it can
+    // only be included if the path of the open document is canonicalised like the included path
+    let dir = std::env::temp_dir().join(format!("xs-check-lsp-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("real")).unwrap();
+    fs::write(dir.join("real/main.xs"), MAIN).unwrap();
+    std::os::unix::fs::symlink("real", dir.join("link")).unwrap();
+    let main_uri = format!("file://{}/link/main.xs", dir.display());
+    let lib_uri = format!("file://{}/link/lib.xs", dir.display());
+    for (uri, text) in [(&lib_uri, LIB), (&main_uri, MAIN)] {
+        client.notify("textDocument/didOpen", json!({
+            "textDocument": { "uri": uri, "languageId": "xs", "version": 1, "text": text }
+        }));
+    }
+
+    // opening a document publishes the diagnostics of every open document
+    let published = (0..3).map(|_| client.recv()).collect::<Vec<Value>>();
+    assert!(published.iter().all(|notif| notif["method"] == "textDocument/publishDiagnostics"));
+    let main_diags = &published.iter()
+        .rfind(|notif| notif["params"]["uri"] == main_uri.as_str())
+        .unwrap()["params"]["diagnostics"];
+    let errors = main_diags.as_array().unwrap().iter()
+        .filter(|diag| diag["severity"] == 1)
+        .map(|diag| (diag["code"].clone(), diag["range"]["start"].clone()))
+        .collect::<Vec<(Value, Value)>>();
+    assert_eq!(errors, vec![(json!("XS0003"), json!({ "line": 3, "character": 12 }))]);
+
+    let hover = |client: &mut Client, id, line, character| {
+        let result = client.request(id, "textDocument/hover", json!({
+            "textDocument": { "uri": main_uri },
+            "position": { "line": line, "character": character },
+        }));
+        result["contents"]["value"].as_str().unwrap_or_default().to_string()
+    };
+    assert_eq!(hover(&mut client, 2, 2, 12), "```xs\nfloat ratio\n```\n---\ntype: `float`");
+    assert_eq!(
+        hover(&mut client, 3, 2, 20),
+        "```xs\nfloat half(float x = 0.0)\n```\n---\ntype: `float -> float`\n\nHalves `x`",
+    );
+
+    stop(child, client, 4);
+}
+
+#[test]
+fn diagnostics_follow_the_project_file() {
+    let (child, mut client) = start();
+
+    let dir = std::env::temp_dir().join(format!("xs-check-lsp-{}-project", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("xs-check.toml"), "\
+entries = [\"src/main.xs\"]
+
+[lints]
+XS1015 = \"off\"
+XS1001 = \"error\"
+").unwrap();
+    let uri = format!("file://{}/src/main.xs", dir.display());
+    // `f` is never read
+    client.notify("textDocument/didOpen", json!({
+        "textDocument": {
+            "uri": uri,
+            "languageId": "xs",
+            "version": 1,
+            "text": "void main() {\n    float f = 1 + 1.5;\n}\n",
+        }
+    }));
+
+    let published = client.recv();
+    let diags = published["params"]["diagnostics"].as_array().unwrap().iter()
+        .map(|diag| (diag["code"].clone(), diag["severity"].clone(), diag["range"]["start"]["line"].clone()))
+        .collect::<Vec<(Value, Value, Value)>>();
+    assert_eq!(diags, vec![(json!("XS1001"), json!(1), json!(1))]);
+
+    stop(child, client, 2);
+}
@@ -0,0 +1,18 @@
+use std::collections::HashSet;
+use xs_check::api::Code;
+use xs_check::internal::r#static::module_loader::{parse_src, Parsed};
+
+#[test]
+fn parse_errors_are_reported_once() {
+    for src in ["int a = 1;\na = - -a;\n", "void f() {\n    a = - -a;\n}\n", "void f() { if (a { } }"] {
+        let Parsed { errs, .. } = parse_src(src);
+        assert!(!errs.is_empty(), "{}", src);
+        assert!(errs.iter().all(|err| err.code == Code::UnexpectedToken), "{}", src);
+
+        let mut seen = HashSet::new();
+        for err in errs.iter() {
+            let first = seen.insert((err.span, err.msg.as_str()));
+            assert!(first, "`{}` is reported twice for {:?}", err.msg, src);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use xs_check::api::{Code, Severity};
+use xs_check::internal::r#static::prelude::is_builtin_name;
+use xs_check::{check_source, Options};
+
+/// The code and message of each diagnostic reported for `src`
+fn diags(src: &str) -> Vec<(Code, String)> {
+    check_source(src, &Options::default()).diagnostics.into_iter()
+        .map(|diag| (diag.code, diag.message))
+        .collect()
+}
+
+#[test]
+fn builtin_names() {
+    assert!(is_builtin_name("xsGetTime"));
+    assert!(is_builtin_name("cAttributeFood"));
+    assert!(!is_builtin_name("xs"));
+    assert!(!is_builtin_name("xsample"));
+    assert!(!is_builtin_name("count"));
+    assert!(!is_builtin_name("c"));
+    assert!(!is_builtin_name("getTime"));
+}
+
+#[test]
+fn unknown_builtins_are_warnings() {
+    let src = "\
+void f() {
+    int a = cUnitTypeMissing;
+    xsMissingBuiltin(a, b);
+}
+";
+    assert_eq!(diags(src), vec![
+        (Code::UnknownBuiltin, "`cUnitTypeMissing` is not a builtin known to this checker".to_string()),
+        (Code::UnknownBuiltin, "`xsMissingBuiltin` is not a builtin known to this checker".to_string()),
+        (Code::UndefinedName, "Undefined name `b`".to_string()),
+    ]);
+
+    let report = check_source(src, &Options::default());
+    assert_eq!(report.diagnostics[0].severity, Severity::Warning);
+}
+
+#[test]
+fn other_names_are_undefined() {
+    assert_eq!(diags("void f() {\n    int a = count;\n    getCount(a);\n}\n"), vec![
+        (Code::UndefinedName, "Undefined name `count`".to_string()),
+        (Code::UndefinedName, "Undefined name `getCount`".to_string()),
+    ]);
+}
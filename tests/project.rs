@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use xs_check::api::{Code, LintLevel, PreludeVersion};
+use xs_check::project::{Project, CONFIG_FILE};
+use xs_check::check_project;
+
+/// An empty directory for the files of the test `name`
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xs-check-project-{}-{}", process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes `config` as the project file of a new directory for the test `name` and loads it
+fn load(name: &str, config: &str) -> (PathBuf, Result<Project, String>) {
+    let dir = temp_dir(name);
+    fs::write(dir.join(CONFIG_FILE), config).unwrap();
+    let project = Project::load(&dir.join(CONFIG_FILE)).map_err(|err| err.msg);
+    (dir, project)
+}
+
+#[test]
+fn entries_and_include_dirs_are_relative_to_the_project_file() {
+    let (dir, project) = load("relative", "\
+entries = [\"main.xs\", \"campaign/scenario1.xs\"]
+include-dirs = [\"lib\"]
+");
+    let project = project.unwrap();
+
+    assert_eq!(project.path, dir.join(CONFIG_FILE));
+    assert_eq!(project.entries, vec![dir.join("main.xs"), dir.join("campaign/scenario1.xs")]);
+    assert_eq!(project.options.search_roots, vec![dir.join("lib")]);
+    assert_eq!(project.options.prelude, PreludeVersion::LATEST);
+    assert!(project.options.lints.is_empty());
+}
+
+#[test]
+fn entries_must_be_given() {
+    let (_dir, project) = load("no_entries", "entries = []");
+    assert_eq!(project, Err("`entries` must name at least one script".to_string()));
+    assert!(load("missing_entries", "include-dirs = [\"lib\"]").1.is_err());
+}
+
+#[test]
+fn prelude_versions() {
+    for (name, version) in [("latest", PreludeVersion::LATEST), ("v1", PreludeVersion::V1)] {
+        let config = format!("entries = [\"main.xs\"]\nprelude = \"{}\"", name);
+        let (_dir, project) = load(&format!("prelude_{}", name), &config);
+        assert_eq!(project.unwrap().options.prelude, version);
+    }
+
+    let (_dir, project) = load("prelude_unknown", "entries = [\"main.xs\"]\nprelude = \"v2\"");
+    assert_eq!(project, Err("unknown prelude version `v2`, expected `latest` or `v1`".to_string()));
+}
+
+#[test]
+fn lints_configure_warnings() {
+    let (_dir, project) = load("lints", "\
+entries = [\"main.xs\"]
+
+[lints]
+XS1001 = \"error\"
+XS1002 = \"off\"
+");
+    assert_eq!(project.unwrap().options.lints, HashMap::from([
+        (Code::IntFirstArith, LintLevel::Error),
+        (Code::FloatDowncast, LintLevel::Off),
+    ]));
+}
+
+#[test]
+fn unknown_lints_are_rejected() {
+    let (_dir, project) = load("unknown_lint", "entries = [\"main.xs\"]\n\n[lints]\nXS9999 = \"off\"");
+    assert_eq!(project, Err("unknown lint `XS9999`".to_string()));
+}
+
+#[test]
+fn errors_cannot_be_configured() {
+    let (_dir, project) = load("error_lint", "entries = [\"main.xs\"]\n\n[lints]\nXS0005 = \"off\"");
+    assert_eq!(project, Err("`XS0005` is an error, only warnings can be configured".to_string()));
+}
+
+#[test]
+fn unknown_fields_are_rejected() {
+    assert!(load("unknown_field", "entries = [\"main.xs\"]\nsearch-roots = [\"lib\"]").1.is_err());
+}
+
+#[test]
+fn files_shared_by_entries_are_checked_once() {
+    let (dir, project) = load("shared", "entries = [\"a.xs\", \"b.xs\"]\ninclude-dirs = [\"lib\"]");
+    fs::create_dir_all(dir.join("lib")).unwrap();
+    fs::write(dir.join("a.xs"), "include \"shared.xs\";").unwrap();
+    fs::write(dir.join("b.xs"), "include \"shared.xs\";").unwrap();
+    fs::write(dir.join("lib/shared.xs"), "int f() {\n    return (x);\n}\n").unwrap();
+
+    let report = check_project(&project.unwrap()).unwrap();
+
+    assert_eq!(report.files, vec![dir.join("a.xs"), dir.join("lib/shared.xs"), dir.join("b.xs")]);
+    let diags = report.diagnostics.iter()
+        .map(|diag| (diag.code, diag.location.file.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(diags, vec![(Code::UndefinedName, dir.join("lib/shared.xs"))]);
+}
+
+#[test]
+fn an_entry_which_cannot_be_read_is_an_error() {
+    let (dir, project) = load("missing_entry", "entries = [\"a.xs\", \"missing.xs\"]");
+    fs::write(dir.join("a.xs"), "").unwrap();
+
+    let err = check_project(&project.unwrap()).unwrap_err();
+
+    assert_eq!(err.path, dir.join(CONFIG_FILE));
+    assert!(err.msg.starts_with("cannot read entry `"), "{}", err.msg);
+}
@@ -0,0 +1,117 @@
+use xs_check::api::Code;
+use xs_check::{check_source, Diagnostic, Options};
+
+/// The UnreachableCode and UnusedLabel diagnostics reported for `src`
+fn reachability(src: &str) -> Vec<Diagnostic> {
+    check_source(src, &Options::default()).diagnostics.into_iter()
+        .filter(|diag| matches!(diag.code, Code::UnreachableCode | Code::UnusedLabel))
+        .collect()
+}
+
+/// The code, message and line of each diagnostic
+fn summary(diags: &[Diagnostic]) -> Vec<(Code, &str, usize)> {
+    diags.iter().map(|diag| (diag.code, diag.message.as_str(), diag.location.start.line)).collect()
+}
+
+#[test]
+fn code_after_a_jump_is_reported_once() {
+    let diags = reachability("\
+int f(int a = 0) {
+    while (a > 0) {
+        break;
+        a = 1;
+        a = 2;
+    }
+    return (a);
+    a = 3;
+}
+");
+    assert_eq!(summary(&diags), vec![
+        (Code::UnreachableCode, "This code is never run", 4),
+        (Code::UnreachableCode, "This code is never run", 8),
+    ]);
+    assert!(diags[0].related.is_empty());
+}
+
+#[test]
+fn code_behind_a_literal_condition_points_at_the_condition() {
+    let src = "\
+void f() {
+    if (false) {
+        xsChatData(\"never\");
+    }
+    while (true) {
+    }
+    xsChatData(\"never either\");
+}
+";
+    let diags = reachability(src);
+    assert_eq!(summary(&diags), vec![
+        (Code::UnreachableCode, "This code is never run as the condition is always `false`", 3),
+        (Code::UnreachableCode, "This code is never run as the condition is always `true`", 7),
+    ]);
+
+    let related = diags.iter()
+        .map(|diag| (diag.related[0].0.as_str(), &src[diag.related[0].1.span.clone()]))
+        .collect::<Vec<_>>();
+    assert_eq!(related, vec![
+        ("this condition is always `false`", "false"),
+        ("this condition is always `true`", "true"),
+    ]);
+}
+
+#[test]
+fn code_after_a_goto_is_reachable_through_its_label() {
+    assert_eq!(summary(&reachability("\
+void f(int a = 0) {
+    goto skip;
+    xsChatData(\"skipped\");
+    label skip;
+    xsChatData(\"run\");
+}
+")), vec![
+        (Code::UnreachableCode, "This code is never run", 3),
+    ]);
+}
+
+#[test]
+fn labels_without_a_goto_are_unused() {
+    let diags = reachability("\
+void f() {
+    label used;
+    label unused;
+    if (xsGetTime() < 10) {
+        goto used;
+    }
+}
+");
+    assert_eq!(summary(&diags), vec![(Code::UnusedLabel, "No `goto` jumps to label `unused`", 3)]);
+    assert_eq!(diags[0].location.span, 37..43);
+}
+
+#[test]
+fn dead_loops_and_branches_are_reported_as_a_whole() {
+    let src = "\
+int f(int a = 0) {
+    return (a);
+    for (i = 0; < a) {
+        a = a + i;
+    }
+}
+int g(int a = 0) {
+    return (a);
+    if (a > 0) {
+        a = 1;
+    }
+}
+";
+    let diags = reachability(src);
+    assert_eq!(summary(&diags), vec![
+        (Code::UnreachableCode, "This code is never run", 3),
+        (Code::UnreachableCode, "This code is never run", 9),
+    ]);
+
+    let reported = diags.iter().map(|diag| &src[diag.location.span.clone()]).collect::<Vec<_>>();
+    assert!(reported[0].starts_with("for (i = 0; < a)"), "{}", reported[0]);
+    assert!(reported[1].starts_with("if (a > 0)"), "{}", reported[1]);
+}
@@ -0,0 +1,115 @@
+use xs_check::api::Code;
+use xs_check::{check_source, Options};
+
+/// Asserts the line of each MissingReturn reported for `src`
+fn assert_missing_returns(src: &str, lines: &[usize]) {
+    let actual = check_source(src, &Options::default()).diagnostics.iter()
+        .filter(|diag| diag.code == Code::MissingReturn)
+        .map(|diag| diag.location.start.line)
+        .collect::<Vec<usize>>();
+    assert_eq!(actual, lines, "{}", src);
+}
+
+#[test]
+fn if_without_else_may_fall_off_the_end() {
+    assert_missing_returns("\
+int f(int a = 0) {
+    if (a > 0) {
+        return (1);
+    }
+}
+", &[5]);
+    assert_missing_returns("\
+int f(int a = 0) {
+    if (a > 0) {
+        return (1);
+    } else {
+        return (0);
+    }
+}
+", &[]);
+}
+
+#[test]
+fn message_names_the_function_and_its_return_type() {
+    let report = check_source("float f() {\n}\n", &Options::default());
+    let diag = report.diagnostics.iter().find(|diag| diag.code == Code::MissingReturn).unwrap();
+
+    assert_eq!(diag.message, "Not all paths of `f` return a value");
+    assert_eq!(diag.location.span, 12..13);
+    assert_eq!(diag.related[0].0, "`f` returns `float`");
+    assert_eq!(diag.related[0].1.span, 6..7);
+}
+
+#[test]
+fn while_true_only_falls_off_the_end_when_it_breaks() {
+    assert_missing_returns("\
+int f() {
+    while (true) {
+        return (1);
+    }
+}
+", &[]);
+    assert_missing_returns("\
+int f(int a = 0) {
+    while (true) {
+        if (a > 0) {
+            break;
+        }
+    }
+}
+", &[7]);
+}
+
+#[test]
+fn switch_returns_on_every_path_only_with_a_default() {
+    assert_missing_returns("\
+int f(int a = 0) {
+    switch (a) {
+        case 0 : { return (1); }
+        default : { return (2); }
+    }
+}
+", &[]);
+    assert_missing_returns("\
+int f(int a = 0) {
+    switch (a) {
+        case 0 : { return (1); }
+        case 1 : { return (2); }
+    }
+}
+", &[6]);
+    // a case which breaks leaves the switch
+    assert_missing_returns("\
+int f(int a = 0) {
+    switch (a) {
+        case 0 : { break; }
+        default : { return (2); }
+    }
+}
+", &[6]);
+}
+
+#[test]
+fn for_loop_may_not_run_at_all() {
+    assert_missing_returns("\
+int f(int n = 0) {
+    for (i = 0; < n) {
+        return (i);
+    }
+}
+", &[5]);
+    assert_missing_returns("\
+int f(int n = 0) {
+    for (i = 0; < n) {
+        return (i);
+    }
+    return (0);
+}
+", &[]);
+}
+
+#[test]
+fn void_functions_need_no_return() {
+    assert_missing_returns("void f(int a = 0) {\n    if (a > 0) {\n        return;\n    }\n}\n", &[]);
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+use xs_check::internal::dynamic::interpreter::Interpreter;
+use xs_check::internal::dynamic::scheduler::SchedulerConfig;
+use xs_check::internal::r#static::module_loader::ModuleLoader;
+use xs_check::internal::r#static::prelude::PreludeVersion;
+
+/// The time in milliseconds and name of each rule run when the rules of `src` are simulated for
+/// `duration` milliseconds in ticks of `tick` milliseconds
+fn simulate(src: &str, duration: i32, tick: i32) -> Vec<(i32, String)> {
+    let path = PathBuf::from("test.xs");
+    let mut loader = ModuleLoader::new(vec![]);
+    loader.set_source(&path, src.to_string());
+    let module = loader.load(&path).unwrap();
+    assert!(module.errs.is_empty(), "the script does not parse: {:?}", module.errs);
+
+    let mut out = vec![];
+    let mut interpreter = Interpreter::new(PreludeVersion::LATEST, &mut out);
+    let cfg = SchedulerConfig { tick, ..SchedulerConfig::default() };
+    let firings = interpreter.load(module, &mut loader)
+        .and_then(|_| interpreter.simulate(duration, &cfg))
+        .unwrap();
+
+    firings.into_iter().map(|firing| (firing.time, firing.rule.0)).collect()
+}
+
+#[test]
+fn rules_wait_for_their_min_interval() {
+    assert_eq!(simulate("rule a active minInterval 2 { }", 5_000, 50), vec![
+        (2_000, "a".to_string()),
+        (4_000, "a".to_string()),
+    ]);
+}
+
+#[test]
+fn intervals_too_long_for_the_clock_never_pass() {
+    // 3000000 seconds do not fit in an `int` of milliseconds
+    assert_eq!(simulate("rule a active minInterval 3000000 { }", 1_000, 50), vec![]);
+    assert_eq!(simulate("rule a active maxInterval 3000000 { }", 1_000, 50), vec![]);
+
+    // the third run would be due after the clock runs out
+    let fired = simulate("rule a active runImmediately minInterval 1000000 { }", i32::MAX, 100_000_000);
+    assert_eq!(fired.iter().map(|(time, _rule)| *time).collect::<Vec<i32>>(), vec![
+        0, 1_000_000_000, 2_000_000_000,
+    ]);
+}
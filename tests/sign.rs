@@ -0,0 +1,139 @@
+use xs_check::api::Code;
+use xs_check::{check_source, Diagnostic, Options};
+
+/// The DivByZero, NegativeArraySize and ArrayIndexOutOfRange diagnostics reported for `src`
+fn signs(src: &str) -> Vec<Diagnostic> {
+    check_source(src, &Options::default()).diagnostics.into_iter()
+        .filter(|diag| {
+            matches!(diag.code, Code::DivByZero | Code::NegativeArraySize | Code::ArrayIndexOutOfRange)
+        })
+        .collect()
+}
+
+/// Asserts the code, message and line of each diagnostic reported for `src`
+fn assert_signs(src: &str, expected: &[(Code, &str, usize)]) {
+    let diags = signs(src);
+    let actual = diags.iter()
+        .map(|diag| (diag.code, diag.message.as_str(), diag.location.start.line))
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected, "{}", src);
+}
+
+#[test]
+fn division_by_zero() {
+    assert_signs("\
+int f() {
+    int zero = 0;
+    int a = 10 / zero;
+    float b = 1.0 % (zero * 2);
+    return (a + b);
+}
+", &[
+        (Code::DivByZero, "This divides by zero", 3),
+        (Code::DivByZero, "This divides by zero", 4),
+    ]);
+}
+
+#[test]
+fn division_which_may_be_by_zero() {
+    let src = "\
+int f(int a = 0) {
+    int d = 2;
+    if (a > 0) {
+        d = -1;
+    }
+    return (10 / (d + 1));
+}
+";
+    assert_signs(src, &[(Code::DivByZero, "This may divide by zero", 6)]);
+
+    let diags = signs(src);
+    assert_eq!(diags[0].related[0].0, "this is between `0` and `3`");
+    assert_eq!(&src[diags[0].related[0].1.span.clone()], "(d + 1)");
+}
+
+#[test]
+fn float_divisor_of_an_int_is_truncated() {
+    let diags = signs("int f() {\n    int a = 7;\n    return (a / 0.5);\n}\n");
+    assert_eq!(diags[0].message, "This divides by zero");
+    let note = "The divisor is converted to an `int` first, as the dividend is an `int`";
+    assert_eq!(diags[0].notes, vec![note]);
+
+    assert_signs("int f() {\n    int a = 7;\n    return (a / 2.5);\n}\n", &[]);
+}
+
+#[test]
+fn loop_counters_are_refined_by_their_condition() {
+    assert_signs("\
+int f() {
+    int total = 0;
+    for (i = 10; > 0) {
+        total = total + 100 / i;
+    }
+    return (total);
+}
+", &[]);
+    assert_signs("\
+int f() {
+    int total = 0;
+    for (i = 10; >= 0) {
+        total = total + 100 / i;
+    }
+    return (total);
+}
+", &[(Code::DivByZero, "This may divide by zero", 4)]);
+}
+
+#[test]
+fn statics_and_params_may_hold_any_value() {
+    assert_signs("\
+int f(int a = 0) {
+    static int calls = 0;
+    calls++;
+    return (100 / a + 100 / calls);
+}
+", &[]);
+}
+
+#[test]
+fn negative_array_sizes() {
+    assert_signs("\
+void f(int a = 0) {
+    int n = -2;
+    int arr1 = xsArrayCreateInt(n, 0, \"arr1\");
+    if (a > 0) {
+        n = 4;
+    }
+    int arr2 = xsArrayCreateInt(n, 0, \"arr2\");
+    int arr3 = xsArrayCreateInt(a, 0, \"arr3\");
+}
+", &[
+        (Code::NegativeArraySize, "This array is created with a negative size `-2`", 3),
+        (
+            Code::NegativeArraySize,
+            "This array may be created with a negative size, its size is between `-2` and `4`",
+            7,
+        ),
+    ]);
+}
+
+#[test]
+fn array_indices_out_of_range() {
+    let src = "\
+int f(int a = 0) {
+    int arr = xsArrayCreateInt(3, 0, \"arr\");
+    xsArraySetInt(arr, -1, 5);
+    xsArraySetInt(arr, 2, 5);
+    xsArraySetInt(arr, a, 5);
+    return (xsArrayGetInt(arr, 3) + xsArrayGetSize(arr));
+}
+";
+    assert_signs(src, &[
+        (Code::ArrayIndexOutOfRange, "This index is negative, it is `-1`", 3),
+        (Code::ArrayIndexOutOfRange, "This index is out of range, it is `3`", 6),
+    ]);
+
+    let diags = signs(src);
+    assert_eq!(diags[1].related[0].0, "the size of this array is `3`");
+    assert_eq!(&src[diags[1].related[0].1.span.clone()], "arr");
+}
@@ -0,0 +1,112 @@
+use xs_check::api::Code;
+use xs_check::{check_source, Options};
+
+/// Asserts the message and line of each UninitRead reported for `src`
+fn assert_uninit(src: &str, expected: &[(&str, usize)]) {
+    let report = check_source(src, &Options::default());
+    let actual = report.diagnostics.iter()
+        .filter(|diag| diag.code == Code::UninitRead)
+        .map(|diag| (diag.message.as_str(), diag.location.start.line))
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected, "{}", src);
+}
+
+#[test]
+fn assigned_on_one_branch_only() {
+    assert_uninit("\
+int f(int a = 0) {
+    int x;
+    if (a > 0) {
+        x = 1;
+    }
+    return (x);
+}
+", &[("`x` may be read before it is assigned a value", 6)]);
+    assert_uninit("\
+int f(int a = 0) {
+    int x;
+    if (a > 0) {
+        x = 1;
+    } else {
+        x = 2;
+    }
+    return (x);
+}
+", &[]);
+}
+
+#[test]
+fn related_span_points_at_the_declaration() {
+    let src = "int f() {\n    int x;\n    return (x);\n}\n";
+    let report = check_source(src, &Options::default());
+    let diag = report.diagnostics.iter().find(|diag| diag.code == Code::UninitRead).unwrap();
+
+    assert_eq!(&src[diag.location.span.clone()], "x");
+    assert_eq!(diag.related[0].0, "`x` is declared here without a value");
+    assert_eq!(diag.related[0].1.start.line, 2);
+    assert_eq!(diag.notes, vec!["Until it is assigned, a variable holds the default value of its type"]);
+}
+
+#[test]
+fn assigned_inside_a_loop_body() {
+    // the body may not run, but a read inside it after the assignment is fine
+    assert_uninit("\
+int f(int n = 0) {
+    int x;
+    for (i = 0; < n) {
+        x = i;
+        xsChatData(\"%d\", x);
+    }
+    return (x);
+}
+", &[("`x` may be read before it is assigned a value", 7)]);
+    // a read before the assignment sees the value of the previous iteration, or none at all
+    assert_uninit("\
+void f(int n = 0) {
+    int x;
+    while (n > 0) {
+        xsChatData(\"%d\", x);
+        x = n;
+        n--;
+    }
+}
+", &[("`x` may be read before it is assigned a value", 4)]);
+}
+
+#[test]
+fn reads_after_a_goto() {
+    // the `goto` jumps over the assignment
+    assert_uninit("\
+int f(int a = 0) {
+    int x;
+    if (a > 0) {
+        goto done;
+    }
+    x = 1;
+    label done;
+    return (x);
+}
+", &[("`x` may be read before it is assigned a value", 8)]);
+    assert_uninit("\
+int f() {
+    int x;
+    x = 1;
+    goto done;
+    x = 2;
+    label done;
+    return (x);
+}
+", &[]);
+}
+
+#[test]
+fn statics_and_declarations_with_a_value_are_not_reported() {
+    assert_uninit("\
+int f() {
+    static int calls;
+    int x = 0;
+    calls++;
+    return (calls + x);
+}
+", &[]);
+}
@@ -0,0 +1,96 @@
+use xs_check::api::Code;
+use xs_check::{check_source, Options};
+
+/// Asserts the code, message and line of each UnusedVariable, DeadStore and UnusedParam reported
+/// for `src`
+fn assert_unused(src: &str, expected: &[(Code, &str, usize)]) {
+    let report = check_source(src, &Options::default());
+    let actual = report.diagnostics.iter()
+        .filter(|diag| matches!(diag.code, Code::UnusedVariable | Code::DeadStore | Code::UnusedParam))
+        .map(|diag| (diag.code, diag.message.as_str(), diag.location.start.line))
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected, "{}", src);
+}
+
+#[test]
+fn variables_and_params_which_are_never_read() {
+    assert_unused("\
+int f(int a = 0, int b = 0) {
+    int x = 1;
+    int y = 2;
+    x = 3;
+    return (y + b);
+}
+", &[
+        (Code::UnusedParam, "Parameter `a` is never read", 1),
+        (Code::UnusedVariable, "Variable `x` is never read", 2),
+    ]);
+}
+
+#[test]
+fn names_starting_with_an_underscore_are_not_reported() {
+    assert_unused("\
+int f(int _a = 0) {
+    int _x = 1;
+    int y = 2;
+    _x = 3;
+    y = 4;
+    return (y);
+}
+", &[
+        (Code::DeadStore, "The value assigned to `y` is never read", 3),
+    ]);
+}
+
+#[test]
+fn params_of_mutable_functions_are_not_reported() {
+    assert_unused("\
+mutable int f(int a = 0) {
+    int x = 1;
+    return (0);
+}
+", &[
+        (Code::UnusedVariable, "Variable `x` is never read", 2),
+    ]);
+}
+
+#[test]
+fn stores_to_statics_are_never_dead() {
+    assert_unused("\
+int f() {
+    static int calls = 0;
+    calls = calls + 1;
+    calls = 5;
+    return (0);
+}
+", &[]);
+}
+
+#[test]
+fn dead_stores_take_loops_into_account() {
+    // the value assigned at the end of the body is read by the next iteration
+    assert_unused("\
+int f() {
+    int total = 0;
+    int last = 0;
+    for (i = 0; < 10) {
+        total = total + last;
+        last = i;
+    }
+    return (total);
+}
+", &[]);
+    assert_unused("\
+int f() {
+    // read when the loop does not run
+    int x = 0;
+    for (i = 0; < 10) {
+        x = i;
+        x = i * 2;
+    }
+    return (x);
+}
+", &[
+        (Code::DeadStore, "The value assigned to `x` is never read", 5),
+    ]);
+}
@@ -0,0 +1,97 @@
+use xs_check::api::Code;
+use xs_check::{check_source, Options};
+
+/// The code and message of each diagnostic reported for `src`
+fn diags(src: &str) -> Vec<(Code, String)> {
+    check_source(src, &Options::default()).diagnostics.into_iter()
+        .map(|diag| (diag.code, diag.message))
+        .collect()
+}
+
+#[test]
+fn members_are_floats() {
+    assert_eq!(diags("\
+float f() {
+    vector v = vector(1.0, 2.0, 3.0);
+    return (v.x + v.y * v.z);
+}
+"), vec![]);
+    assert_eq!(diags("\
+string f() {
+    vector v = vector(1.0, 2.0, 3.0);
+    return (v.y);
+}
+"), vec![(Code::TypeMismatch, "Expected `string` found `float`".to_string())]);
+}
+
+#[test]
+fn only_vectors_have_members() {
+    let src = "\
+float f() {
+    vector v = vector(1.0, 2.0, 3.0);
+    int i = 1;
+    return (v.w + i.x);
+}
+";
+    assert_eq!(diags(src), vec![
+        (Code::InvalidMember, "A `vector` only has the members `x`, `y` and `z`".to_string()),
+        (Code::InvalidMember, "Type `int` has no members".to_string()),
+    ]);
+
+    let report = check_source(src, &Options::default());
+    let spans = report.diagnostics.iter()
+        .map(|diag| &src[diag.location.span.clone()])
+        .collect::<Vec<&str>>();
+    assert_eq!(spans, vec!["w", "x"]);
+}
+
+#[test]
+fn vectors_are_added_and_scaled() {
+    assert_eq!(diags("\
+vector f() {
+    vector v = vector(1.0, 2.0, 3.0);
+    vector w = v + v - vector(0.0, 1.0, 0.0);
+    return (w * 2 / 0.5);
+}
+"), vec![]);
+
+    let report = check_source("\
+vector f() {
+    vector v = vector(1.0, 2.0, 3.0);
+    return (2.0 * v);
+}
+", &Options::default());
+    let diag = &report.diagnostics[0];
+    assert_eq!(diag.code, Code::InvalidArithOperands);
+    assert_eq!(diag.message, "Cannot multiply types `float` and `vector`");
+    assert_eq!(diag.notes, vec![
+        "The type of an arithmetic operation is the type of its first operand, write the `vector` first to \
+        scale it",
+    ]);
+
+    assert_eq!(diags("\
+vector f() {
+    vector v = vector(1.0, 2.0, 3.0);
+    return (v * v);
+}
+"), vec![(Code::InvalidArithOperands, "Cannot multiply types `vector` and `vector`".to_string())]);
+}
+
+#[test]
+fn vectors_and_bools_may_only_be_compared_for_equality() {
+    assert_eq!(diags("\
+bool f(bool a = true) {
+    vector v = vector(1.0, 2.0, 3.0);
+    return ((v == v) == (v != v) && a == false);
+}
+"), vec![]);
+    assert_eq!(diags("\
+bool f(bool a = true) {
+    vector v = vector(1.0, 2.0, 3.0);
+    return ((v < v) && (a >= true));
+}
+"), vec![
+        (Code::CrashingComparison, "This comparison will cause a silent XS crash!".to_string()),
+        (Code::CrashingComparison, "This comparison will cause a silent XS crash!".to_string()),
+    ]);
+}
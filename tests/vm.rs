@@ -0,0 +1,284 @@
+use std::path::{Path, PathBuf};
+use xs_check::internal::codegen::bc::lower::lower_program;
+use xs_check::internal::codegen::pa::munch::lower_module;
+use xs_check::internal::dynamic::interpreter::Interpreter;
+use xs_check::internal::dynamic::scheduler::{Firing, SchedulerConfig};
+use xs_check::internal::r#static::module_loader::ModuleLoader;
+use xs_check::internal::r#static::prelude::{prelude, PreludeVersion};
+use xs_check::internal::vm::machine::Vm;
+
+/// How long the rules of a script are simulated for, in milliseconds
+const SIMULATE: i32 = 20_000;
+
+/// What a script printed, the rules it ran and the error it stopped with, if any
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    out: String,
+    firings: Vec<Firing>,
+    err: Option<String>,
+}
+
+fn cfg() -> SchedulerConfig {
+    SchedulerConfig { trace: true, ..SchedulerConfig::default() }
+}
+
+fn interpret(loader: &mut ModuleLoader, path: &Path) -> Outcome {
+    let module = loader.load(path).expect("the script can be read");
+    let mut out = vec![];
+    let mut firings = vec![];
+    let mut interpreter = Interpreter::new(PreludeVersion::LATEST, &mut out);
+    let result = interpreter.load(module, loader)
+        .and_then(|_| match interpreter.is_callable("main") {
+            true => interpreter.call("main", vec![]).map(|_| ()),
+            false => Ok(()),
+        })
+        .and_then(|_| interpreter.simulate(SIMULATE, &cfg()))
+        .map(|fired| firings = fired);
+    let err = result.err().map(|err| err.msg);
+
+    Outcome { out: String::from_utf8(out).unwrap(), firings, err }
+}
+
+fn run_vm(loader: &mut ModuleLoader, path: &Path) -> Outcome {
+    let module = loader.load(path).expect("the script can be read");
+    let bc = lower_program(&lower_module(&module, loader, &prelude(PreludeVersion::LATEST)));
+    let mut out = vec![];
+    let mut firings = vec![];
+    let mut host = Interpreter::new(PreludeVersion::LATEST, &mut out);
+    let mut vm = Vm::new(&bc, &mut host, PreludeVersion::LATEST);
+    let result = vm.load()
+        .and_then(|_| match vm.is_callable("main") {
+            true => vm.call("main", vec![]).map(|_| ()),
+            false => Ok(()),
+        })
+        .and_then(|_| vm.simulate(SIMULATE, &cfg()))
+        .map(|fired| firings = fired);
+    let err = result.err().map(|err| err.msg);
+    drop(vm);
+    drop(host);
+
+    Outcome { out: String::from_utf8(out).unwrap(), firings, err }
+}
+
+fn assert_same(loader: &mut ModuleLoader, path: &Path) {
+    let expected = interpret(loader, path);
+    let actual = run_vm(loader, path);
+    assert_eq!(actual, expected, "`{}` behaves differently on the VM", path.display());
+}
+
+fn assert_same_src(src: &str) {
+    let path = PathBuf::from("test.xs");
+    let mut loader = ModuleLoader::new(vec![]);
+    loader.set_source(&path, src.to_string());
+    assert!(loader.load(&path).unwrap().errs.is_empty(), "the script does not parse");
+    assert_same(&mut loader, &path);
+}
+
+#[test]
+fn test_xs_runs_the_same() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_xs");
+    let mut loader = ModuleLoader::new(vec![dir.clone()]);
+    for entry in dir.read_dir().unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "xs") {
+            assert_same(&mut loader, &path);
+        }
+    }
+}
+
+#[test]
+fn functions_and_defaults() {
+    assert_same_src(r#"
+        int add(int a = 1, int b = 2) {
+            return (a + b);
+        }
+        float half(float x = 3.0) {
+            return (x / 2.0);
+        }
+        string greet(string name = "world") {
+            return ("hello " + name);
+        }
+        void main() {
+            xsChatData("%d %d %d", add(), add(5), add(5, 6));
+            xsChatData("%f %f", half(), half(5.0));
+            xsChatData(greet() + ", " + greet("there"));
+            vector v = vector(1.0, 2.0, 3.0);
+            xsChatData("" + v + " " + v.y);
+            xsChatData("" + (7 / 2) + " " + (7 % 3) + " " + (-7 / 2.0) + " " + (7 > 2) + " " + (1 == 1.0));
+        }
+    "#);
+}
+
+#[test]
+fn loops_and_switches() {
+    assert_same_src(r#"
+        void main() {
+            int total = 0;
+            int i = 0;
+            while (i < 10) {
+                i++;
+                if (i % 3 == 0) {
+                    continue;
+                }
+                total = total + i;
+            }
+            for (j = 10; > 0) {
+                total = total - 1;
+                if (total < 20) {
+                    break;
+                }
+            }
+            int n = 0;
+            while (n < 5) {
+                n++;
+            }
+            for (k = 0; < 4) {
+                switch (k) {
+                    case 0 : { xsChatData("zero"); }
+                    case 1 : { xsChatData("one"); break; }
+                    default : { xsChatData("many %d", k); }
+                }
+            }
+            xsChatData("%d %d", total, n);
+        }
+    "#);
+}
+
+#[test]
+fn statics_globals_and_recursion() {
+    assert_same_src(r#"
+        int counter = 10;
+        const float RATE = 1.5;
+
+        int count() {
+            static int calls = 0;
+            calls++;
+            counter = counter + 1;
+            return (calls);
+        }
+
+        int fib(int n = 0) {
+            if (n < 2) {
+                return (n);
+            }
+            return (fib(n - 1) + fib(n - 2));
+        }
+
+        void main() {
+            count();
+            count();
+            xsChatData("%d %d %f %d", count(), counter, RATE, fib(15));
+            xsChatData("%d", cAttributeStone);
+        }
+    "#);
+}
+
+#[test]
+fn arrays_and_random_numbers() {
+    assert_same_src(r#"
+        void main() {
+            int arr = xsArrayCreateInt(5, 7, "arr");
+            for (i = 0; < 5) {
+                xsArraySetInt(arr, i, i * xsGetRandomNumberMax(100));
+            }
+            for (i = 0; < xsArrayGetSize(arr)) {
+                xsChatData("%d", xsArrayGetInt(arr, i));
+            }
+            xsChatData("%d", xsArrayGetInt(arr, 9));
+        }
+    "#);
+}
+
+#[test]
+fn rules_are_scheduled_the_same() {
+    assert_same_src(r#"
+        int fired = 0;
+
+        rule often active highFrequency priority 10 group "g" {
+            fired++;
+            if (fired == 50) {
+                xsDisableSelf();
+                xsEnableRule("later");
+            }
+        }
+
+        rule later minInterval 2 maxInterval 4 {
+            xsChatData("later at %d, %d", xsGetTime(), fired);
+            xsSetRuleMinIntervalSelf(1);
+            if (xsIsRuleGroupEnabled("g") == false) {
+                xsEnableRuleGroup("g");
+            }
+        }
+
+        rule rare active runImmediately minInterval 5 group "g" {
+            xsChatData("rare at %d", xsGetGameTime());
+            xsSetRulePriority("later", 20);
+        }
+    "#);
+}
+
+#[test]
+fn intervals_too_long_for_the_clock_are_scheduled_the_same() {
+    // 3000000 seconds do not fit in an `int` of milliseconds
+    assert_same_src(r#"
+        rule a active minInterval 3000000 { xsChatData("a"); }
+        rule b active maxInterval 3000000 { xsSetRuleMaxIntervalSelf(2147483); }
+        rule c active highFrequency { xsSetRuleMinInterval("a", 2147483); xsDisableSelf(); }
+    "#);
+}
+
+#[test]
+fn errors_stop_the_script_the_same() {
+    assert_same_src(r#"
+        infiniteRecursionLimit 40;
+        int f(int n = 0) {
+            return (f(n + 1));
+        }
+        void main() {
+            f();
+        }
+    "#);
+    assert_same_src(r#"
+        infiniteRecursionLimit 20;
+        int f(int n = 0) {
+            if (n == 10) {
+                return (n);
+            }
+            return (f(n + 1));
+        }
+        void main() {
+            xsChatData("%d", f());
+            f(-100);
+        }
+    "#);
+    // `continue` skips the step of a `for` loop
+    assert_same_src(r#"
+        infiniteLoopLimit 100;
+        void main() {
+            for (i = 0; < 10) {
+                if (i == 3) {
+                    continue;
+                }
+                xsChatData("%d", i);
+            }
+        }
+    "#);
+    assert_same_src(r#"
+        void main() {
+            xsChatData("before");
+            xsEnableRule("missing");
+            xsChatData("after");
+        }
+    "#);
+    assert_same_src(r#"
+        void main() {
+            xsDisableSelf();
+        }
+    "#);
+    assert_same_src(r#"
+        void main() {
+            int arr = xsArrayCreateInt(2, 0, "arr");
+            xsArraySetInt(arr, 5, 1);
+        }
+    "#);
+}